@@ -380,7 +380,7 @@ impl ModuleContext {
         // - for exported procedures: hash("module_path::proc_name")
         // - for internal procedures: hash("module_path::proc_index")
         let proc_id = if proc_context.is_export {
-            ProcedureId::from_name(&proc_context.name, &self.path)
+            proc_context.name.to_id(&self.path)
         } else {
             let proc_idx = self.compiled_procs.len() as u16;
             ProcedureId::from_index(proc_idx, &self.path)
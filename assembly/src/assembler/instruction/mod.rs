@@ -301,6 +301,7 @@ impl Assembler {
             // ----- exec/call instructions -------------------------------------------------------
             Instruction::ExecLocal(idx) => self.exec_local(*idx, ctx),
             Instruction::ExecImported(id) => self.exec_imported(id, ctx),
+            Instruction::ExecMastRoot(root) => self.exec_mast_root(root, ctx),
             Instruction::CallLocal(idx) => self.call_local(*idx, ctx),
             Instruction::CallMastRoot(root) => self.call_mast_root(root, ctx),
             Instruction::CallImported(id) => self.call_imported(id, ctx),
@@ -45,6 +45,27 @@ impl Assembler {
         Ok(Some(proc.code_root().clone()))
     }
 
+    pub(super) fn exec_mast_root(
+        &self,
+        root: &RpoDigest,
+        context: &mut AssemblyContext,
+    ) -> Result<Option<CodeBlock>, AssemblyError> {
+        // get the procedure from the assembler; as with `call`, the procedure must have been
+        // loaded into the procedure cache previously
+        let proc_cache = self.proc_cache.borrow();
+        let proc = proc_cache
+            .get_by_hash(root)
+            .ok_or(AssemblyError::proc_mast_root_not_found(root))?;
+        debug_assert!(proc.is_export(), "not imported procedure");
+
+        // register and "inlined" call to the procedure; this updates the callset of the
+        // procedure currently being compiled
+        context.register_external_call(proc, true)?;
+
+        // return the code block of the procedure
+        Ok(Some(proc.code_root().clone()))
+    }
+
     pub(super) fn call_local(
         &self,
         index: u16,
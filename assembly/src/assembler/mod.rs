@@ -337,6 +337,8 @@ impl Assembler {
 
                     blocks.push(block);
                 }
+
+                Node::Nop => {}
             }
         }
 
@@ -0,0 +1,42 @@
+use super::{Instruction, ProcedureId, RpoDigest};
+
+// CALL TARGET
+// ================================================================================================
+
+/// A procedure invoked via an `exec`, `call`, or `syscall` instruction, as reported by
+/// [ProgramAst::visit_calls](super::ProgramAst::visit_calls).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallTarget {
+    /// A local procedure, identified by its index, executed via `exec`.
+    ExecLocal(u16),
+    /// An imported procedure executed via `exec`.
+    ExecImported(ProcedureId),
+    /// A procedure identified by its MAST root executed via `exec`.
+    ExecMastRoot(RpoDigest),
+    /// A local procedure, identified by its index, invoked via `call`.
+    CallLocal(u16),
+    /// An imported procedure invoked via `call`.
+    CallImported(ProcedureId),
+    /// A procedure identified by its MAST root invoked via `call`.
+    CallMastRoot(RpoDigest),
+    /// A kernel procedure invoked via `syscall`.
+    SysCall(ProcedureId),
+}
+
+impl CallTarget {
+    /// Returns the target of the provided instruction, or None if the instruction does not invoke
+    /// a procedure.
+    pub fn from_instruction(instruction: &Instruction) -> Option<Self> {
+        let target = match instruction {
+            Instruction::ExecLocal(index) => Self::ExecLocal(*index),
+            Instruction::ExecImported(id) => Self::ExecImported(*id),
+            Instruction::ExecMastRoot(root) => Self::ExecMastRoot(*root),
+            Instruction::CallLocal(index) => Self::CallLocal(*index),
+            Instruction::CallImported(id) => Self::CallImported(*id),
+            Instruction::CallMastRoot(root) => Self::CallMastRoot(*root),
+            Instruction::SysCall(id) => Self::SysCall(*id),
+            _ => return None,
+        };
+        Some(target)
+    }
+}
@@ -1,8 +1,9 @@
 use super::{
-    ByteReader, ByteWriter, Deserializable, DeserializationError, Node, Serializable,
-    SourceLocation, Vec,
+    walk_body, AstFormatVersion, AstSerdeOptions, ByteReader, ByteWriter, Deserializable,
+    DeserializationError, Instruction, Node, Serializable, SourceLocation, SourceSpan, Vec,
+    Visitor, MAX_BODY_LEN,
 };
-use core::{iter, slice};
+use core::{iter, mem, slice};
 
 // CODE BODY
 // ================================================================================================
@@ -11,10 +12,14 @@ use core::{iter, slice};
 ///
 /// When present, the number of locations is equal to the number of nodes + 1. This is because the
 /// last location tracks the `end` token of a body which does not have its own node.
+///
+/// Parsed code bodies additionally track the location immediately past the end of every node, so
+/// that the full [SourceSpan] of a node can be retrieved via [CodeBody::span].
 #[derive(Clone, Default, Eq, Debug)]
 pub struct CodeBody {
     nodes: Vec<Node>,
     locations: Vec<SourceLocation>,
+    ends: Vec<SourceLocation>,
 }
 
 impl CodeBody {
@@ -29,6 +34,7 @@ impl CodeBody {
         Self {
             nodes: nodes.into_iter().collect(),
             locations: Vec::new(),
+            ends: Vec::new(),
         }
     }
 
@@ -47,6 +53,18 @@ impl CodeBody {
         self
     }
 
+    /// Binds the end locations of the source spans to their respective [Node].
+    ///
+    /// It is expected that `ends` have the same length as `self.nodes`, and that the start
+    /// locations have been bound via [CodeBody::with_source_locations].
+    pub fn with_end_locations<L>(mut self, ends: L) -> Self
+    where
+        L: IntoIterator<Item = SourceLocation>,
+    {
+        self.ends = ends.into_iter().collect();
+        self
+    }
+
     // STATE MUTATORS
     // --------------------------------------------------------------------------------------------
 
@@ -67,6 +85,184 @@ impl CodeBody {
     /// Removes source location information from this code body.
     pub fn clear_locations(&mut self) {
         self.locations.clear();
+        self.ends.clear();
+    }
+
+    /// Invokes `f` on every node of this code body, including the nodes of nested control flow
+    /// blocks.
+    ///
+    /// Nodes are visited in pre-order, in the same way as in [CodeBody::visit_mut]. This is a
+    /// shorthand for [walk_body] with a visitor which implements only [Visitor::visit_node].
+    pub fn visit<F>(&self, f: &mut F)
+    where
+        F: FnMut(&Node),
+    {
+        struct NodeVisitor<'a, F>(&'a mut F);
+
+        impl<F: FnMut(&Node)> Visitor for NodeVisitor<'_, F> {
+            fn visit_node(&mut self, node: &Node) {
+                (self.0)(node)
+            }
+        }
+
+        walk_body(self, &mut NodeVisitor(f));
+    }
+
+    /// Invokes `f` on every node of this code body, including the nodes of nested control flow
+    /// blocks.
+    ///
+    /// Nodes are visited in pre-order: a block node is passed to `f` before any of its children,
+    /// and the children are visited after `f` returns. The number of nodes in each body must
+    /// not be changed by `f` so that the nodes remain aligned with their source locations.
+    pub fn visit_mut<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(&mut Node),
+    {
+        for node in self.nodes.iter_mut() {
+            f(node);
+            match node {
+                Node::Instruction(_) | Node::Nop => {}
+                Node::IfElse {
+                    true_case,
+                    false_case,
+                } => {
+                    true_case.visit_mut(f);
+                    false_case.visit_mut(f);
+                }
+                Node::Repeat { body, .. } | Node::While { body } => body.visit_mut(f),
+            }
+        }
+    }
+
+    /// Removes all [Node::Nop] nodes from this code body, including the nodes of nested control
+    /// flow blocks.
+    ///
+    /// Source locations of the removed nodes are removed as well, so that the remaining nodes stay
+    /// aligned with their locations. The location of the terminating `end` token is preserved.
+    pub fn strip_nops(&mut self) {
+        let mut locations = mem::take(&mut self.locations).into_iter();
+        let mut ends = mem::take(&mut self.ends).into_iter();
+        for mut node in mem::take(&mut self.nodes) {
+            let location = locations.next();
+            let end = ends.next();
+            match &mut node {
+                Node::Nop => continue,
+                Node::Instruction(_) => {}
+                Node::IfElse {
+                    true_case,
+                    false_case,
+                } => {
+                    true_case.strip_nops();
+                    false_case.strip_nops();
+                }
+                Node::Repeat { body, .. } | Node::While { body } => body.strip_nops(),
+            }
+            self.nodes.push(node);
+            self.locations.extend(location);
+            self.ends.extend(end);
+        }
+        // keep the location of the `end` token, if present
+        self.locations.extend(locations);
+    }
+
+    /// Applies peephole optimizations to this code body, including the bodies of nested control
+    /// flow blocks, and returns the number of rewrites applied.
+    ///
+    /// Only adjacent instructions within the same body are rewritten, so that rewrites never
+    /// cross control flow boundaries. The following rewrites are applied:
+    /// - A push of a single value or a `dup.0` immediately followed by `drop` is removed.
+    /// - A push of a single value immediately following a push of the same value is replaced
+    ///   with `dup.0`.
+    ///
+    /// Source locations of the removed nodes are removed as well.
+    pub(super) fn peephole_optimize(&mut self) -> usize {
+        let mut num_rewrites = 0;
+        let mut locations = mem::take(&mut self.locations).into_iter();
+        let mut ends = mem::take(&mut self.ends).into_iter();
+        for mut node in mem::take(&mut self.nodes) {
+            let location = locations.next();
+            let end = ends.next();
+            match &mut node {
+                Node::Instruction(_) | Node::Nop => {}
+                Node::IfElse {
+                    true_case,
+                    false_case,
+                } => {
+                    num_rewrites += true_case.peephole_optimize();
+                    num_rewrites += false_case.peephole_optimize();
+                }
+                Node::Repeat { body, .. } | Node::While { body } => {
+                    num_rewrites += body.peephole_optimize()
+                }
+            }
+
+            if let (Some(Node::Instruction(prev)), Node::Instruction(current)) =
+                (self.nodes.last(), &node)
+            {
+                let pushes_one = is_single_value_push(prev) || *prev == Instruction::Dup0;
+                if pushes_one && *current == Instruction::Drop {
+                    // the pushed value is dropped right away
+                    self.nodes.pop();
+                    self.locations.pop();
+                    self.ends.pop();
+                    num_rewrites += 1;
+                    continue;
+                }
+                if is_single_value_push(prev) && prev == current {
+                    // the pushed value is already on top of the stack
+                    node = Node::Instruction(Instruction::Dup0);
+                    num_rewrites += 1;
+                }
+            }
+            self.nodes.push(node);
+            self.locations.extend(location);
+            self.ends.extend(end);
+        }
+        // keep the location of the `end` token, if present
+        self.locations.extend(locations);
+        num_rewrites
+    }
+
+    /// Replaces `exec` invocations of local procedures in this code body, including the bodies of
+    /// nested control flow blocks, with the nodes of the body returned by `f` for the index of the
+    /// invoked procedure. Invocations for which `f` returns None are left unchanged.
+    ///
+    /// The inlined nodes take the source span of the replaced invocation.
+    pub(super) fn inline_execs<F>(&mut self, f: &mut F)
+    where
+        F: FnMut(u16) -> Option<CodeBody>,
+    {
+        let mut locations = mem::take(&mut self.locations).into_iter();
+        let mut ends = mem::take(&mut self.ends).into_iter();
+        for mut node in mem::take(&mut self.nodes) {
+            let location = locations.next();
+            let end = ends.next();
+            match &mut node {
+                Node::Instruction(Instruction::ExecLocal(index)) => {
+                    if let Some(body) = f(*index) {
+                        let num_nodes = body.nodes.len();
+                        self.nodes.extend(body.nodes);
+                        self.locations.extend(iter::repeat(location).take(num_nodes).flatten());
+                        self.ends.extend(iter::repeat(end).take(num_nodes).flatten());
+                        continue;
+                    }
+                }
+                Node::Instruction(_) | Node::Nop => {}
+                Node::IfElse {
+                    true_case,
+                    false_case,
+                } => {
+                    true_case.inline_execs(f);
+                    false_case.inline_execs(f);
+                }
+                Node::Repeat { body, .. } | Node::While { body } => body.inline_execs(f),
+            }
+            self.nodes.push(node);
+            self.locations.extend(location);
+            self.ends.extend(end);
+        }
+        // keep the location of the `end` token, if present
+        self.locations.extend(locations);
     }
 
     // SERIALIZATION / DESERIALIZATION
@@ -75,30 +271,66 @@ impl CodeBody {
     /// Loads the [SourceLocation] from the `source`.
     ///
     /// The `source` is expected to provide a locations count equal to the block nodes count + 1,
-    /// having the last element reserved for its `end` node. This way, the locations count is not
-    /// expected to be read, as opposed to common vector serialization strategies.
+    /// having the last element reserved for its `end` node, followed by the end locations of the
+    /// spans of the nodes if the format version of the serde options is at least
+    /// [AstFormatVersion::V1]. This way, the locations count is not expected to be read, as
+    /// opposed to common vector serialization strategies.
     ///
     /// This implementation intentionally diverges from [Deserializable] so locations can be
     /// optionally stored.
     pub fn load_source_locations<R: ByteReader>(
         &mut self,
         source: &mut R,
+        options: AstSerdeOptions,
     ) -> Result<(), DeserializationError> {
         self.locations = (0..=self.nodes.len())
             .map(|_| SourceLocation::read_from(source))
             .collect::<Result<_, _>>()?;
+        self.ends = if options.version >= AstFormatVersion::V1 {
+            (0..self.nodes.len())
+                .map(|_| SourceLocation::read_from(source))
+                .collect::<Result<_, _>>()?
+        } else {
+            Vec::new()
+        };
         Ok(())
     }
 
+    /// Writes the number of nodes of this body into `target`, followed by the nodes themselves.
+    ///
+    /// [Node::Nop] nodes are not serialized, and are not included in the number of nodes.
+    ///
+    /// # Panics
+    /// Panics if the body contains more nodes than can be serialized.
+    pub(crate) fn write_nodes<W: ByteWriter>(&self, target: &mut W) {
+        // the assert below is OK because we enforce limits on the number of body instructions in
+        // the body parser
+        let nodes = self.nodes.iter().filter(|node| !matches!(node, Node::Nop));
+        let num_nodes = nodes.clone().count();
+        assert!(num_nodes <= MAX_BODY_LEN, "too many body nodes");
+        target.write_u16(num_nodes as u16);
+        nodes.for_each(|node| node.write_into(target));
+    }
+
     /// Writes the [SourceLocation] into `target`.
     ///
     /// The locations will be written directly, without storing the locations count. This is the
-    /// counterpart of [CodeBody::load_source_locations].
+    /// counterpart of [CodeBody::load_source_locations]. The locations of [Node::Nop] nodes are
+    /// skipped, as the nodes themselves are not serialized.
     ///
     /// This implementation intentionally diverges from [Serializable] so locations can be
     /// optionally stored.
-    pub fn write_source_locations<W: ByteWriter>(&self, target: &mut W) {
-        self.locations.iter().for_each(|l| l.write_into(target));
+    pub fn write_source_locations<W: ByteWriter>(&self, target: &mut W, options: AstSerdeOptions) {
+        let is_nop = |i: &usize| matches!(self.nodes.get(*i), Some(Node::Nop));
+        let locations = self.locations.iter().enumerate().filter(|(i, _)| !is_nop(i));
+        locations.clone().for_each(|(_, l)| l.write_into(target));
+        if self.locations.is_empty() || options.version < AstFormatVersion::V1 {
+            return;
+        }
+        // nodes without a recorded end location are written as empty spans
+        for (i, location) in locations.filter(|(i, _)| *i < self.nodes.len()) {
+            self.ends.get(i).unwrap_or(location).write_into(target);
+        }
     }
 
     // PUBLIC ACCESSORS
@@ -114,6 +346,127 @@ impl CodeBody {
         &self.locations
     }
 
+    /// Returns the locations immediately past the end of the nodes of this body structure.
+    pub fn end_locations(&self) -> &[SourceLocation] {
+        &self.ends
+    }
+
+    /// Returns the [SourceSpan] of the node at the specified index, or None if the body contains
+    /// fewer nodes or the span of the node is not known.
+    ///
+    /// For control flow nodes, the span covers the whole block up to and including its `end`
+    /// token. Only the nodes of this body are indexed; the nodes of nested blocks can be reached
+    /// via their respective bodies. [Node::Nop] nodes are not serialized, and thus are skipped
+    /// when indexing, so that the indexes are the same after a serialization round-trip.
+    pub fn span(&self, index: usize) -> Option<SourceSpan> {
+        let (position, _) = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| !matches!(node, Node::Nop))
+            .nth(index)?;
+        let start = self.locations.get(position)?;
+        let end = self.ends.get(position)?;
+        Some(SourceSpan::new(*start, *end))
+    }
+
+    /// Returns the node at the specified index, or None if this code body contains fewer nodes.
+    ///
+    /// Nodes are indexed in the order in which they are visited by [CodeBody::visit], i.e., in
+    /// depth-first pre-order: a control flow node is numbered before the nodes of its bodies,
+    /// and the nodes of the `true` branch of an `if` node are numbered before the nodes of its
+    /// `else` branch. [Node::Nop] nodes are not numbered, as they are not serialized. Thus, the
+    /// indexes depend only on the serialized nodes, and are stable across serialization
+    /// round-trips.
+    pub fn node_at(&self, index: usize) -> Option<&Node> {
+        let mut remaining = index;
+        self.find_node(&mut remaining)
+    }
+
+    /// Returns the node at the `remaining` position of the pre-order traversal of this code body,
+    /// decrementing `remaining` by the number of nodes passed over.
+    fn find_node(&self, remaining: &mut usize) -> Option<&Node> {
+        for node in self.nodes.iter().filter(|node| !matches!(node, Node::Nop)) {
+            if *remaining == 0 {
+                return Some(node);
+            }
+            *remaining -= 1;
+            let found = match node {
+                Node::Instruction(_) | Node::Nop => None,
+                Node::IfElse {
+                    true_case,
+                    false_case,
+                } => match true_case.find_node(remaining) {
+                    Some(node) => Some(node),
+                    None => false_case.find_node(remaining),
+                },
+                Node::Repeat { body, .. } | Node::While { body } => body.find_node(remaining),
+            };
+            if found.is_some() {
+                return found;
+            }
+        }
+        None
+    }
+
+    /// Returns the number of instructions in this code body, including the instructions of nested
+    /// control flow blocks.
+    ///
+    /// Control flow blocks themselves are not counted as instructions.
+    pub fn instruction_count(&self) -> usize {
+        self.nodes
+            .iter()
+            .map(|node| match node {
+                Node::Instruction(_) => 1,
+                Node::IfElse {
+                    true_case,
+                    false_case,
+                } => true_case.instruction_count() + false_case.instruction_count(),
+                Node::Repeat { body, .. } | Node::While { body } => body.instruction_count(),
+                Node::Nop => 0,
+            })
+            .sum()
+    }
+
+    /// Returns the highest procedure local index accessed by the instructions of this code body,
+    /// including the instructions of nested control flow blocks, together with the first
+    /// instruction accessing this index and its source location.
+    ///
+    /// Returns None if no procedure locals are accessed. If source locations are not present, the
+    /// default location is returned.
+    pub fn max_local_access(&self) -> Option<(u16, &Instruction, SourceLocation)> {
+        let mut max_access: Option<(u16, &Instruction, SourceLocation)> = None;
+        for (i, node) in self.nodes.iter().enumerate() {
+            let access = match node {
+                Node::Instruction(
+                    instruction @ (Instruction::Locaddr(index)
+                    | Instruction::LocLoad(index)
+                    | Instruction::LocLoadW(index)
+                    | Instruction::LocStore(index)
+                    | Instruction::LocStoreW(index)),
+                ) => {
+                    let location = self.locations.get(i).copied().unwrap_or_default();
+                    Some((*index, instruction, location))
+                }
+                Node::Instruction(_) | Node::Nop => None,
+                Node::IfElse {
+                    true_case,
+                    false_case,
+                } => match (true_case.max_local_access(), false_case.max_local_access()) {
+                    (Some(a), Some(b)) if b.0 > a.0 => Some(b),
+                    (a, b) => a.or(b),
+                },
+                Node::Repeat { body, .. } | Node::While { body } => body.max_local_access(),
+            };
+            match (access, max_access) {
+                (Some(access), Some(max)) if access.0 <= max.0 => {}
+                (Some(access), _) => max_access = Some(access),
+                (None, _) => {}
+            }
+        }
+        max_access
+    }
+
     /// Returns true if this code body contain source location information.
     pub fn has_locations(&self) -> bool {
         !self.locations.is_empty()
@@ -123,6 +476,9 @@ impl CodeBody {
     // --------------------------------------------------------------------------------------------
 
     /// Returns the internal parts of this code body.
+    ///
+    /// The end locations of the node spans are not included; they can be retrieved beforehand via
+    /// [CodeBody::end_locations].
     pub fn into_parts(self) -> (Vec<Node>, Vec<SourceLocation>) {
         (self.nodes, self.locations)
     }
@@ -142,6 +498,7 @@ impl FromIterator<Node> for CodeBody {
         Self {
             nodes: nodes.into_iter().collect(),
             locations: Vec::new(),
+            ends: Vec::new(),
         }
     }
 }
@@ -149,8 +506,66 @@ impl FromIterator<Node> for CodeBody {
 impl FromIterator<(Node, SourceLocation)> for CodeBody {
     fn from_iter<T: IntoIterator<Item = (Node, SourceLocation)>>(nodes: T) -> Self {
         let (nodes, locations) = nodes.into_iter().unzip();
-        Self { nodes, locations }
+        Self {
+            nodes,
+            locations,
+            ends: Vec::new(),
+        }
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Returns true if the instruction pushes a single value onto the stack.
+fn is_single_value_push(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::PushU8(_)
+            | Instruction::PushU16(_)
+            | Instruction::PushU32(_)
+            | Instruction::PushFelt(_)
+    )
+}
+
+// TESTING UTILITIES
+// ================================================================================================
+
+/// Asserts that the nodes of the provided [CodeBody] survive serialization and deserialization
+/// unchanged.
+///
+/// Source locations are not serialized together with the nodes, and thus are not compared. The
+/// same holds for [Node::Nop] nodes, which are skipped during serialization.
+///
+/// # Panics
+/// Panics if the nodes cannot be deserialized, or if the deserialized nodes differ from the
+/// original ones. In the latter case, the panic message points to the first differing node.
+#[cfg(any(test, feature = "testing"))]
+pub fn assert_codebody_roundtrips(body: &CodeBody) {
+    use super::SliceReader;
+
+    let mut bytes = Vec::new();
+    body.write_nodes(&mut bytes);
+
+    let mut source = SliceReader::new(&bytes);
+    let len = source.read_u16().expect("failed to read body length") as usize;
+    let nodes: Vec<Node> = Deserializable::read_batch_from(&mut source, len)
+        .unwrap_or_else(|err| panic!("failed to deserialize code body: {err}"));
+
+    let mut body = body.clone();
+    body.strip_nops();
+    if let Some(idx) = body.nodes().iter().zip(nodes.iter()).position(|(a, b)| a != b) {
+        panic!(
+            "code body does not round-trip: node {idx} differs\n  original: {:?}\n  deserialized: {:?}",
+            body.nodes()[idx],
+            nodes[idx]
+        );
     }
+    assert_eq!(
+        body.nodes().len(),
+        nodes.len(),
+        "code body does not round-trip: number of nodes differs"
+    );
 }
 
 impl PartialEq for CodeBody {
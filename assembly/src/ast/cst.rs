@@ -0,0 +1,170 @@
+use super::{LibraryPath, SourceLocation, Token, Vec};
+use core::fmt;
+
+// CONCRETE SYNTAX TREE
+// ================================================================================================
+
+/// A lossless concrete syntax tree of Miden assembly source.
+///
+/// Unlike the ASTs in this module, a concrete syntax tree retains all of the source text,
+/// including whitespace, line breaks, and comments. Printing a tree via its [fmt::Display]
+/// implementation reproduces the source it was built from byte-for-byte, which makes it suitable
+/// for tools such as formatters.
+///
+/// Statements which open a block (e.g., `begin`, `proc`, `if`, `while`, `repeat`) are grouped
+/// together with the contents of the block and the matching `end` token into a
+/// [CstNode::Block].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cst<'a> {
+    nodes: Vec<CstNode<'a>>,
+}
+
+/// A node of a [Cst].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CstNode<'a> {
+    /// A whitespace-delimited token, e.g. `push.1` or `exec.u64::add`.
+    Token {
+        text: &'a str,
+        location: SourceLocation,
+    },
+    /// A run of whitespace characters not containing line breaks.
+    Whitespace(&'a str),
+    /// A line break, either `\n` or `\r\n`.
+    Newline(&'a str),
+    /// A line comment, starting with `#` and running until the end of the line.
+    Comment(&'a str),
+    /// A doc comment, starting with `#!` and running until the end of the line.
+    DocComment(&'a str),
+    /// A block statement, starting with a block-opening token and ending with its `end` token.
+    Block(Vec<CstNode<'a>>),
+}
+
+impl<'a> Cst<'a> {
+    // CONSTRUCTOR
+    // --------------------------------------------------------------------------------------------
+
+    /// Builds a concrete syntax tree from the provided source.
+    ///
+    /// No validation of the source is performed; if the block structure of the source is
+    /// malformed, unterminated blocks are closed at the end of the source and dangling `end`
+    /// tokens are kept at the level at which they appear.
+    pub fn new(source: &'a str) -> Self {
+        // the stack of blocks being built; the bottom of the stack holds the top-level nodes
+        let mut stack = Vec::from([Vec::new()]);
+        let mut location = SourceLocation::default();
+        let mut remainder = source;
+
+        while !remainder.is_empty() {
+            let (node, len) = next_node(remainder, location);
+            match node {
+                CstNode::Newline(_) => location = SourceLocation::new(location.line() + 1, 1),
+                _ => location.move_column(len as u32),
+            }
+            remainder = &remainder[len..];
+
+            match node {
+                CstNode::Token { text, .. } if opens_block(text) => stack.push(Vec::from([node])),
+                CstNode::Token { text, .. } if stack.len() > 1 && is_end(text) => {
+                    let mut block = stack.pop().expect("no open block");
+                    block.push(node);
+                    stack.last_mut().expect("no parent block").push(CstNode::Block(block));
+                }
+                _ => stack.last_mut().expect("no current block").push(node),
+            }
+        }
+
+        // close any unterminated blocks
+        while stack.len() > 1 {
+            let block = stack.pop().expect("no open block");
+            stack.last_mut().expect("no parent block").push(CstNode::Block(block));
+        }
+
+        Self {
+            nodes: stack.pop().expect("no top-level nodes"),
+        }
+    }
+
+    // PUBLIC ACCESSORS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns the top-level nodes of this tree.
+    pub fn nodes(&self) -> &[CstNode<'a>] {
+        &self.nodes
+    }
+}
+
+impl<'a> fmt::Display for Cst<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.nodes.iter().try_for_each(|node| write!(f, "{node}"))
+    }
+}
+
+impl<'a> fmt::Display for CstNode<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Token { text, .. } => write!(f, "{text}"),
+            Self::Whitespace(text)
+            | Self::Newline(text)
+            | Self::Comment(text)
+            | Self::DocComment(text) => write!(f, "{text}"),
+            Self::Block(nodes) => nodes.iter().try_for_each(|node| write!(f, "{node}")),
+        }
+    }
+}
+
+// HELPER FUNCTIONS
+// ================================================================================================
+
+/// Reads the next leaf node from the start of `source` and returns it together with its length in
+/// bytes.
+fn next_node(source: &str, location: SourceLocation) -> (CstNode<'_>, usize) {
+    if source.starts_with("\r\n") {
+        return (CstNode::Newline(&source[..2]), 2);
+    }
+    if source.starts_with('\n') {
+        return (CstNode::Newline(&source[..1]), 1);
+    }
+
+    // comments run until the end of the line, excluding the line break
+    if source.starts_with(Token::COMMENT_PREFIX) {
+        let len = source.find(['\r', '\n']).unwrap_or(source.len());
+        let text = &source[..len];
+        if text.starts_with(Token::DOC_COMMENT_PREFIX) {
+            return (CstNode::DocComment(text), len);
+        }
+        return (CstNode::Comment(text), len);
+    }
+
+    let is_whitespace = |c: char| c.is_whitespace() && c != '\n' && c != '\r';
+    if source.starts_with(is_whitespace) {
+        let len = source.find(|c| !is_whitespace(c)).unwrap_or(source.len());
+        return (CstNode::Whitespace(&source[..len]), len);
+    }
+
+    // a lone carriage return is neither a line break nor a token
+    if source.starts_with('\r') {
+        return (CstNode::Whitespace(&source[..1]), 1);
+    }
+
+    let len = source.find(char::is_whitespace).unwrap_or(source.len());
+    let text = &source[..len];
+    (CstNode::Token { text, location }, len)
+}
+
+/// Returns true if the token opens a block which is terminated by an `end` token.
+fn opens_block(token: &str) -> bool {
+    let mut parts = token.split('.');
+    match parts.next() {
+        Some(Token::BEGIN | Token::PROC | Token::IF | Token::WHILE | Token::REPEAT) => true,
+        // re-exported procedures do not have a body
+        Some(Token::EXPORT) => {
+            parts.next().map_or(true, |name| !name.contains(LibraryPath::PATH_DELIM))
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if the token terminates a block.
+fn is_end(token: &str) -> bool {
+    token.split('.').next() == Some(Token::END)
+}
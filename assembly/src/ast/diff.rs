@@ -0,0 +1,129 @@
+use super::{
+    BTreeSet, CodeBody, LibraryPath, Node, ProcedureAst, ProgramAst, String, ToString, Vec,
+};
+
+// DIFFERENCE
+// ================================================================================================
+
+/// A structural difference between two [ProgramAst]s, as reported by [ProgramAst::diff].
+///
+/// Source locations are never considered to be a difference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// The import with the specified alias is missing from one of the programs, or refers to
+    /// different modules in the two programs.
+    Import {
+        alias: String,
+        left: Option<LibraryPath>,
+        right: Option<LibraryPath>,
+    },
+    /// The programs have a different number of local procedures.
+    ProcedureCount { left: usize, right: usize },
+    /// The local procedures at the specified index differ in their name, number of locals, or
+    /// body. `node` is the index of the first differing node of the body, if the bodies differ.
+    Procedure {
+        index: usize,
+        name: String,
+        node: Option<usize>,
+    },
+    /// The bodies of the programs differ starting at the specified node index.
+    Body { node: usize },
+}
+
+// STRUCTURAL DIFF
+// ================================================================================================
+
+/// Returns the structural differences between the `left` and `right` programs.
+///
+/// Imports are reported in the order of their aliases, followed by local procedures in the order
+/// of their indexes, followed by the program body.
+pub(super) fn diff_programs(left: &ProgramAst, right: &ProgramAst) -> Vec<Difference> {
+    let mut differences = Vec::new();
+
+    let aliases = left.imports.keys().chain(right.imports.keys()).collect::<BTreeSet<_>>();
+    for alias in aliases {
+        let (left_path, right_path) = (left.imports.get(alias), right.imports.get(alias));
+        if left_path != right_path {
+            differences.push(Difference::Import {
+                alias: alias.clone(),
+                left: left_path.cloned(),
+                right: right_path.cloned(),
+            });
+        }
+    }
+
+    if left.local_procs.len() != right.local_procs.len() {
+        differences.push(Difference::ProcedureCount {
+            left: left.local_procs.len(),
+            right: right.local_procs.len(),
+        });
+    }
+    for (index, (left, right)) in left.local_procs.iter().zip(right.local_procs.iter()).enumerate()
+    {
+        if let Some(difference) = diff_procedures(index, left, right) {
+            differences.push(difference);
+        }
+    }
+
+    if let Some(node) = first_divergence(&left.body, &right.body) {
+        differences.push(Difference::Body { node });
+    }
+
+    differences
+}
+
+/// Returns the difference between the procedures at `index`, if any.
+fn diff_procedures(index: usize, left: &ProcedureAst, right: &ProcedureAst) -> Option<Difference> {
+    let node = first_divergence(&left.body, &right.body);
+    if left.name == right.name && left.num_locals == right.num_locals && node.is_none() {
+        return None;
+    }
+    Some(Difference::Procedure {
+        index,
+        name: left.name.to_string(),
+        node,
+    })
+}
+
+/// Returns the index of the first top-level node at which the two bodies differ, or None if the
+/// bodies are structurally equal.
+///
+/// If one body is a prefix of the other, the length of the shorter body is returned.
+fn first_divergence(left: &CodeBody, right: &CodeBody) -> Option<usize> {
+    let (left, right) = (left.nodes(), right.nodes());
+    left.iter()
+        .zip(right.iter())
+        .position(|(left, right)| !nodes_eq(left, right))
+        .or_else(|| (left.len() != right.len()).then_some(left.len().min(right.len())))
+}
+
+/// Returns true if the two nodes are equal, ignoring the source locations of nested bodies.
+fn nodes_eq(left: &Node, right: &Node) -> bool {
+    let bodies_eq = |left, right| first_divergence(left, right).is_none();
+    match (left, right) {
+        (
+            Node::IfElse {
+                true_case: left_true,
+                false_case: left_false,
+            },
+            Node::IfElse {
+                true_case: right_true,
+                false_case: right_false,
+            },
+        ) => bodies_eq(left_true, right_true) && bodies_eq(left_false, right_false),
+        (
+            Node::Repeat {
+                times: left_times,
+                body: left_body,
+            },
+            Node::Repeat {
+                times: right_times,
+                body: right_body,
+            },
+        ) => left_times == right_times && bodies_eq(left_body, right_body),
+        (Node::While { body: left_body }, Node::While { body: right_body }) => {
+            bodies_eq(left_body, right_body)
+        }
+        _ => left == right,
+    }
+}
@@ -4,32 +4,64 @@
 //! code into relevant ASTs. This can be done via their `parse()` methods.
 
 use super::{
-    crypto::hash::RpoDigest, BTreeMap, ByteReader, ByteWriter, Deserializable,
-    DeserializationError, Felt, LabelError, LibraryPath, ParsingError, ProcedureId, ProcedureName,
+    crypto::hash::{Blake3_256, RpoDigest},
+    BTreeMap, BTreeSet, ByteReader, ByteWriter, Deserializable, DeserializationError, Felt,
+    LabelError, LibraryPath, ParsingError, ParsingWarning, ProcedureId, ProcedureName, RenderError,
     Serializable, SliceReader, StarkField, String, ToString, Token, TokenStream, Vec,
     MAX_LABEL_LEN,
 };
 use core::{iter, str::from_utf8};
 use vm_core::utils::bound_into_included_u64;
 
-pub use super::tokens::SourceLocation;
+pub use super::tokens::{SourceLocation, SourceSpan};
 
 mod nodes;
-pub use nodes::{AdviceInjectorNode, Instruction, Node};
+pub use nodes::{AdviceInjectorNode, Instruction, Node, OPCODE_NAMES};
 
 mod code_body;
+#[cfg(any(test, feature = "testing"))]
+pub use code_body::assert_codebody_roundtrips;
 pub use code_body::CodeBody;
 
+mod cst;
+pub use cst::{Cst, CstNode};
+
 mod invocation_target;
 pub use invocation_target::InvocationTarget;
 
+mod call_target;
+pub use call_target::CallTarget;
+
 mod parsers;
-use parsers::{parse_constants, parse_imports, ParserContext};
+use parsers::{parse_constants, parse_imports, unused_symbol_warnings, ParserContext};
 
 pub(crate) use parsers::{NAMESPACE_LABEL_PARSER, PROCEDURE_LABEL_PARSER};
 
+mod options;
+pub use options::ParsingOptions;
+
+mod symbols;
+pub use symbols::{Symbol, SymbolKind};
+
+mod visitor;
+pub use visitor::{walk_body, Visitor};
+
+mod source;
+
+mod diff;
+pub use diff::Difference;
+
+mod stack_effect;
+pub use stack_effect::StackEffect;
+
+mod stack_balance;
+pub use stack_balance::StackWarning;
+
+mod procedure_builder;
+pub use procedure_builder::ProcedureAstBuilder;
+
 mod serde;
-pub use serde::AstSerdeOptions;
+pub use serde::{AstFormatVersion, AstSerdeOptions};
 
 #[cfg(test)]
 pub mod tests;
@@ -46,6 +78,19 @@ const MAX_REEXPORTED_PROCS: usize = u16::MAX as usize;
 /// Maximum number of bytes for a single documentation comment.
 const MAX_DOCS_LEN: usize = u16::MAX as usize;
 
+/// Maximum number of bytes for module documentation.
+const MAX_MODULE_DOCS_LEN: usize = 1 << 20;
+
+/// Leading byte of compressed module serializations. Uncompressed serializations start with the
+/// serialized [AstSerdeOptions], whose only byte is far below this value for all supported format
+/// versions.
+#[cfg(feature = "compress")]
+const COMPRESSED_MODULE_MAGIC: u8 = 0xcd;
+
+/// Compression level used for compressed module serializations.
+#[cfg(feature = "compress")]
+const MODULE_COMPRESSION_LEVEL: u8 = 6;
+
 /// Maximum number of nodes in statement body (e.g., procedure body, loop body etc.).
 const MAX_BODY_LEN: usize = u16::MAX as usize;
 
@@ -58,8 +103,9 @@ const MAX_STACK_WORD_OFFSET: u8 = 12;
 // TYPE ALIASES
 // ================================================================================================
 type LocalProcMap = BTreeMap<String, (u16, ProcedureAst)>;
-type LocalConstMap = BTreeMap<String, u64>;
+type LocalConstMap = BTreeMap<String, (u64, Option<String>, SourceLocation)>;
 type ReExportedProcMap = BTreeMap<String, ProcReExport>;
+type InvokedProcsMap = BTreeMap<ProcedureId, (ProcedureName, LibraryPath)>;
 
 // EXECUTABLE PROGRAM AST
 // ================================================================================================
@@ -67,12 +113,15 @@ type ReExportedProcMap = BTreeMap<String, ProcReExport>;
 /// An abstract syntax tree of an executable Miden program.
 ///
 /// A program AST consists of a list of internal procedure ASTs and a body of the program.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct ProgramAst {
     body: CodeBody,
     local_procs: Vec<ProcedureAst>,
     imports: BTreeMap<String, LibraryPath>,
     start: SourceLocation,
+    /// Imports and constants declared in the source of this program. These are collected only
+    /// when the program is parsed from source, and are neither serialized nor compared.
+    declarations: Vec<Symbol>,
 }
 
 impl ProgramAst {
@@ -100,9 +149,50 @@ impl ProgramAst {
             local_procs,
             imports,
             start,
+            declarations: Vec::new(),
         })
     }
 
+    /// Returns a [ProgramAst] assembled from the provided parts.
+    ///
+    /// This is the inverse of [Self::into_parts] combined with [Self::imports],
+    /// [Self::body_start_location], and the source locations of the program body: a program
+    /// rebuilt from these parts is equal to the original program. If `locations` is empty, the
+    /// program is built without source locations.
+    ///
+    /// The end locations of the body nodes and the declarations of imports and constants are not
+    /// part of these parts; use [Self::into_program_parts] and [Self::from_program_parts] to
+    /// preserve them as well.
+    ///
+    /// # Errors
+    /// Returns an error if the number of imports or local procedures exceeds the limits on the
+    /// size of a program.
+    pub fn from_parts(
+        imports: BTreeMap<String, LibraryPath>,
+        local_procs: Vec<ProcedureAst>,
+        body: Vec<Node>,
+        start: SourceLocation,
+        locations: Vec<SourceLocation>,
+    ) -> Result<Self, ParsingError> {
+        Ok(Self::new(body, local_procs, imports)?.with_source_locations(locations, start))
+    }
+
+    /// Returns a [ProgramAst] rebuilt from the parts returned by [Self::into_program_parts].
+    ///
+    /// Unlike [Self::from_parts], this preserves the end locations of the body nodes and the
+    /// declarations of imports and constants reported by [Self::symbols].
+    ///
+    /// # Errors
+    /// Returns an error if the number of imports or local procedures exceeds the limits on the
+    /// size of a program.
+    pub fn from_program_parts(parts: ProgramParts) -> Result<Self, ParsingError> {
+        let mut program = Self::new(Vec::new(), parts.local_procs, parts.imports)?;
+        program.body = parts.body;
+        program.start = parts.start;
+        program.declarations = parts.declarations;
+        Ok(program)
+    }
+
     /// Binds the provided `locations` to the nodes of this program's body.
     ///
     /// The `start` location points to the `begin` token which does not have its own node.
@@ -119,6 +209,118 @@ impl ProgramAst {
         self
     }
 
+    /// Records the imports and constants declared in the source of this program.
+    fn with_declarations(mut self, declarations: Vec<Symbol>) -> Self {
+        self.declarations = declarations;
+        self
+    }
+
+    // STATE MUTATORS
+    // --------------------------------------------------------------------------------------------
+
+    /// Appends the provided procedure to the list of internal procedures of this program.
+    ///
+    /// The procedure is added after all existing procedures, and thus indexes of the existing
+    /// procedures are not affected. The body and the imports of this program are left untouched.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - This program already contains a procedure with the same name.
+    /// - Adding the procedure would exceed the maximum number of procedures in a program.
+    pub fn merge_procs(&mut self, other: ProcedureAst) -> Result<(), ParsingError> {
+        if self.local_procs.iter().any(|proc| proc.name == other.name) {
+            return Err(ParsingError::duplicate_merged_proc_name(&other.name, other.start));
+        }
+
+        if self.local_procs.len() >= MAX_LOCAL_PROCS {
+            return Err(ParsingError::too_many_module_procs(
+                self.local_procs.len() + 1,
+                MAX_LOCAL_PROCS,
+            ));
+        }
+
+        self.local_procs.push(other);
+        Ok(())
+    }
+
+    /// Replaces the body of the local procedure with the specified name with `new_body`.
+    ///
+    /// The name, number of locals, docs, attributes, and the export flag of the procedure are
+    /// preserved. The source locations of the old body no longer apply, and thus the new body has
+    /// no source locations.
+    ///
+    /// # Errors
+    /// Returns an error if this program does not contain a local procedure with the specified
+    /// name.
+    pub fn replace_proc_body(
+        &mut self,
+        name: &str,
+        new_body: Vec<Node>,
+    ) -> Result<(), ParsingError> {
+        let proc = self
+            .local_procs
+            .iter_mut()
+            .find(|proc| proc.name.as_ref() == name)
+            .ok_or_else(|| ParsingError::undefined_replaced_proc(name))?;
+        proc.body = CodeBody::new(new_body);
+        Ok(())
+    }
+
+    /// Adds an import of the module at the specified `path` to this program.
+    ///
+    /// The module is imported under the last component of its path, as if it was imported via a
+    /// `use` statement.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - This program already imports a module under the same name.
+    /// - Adding the import would exceed the maximum number of imports in a program.
+    pub fn add_import(&mut self, path: LibraryPath) -> Result<(), ParsingError> {
+        if let Some(existing_path) = self.imports.get(path.last()) {
+            let statement = format!("{}.{}", Token::USE, path.as_str());
+            let token = Token::new(&statement, SourceLocation::default());
+            return Err(if *existing_path == path {
+                ParsingError::duplicate_module_import(&token, path.as_str())
+            } else {
+                ParsingError::conflicting_import_alias(&token, existing_path, path.as_str())
+            });
+        }
+
+        if self.imports.len() >= MAX_IMPORTS {
+            return Err(ParsingError::too_many_imports(self.imports.len() + 1, MAX_IMPORTS));
+        }
+
+        self.imports.insert(path.last().to_string(), path);
+        Ok(())
+    }
+
+    /// Removes the doc comments from all procedures of this program.
+    ///
+    /// Programs have no module or constant docs, but their procedures may carry docs if they were
+    /// provided via [Self::new], [Self::from_parts], or [Self::merge_procs].
+    pub fn strip_docs(&mut self) {
+        self.local_procs.iter_mut().for_each(|proc| proc.docs = None);
+    }
+
+    // STRUCTURAL COMPARISON
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns true if this program is structurally equal to the `other` program.
+    ///
+    /// Unlike `==`, this ignores source locations, and compares only the imports, the names,
+    /// number of locals, and bodies of local procedures, and the body of the program.
+    pub fn structural_eq(&self, other: &ProgramAst) -> bool {
+        self.diff(other).is_empty()
+    }
+
+    /// Returns the structural differences between this program and the `other` program.
+    ///
+    /// An empty vector is returned if the programs are structurally equal. For each differing
+    /// body, only the first diverging node is reported. See [Self::structural_eq] for details.
+    pub fn diff(&self, other: &ProgramAst) -> Vec<Difference> {
+        diff::diff_programs(self, other)
+    }
+
     // PUBLIC ACCESSORS
     // --------------------------------------------------------------------------------------------
 
@@ -127,6 +329,41 @@ impl ProgramAst {
         iter::once(&self.start).chain(self.body.source_locations().iter())
     }
 
+    /// Returns the [SourceLocation] of the `begin` token of this program.
+    pub fn body_start_location(&self) -> &SourceLocation {
+        &self.start
+    }
+
+    /// Returns all symbols defined in this program (i.e., its imports, constants, and internal
+    /// procedures), in this order.
+    ///
+    /// Symbols are reported at the location of their declaration. Constants are inlined into the
+    /// program when it is parsed, and thus are reported only if this program was parsed from
+    /// source; for other programs, imports are reported at the default location.
+    pub fn symbols(&self) -> Vec<Symbol> {
+        let declared = |name: &String, kind| {
+            self.declarations
+                .iter()
+                .find(|symbol| symbol.kind == kind && symbol.name == *name)
+        };
+        let imports = self.imports.keys().map(|name| {
+            let location = declared(name, SymbolKind::Import)
+                .map(|symbol| symbol.location)
+                .unwrap_or_default();
+            Symbol::new(name.clone(), SymbolKind::Import, location)
+        });
+        let constants = self
+            .declarations
+            .iter()
+            .filter(|symbol| symbol.kind == SymbolKind::Constant)
+            .cloned();
+        let procedures = self
+            .local_procs
+            .iter()
+            .map(|proc| Symbol::new(proc.name.to_string(), SymbolKind::Procedure, proc.start));
+        imports.chain(constants).chain(procedures).collect()
+    }
+
     /// Returns a slice over the internal procedures of this program.
     pub fn procedures(&self) -> &[ProcedureAst] {
         &self.local_procs
@@ -137,85 +374,279 @@ impl ProgramAst {
         &self.body
     }
 
+    /// Returns the distinct top-level namespaces of the modules imported by this program.
+    ///
+    /// For example, imports of `std::math::u64` and `std::collections::mmr` both belong to the
+    /// `std` namespace.
+    pub fn imported_namespaces(&self) -> BTreeSet<String> {
+        self.imports.values().map(|path| path.first().to_string()).collect()
+    }
+
+    /// Returns a map of modules imported by this program, keyed by the names under which they are
+    /// imported.
+    pub fn imports(&self) -> &BTreeMap<String, LibraryPath> {
+        &self.imports
+    }
+
+    /// Returns the top-level nodes of the body of this program.
+    pub fn body_nodes(&self) -> &[Node] {
+        self.body.nodes()
+    }
+
+    /// Returns the node of the body of this program at the specified index, or None if the body
+    /// contains fewer nodes.
+    ///
+    /// Nodes of nested control flow blocks are indexed as well; see [CodeBody::node_at] for the
+    /// indexing scheme.
+    pub fn node_at(&self, index: usize) -> Option<&Node> {
+        self.body.node_at(index)
+    }
+
+    /// Returns the number of top-level nodes in the body of this program.
+    ///
+    /// Nodes of nested control flow blocks are not counted.
+    pub fn num_body_nodes(&self) -> usize {
+        self.body.nodes().len()
+    }
+
+    /// Invokes `f` for the target of every `exec`, `call`, and `syscall` instruction of this
+    /// program, including the instructions of nested control flow blocks.
+    ///
+    /// The bodies of the local procedures are visited first, in their declaration order, followed
+    /// by the body of the program. Each body is walked via [walk_body], and thus `f` is invoked
+    /// once for every invocation instruction, in the order in which they appear in the source.
+    pub fn visit_calls(&self, f: impl FnMut(&CallTarget)) {
+        struct CallVisitor<F>(F);
+
+        impl<F: FnMut(&CallTarget)> Visitor for CallVisitor<F> {
+            fn visit_instruction(&mut self, instruction: &Instruction) {
+                if let Some(target) = CallTarget::from_instruction(instruction) {
+                    (self.0)(&target)
+                }
+            }
+        }
+
+        let mut visitor = CallVisitor(f);
+        for proc in self.local_procs.iter() {
+            walk_body(&proc.body, &mut visitor);
+        }
+        walk_body(&self.body, &mut visitor);
+    }
+
+    /// Returns the number of instructions in this program, including the instructions of all
+    /// local procedures.
+    ///
+    /// Instructions are counted via [CodeBody::instruction_count]: instructions of nested control
+    /// flow blocks are included, while the control flow blocks themselves are not counted. The
+    /// body of a procedure is counted once regardless of how many times the procedure is invoked,
+    /// and the bodies of `repeat` blocks are counted once regardless of the number of iterations.
+    pub fn instruction_count(&self) -> usize {
+        let procs_count =
+            self.local_procs.iter().map(|proc| proc.body.instruction_count()).sum::<usize>();
+        procs_count + self.body.instruction_count()
+    }
+
+    /// Returns the name and the instruction count of the internal procedure with the largest
+    /// number of instructions, or None if this program has no internal procedures.
+    ///
+    /// Instructions are counted via [CodeBody::instruction_count], and thus include instructions
+    /// of nested control flow blocks. If several procedures have the same size, the one declared
+    /// first is returned.
+    pub fn largest_procedure(&self) -> Option<(&str, usize)> {
+        self.local_procs
+            .iter()
+            .map(|proc| (proc.name.as_str(), proc.body.instruction_count()))
+            .reduce(|largest, proc| if proc.1 > largest.1 { proc } else { largest })
+    }
+
     // PARSER
     // --------------------------------------------------------------------------------------------
     /// Parses the provided source into a [ProgramAst].
     ///
     /// A program consist of a body and a set of internal (i.e., not exported) procedures.
     pub fn parse(source: &str) -> Result<ProgramAst, ParsingError> {
+        Self::parse_with_options(source, ParsingOptions::default()).map(|(program, _)| program)
+    }
+
+    /// Parses the provided source into a [ProgramAst] and returns it together with a list of
+    /// non-fatal diagnostics collected while parsing.
+    ///
+    /// This is equivalent to [Self::parse_with_options] with the default parsing options. Besides
+    /// indentation diagnostics, the warnings include imports which are never used and constants
+    /// which are never referenced.
+    pub fn parse_with_warnings(
+        source: &str,
+    ) -> Result<(ProgramAst, Vec<ParsingWarning>), ParsingError> {
+        Self::parse_with_options(source, ParsingOptions::default())
+    }
+
+    /// Parses the provided source into a [ProgramAst] using the specified parsing options.
+    ///
+    /// Returns the parsed program together with a list of non-fatal diagnostics collected while
+    /// parsing. Which conditions are reported as warnings rather than errors is controlled by
+    /// the `options`.
+    pub fn parse_with_options(
+        source: &str,
+        options: ParsingOptions,
+    ) -> Result<(ProgramAst, Vec<ParsingWarning>), ParsingError> {
         let mut tokens = TokenStream::new(source)?;
-        let imports = parse_imports(&mut tokens)?;
-        let local_constants = parse_constants(&mut tokens)?;
 
+        // indentation diagnostics are advisory only
+        let mut warnings = tokens
+            .mixed_indentation()
+            .iter()
+            .map(|location| ParsingWarning::mixed_indentation(*location))
+            .collect::<Vec<_>>();
+        let imports_start = tokens.pos();
+        // constant imports are not supported in programs, and thus fail to resolve
+        let (imports, imported_constants) =
+            parse_imports(&mut tokens, &options, &BTreeMap::new(), &mut warnings)?;
+
+        let mut symbols = collect_import_symbols(&mut tokens, imports_start)?;
+
+        let local_constants = parse_constants(&mut tokens, &imported_constants)?;
+        symbols.extend(local_constants.iter().map(|(name, (.., location))| {
+            Symbol::new(name.clone(), SymbolKind::Constant, *location)
+        }));
+
+        // conditionally compiled blocks are never enabled in programs
+        let features = BTreeSet::new();
         let mut context = ParserContext {
             imports: &imports,
             local_procs: LocalProcMap::default(),
             reexported_procs: ReExportedProcMap::default(),
             local_constants,
+            features: &features,
+            invoked_procs: Default::default(),
         };
 
         context.parse_procedures(&mut tokens, false)?;
 
-        // make sure program body is present
-        let next_token = tokens
-            .read()
-            .ok_or_else(|| ParsingError::unexpected_eof(*tokens.eof_location()))?;
-        if next_token.parts()[0] != Token::BEGIN {
-            return Err(ParsingError::unexpected_token(next_token, Token::BEGIN));
-        }
+        let (body, start) = parse_program_body(&context, &mut tokens)?;
+
+        // imports and constants which are never referenced are likely a mistake
+        warnings.extend(unused_symbol_warnings(&mut tokens, imports_start, &symbols));
 
-        let program_start = tokens.pos();
-        // consume the 'begin' token
-        let header = tokens.read().expect("missing program header");
-        let start = *header.location();
-        header.validate_begin()?;
-        tokens.advance();
+        let local_procs = sort_procs_into_vec(context.local_procs);
+        let ends = body.end_locations().to_vec();
+        let (nodes, locations) = body.into_parts();
+        let mut program = Self::new(nodes, local_procs, imports)?
+            .with_source_locations(locations, start)
+            .with_declarations(symbols);
+        program.body = program.body.with_end_locations(ends);
+        Ok((program, warnings))
+    }
 
-        // make sure there is something to be read
-        if tokens.eof() {
-            return Err(ParsingError::unexpected_eof(*tokens.eof_location()));
+    /// Parses the provided source into a [ProgramAst], recovering as much of the program as
+    /// possible if the source is incomplete or malformed.
+    ///
+    /// This is intended for editors, which need to parse the source while it is being typed. If
+    /// the source is a valid program, the program is returned without errors. Otherwise, parsing
+    /// resumes at the next procedure declaration after every error, and the returned program
+    /// contains only the procedures which were parsed completely, together with the program body
+    /// if it was parsed completely as well (or an empty body otherwise). A procedure with a body
+    /// which could not be parsed is never included in the program.
+    ///
+    /// The program is None if the source cannot be tokenized, or if its imports or constants
+    /// cannot be parsed.
+    pub fn parse_partial(source: &str) -> (Option<ProgramAst>, Vec<ParsingError>) {
+        // errors of the regular parser are reported again by the recovering parser below
+        if let Ok(program) = Self::parse(source) {
+            return (Some(program), Vec::new());
         }
 
-        // parse the sequence of nodes and add each node to the list
-        let body = context.parse_body(&mut tokens, false)?;
+        let mut tokens = match TokenStream::new(source) {
+            Ok(tokens) => tokens,
+            Err(err) => return (None, vec![err]),
+        };
+        let options = ParsingOptions::default();
+        let imports_start = tokens.pos();
+        let (imports, local_constants, mut symbols) =
+            match parse_imports(&mut tokens, &options, &BTreeMap::new(), &mut Vec::new()).and_then(
+                |(imports, imported_constants)| {
+                    let symbols = collect_import_symbols(&mut tokens, imports_start)?;
+                    parse_constants(&mut tokens, &imported_constants)
+                        .map(|constants| (imports, constants, symbols))
+                },
+            ) {
+                Ok(parsed) => parsed,
+                Err(err) => return (None, vec![err]),
+            };
+        symbols.extend(local_constants.iter().map(|(name, (.., location))| {
+            Symbol::new(name.clone(), SymbolKind::Constant, *location)
+        }));
+
+        let features = BTreeSet::new();
+        let mut context = ParserContext {
+            imports: &imports,
+            local_procs: LocalProcMap::default(),
+            reexported_procs: ReExportedProcMap::default(),
+            local_constants,
+            features: &features,
+            invoked_procs: Default::default(),
+        };
 
-        // consume the 'end' token
-        match tokens.read() {
-            None => Err(ParsingError::unmatched_begin(
-                tokens.read_at(program_start).expect("no begin token"),
-            )),
-            Some(token) => match token.parts()[0] {
-                Token::END => token.validate_end(),
-                Token::ELSE => Err(ParsingError::dangling_else(token)),
-                _ => Err(ParsingError::unmatched_begin(
-                    tokens.read_at(program_start).expect("no begin token"),
-                )),
-            },
-        }?;
-        tokens.advance();
-
-        // make sure there are no instructions after the end
-        if let Some(token) = tokens.read() {
-            return Err(ParsingError::dangling_ops_after_program(token));
+        // procedures are added to the context only once they are parsed completely
+        let mut errors = Vec::new();
+        let mut body = None;
+        loop {
+            if let Err(err) = context.parse_procedures(&mut tokens, false) {
+                errors.push(err);
+                skip_to_next_declaration(&mut tokens);
+                continue;
+            }
+            match tokens.read() {
+                Some(token) if token.parts()[0] != Token::BEGIN => {
+                    errors.push(ParsingError::unexpected_token(token, Token::BEGIN));
+                    skip_to_next_declaration(&mut tokens);
+                }
+                _ => {
+                    match parse_program_body(&context, &mut tokens) {
+                        Ok(parsed) => body = Some(parsed),
+                        Err(err) => errors.push(err),
+                    }
+                    break;
+                }
+            }
         }
 
         let local_procs = sort_procs_into_vec(context.local_procs);
+        let (body, start) = body.unwrap_or_default();
+        let ends = body.end_locations().to_vec();
         let (nodes, locations) = body.into_parts();
-        Ok(Self::new(nodes, local_procs, imports)?.with_source_locations(locations, start))
+        let program = Self::new(nodes, local_procs, imports).map(|program| {
+            let mut program =
+                program.with_source_locations(locations, start).with_declarations(symbols);
+            program.body = program.body.with_end_locations(ends);
+            program
+        });
+        match program {
+            Ok(program) => (Some(program), errors),
+            Err(err) => {
+                errors.push(err);
+                (None, errors)
+            }
+        }
+    }
+
+    /// Parses the provided source into a lossless concrete syntax tree.
+    ///
+    /// The source is first parsed into a [ProgramAst] to make sure it is a valid program, and any
+    /// errors encountered are returned. Printing the resulting [Cst] reproduces the source
+    /// exactly.
+    pub fn parse_cst(source: &str) -> Result<Cst<'_>, ParsingError> {
+        Self::parse(source)?;
+        Ok(Cst::new(source))
     }
 
     // SERIALIZATION / DESERIALIZATION
     // --------------------------------------------------------------------------------------------
 
-    /// Returns byte representation of this [ProgramAst].
+    /// Writes byte representation of this [ProgramAst] into the specified target.
     ///
-    /// The serde options are serialized as header information for the purposes of deserialization.
-    pub fn to_bytes(&self, options: AstSerdeOptions) -> Vec<u8> {
-        let mut target = Vec::<u8>::default();
-
-        // serialize the options, so that deserialization knows what to do
-        options.write_into(&mut target);
-
+    /// The serde options are NOT serialized - the caller must keep track of the serialization
+    /// options used.
+    pub fn write_into<W: ByteWriter>(&self, target: &mut W, options: AstSerdeOptions) {
         // asserts below are OK because we enforce limits on the number of procedure and the
         // number of body instructions in relevant parsers
 
@@ -225,17 +656,59 @@ impl ProgramAst {
             // We don't need to serialize the library names (the keys),
             // since the libraty paths (the values) contain the library
             // names
-            self.imports.values().for_each(|path| path.write_into(&mut target));
+            self.imports.values().for_each(|path| path.write_into(target));
         }
 
         assert!(self.local_procs.len() <= MAX_LOCAL_PROCS, "too many local procs");
         target.write_u16(self.local_procs.len() as u16);
-        self.local_procs.write_into(&mut target);
+        self.local_procs
+            .iter()
+            .for_each(|proc| proc.write_with_options(target, options));
+
+        self.body.write_nodes(target);
+    }
+
+    /// Returns a [ProgramAst] struct deserialized from the provided source.
+    ///
+    /// The serde options must correspond to the options used for serialization.
+    pub fn read_from<R: ByteReader>(
+        source: &mut R,
+        options: AstSerdeOptions,
+    ) -> Result<Self, DeserializationError> {
+        let mut imports = BTreeMap::<String, LibraryPath>::new();
+        if options.serialize_imports {
+            let num_imports = source.read_u16()? as usize;
+            let paths: Vec<LibraryPath> =
+                read_batch_checked(source, num_imports, "imports", LibraryPath::read_from)?;
+            for path in paths {
+                imports.insert(path.last().to_string(), path);
+            }
+        }
 
-        assert!(self.body.nodes().len() <= MAX_BODY_LEN, "too many body instructions");
-        target.write_u16(self.body.nodes().len() as u16);
-        self.body.nodes().write_into(&mut target);
+        let num_local_procs = source.read_u16()? as usize;
+        let local_procs =
+            read_batch_checked(source, num_local_procs, "local procedures", |source| {
+                ProcedureAst::read_with_options(source, options)
+            })?;
 
+        let body_len = source.read_u16()? as usize;
+        let nodes = read_batch_checked(source, body_len, "body nodes", Node::read_from)?;
+        match Self::new(nodes, local_procs, imports) {
+            Err(err) => Err(DeserializationError::UnknownError(err.message().clone())),
+            Ok(res) => Ok(res),
+        }
+    }
+
+    /// Returns byte representation of this [ProgramAst].
+    ///
+    /// The serde options are serialized as header information for the purposes of deserialization.
+    pub fn to_bytes(&self, options: AstSerdeOptions) -> Vec<u8> {
+        let mut target = Vec::<u8>::default();
+
+        // serialize the options, so that deserialization knows what to do
+        options.write_into(&mut target);
+
+        self.write_into(&mut target, options);
         target
     }
 
@@ -249,24 +722,34 @@ impl ProgramAst {
         // Deserialize the serialization options used when serializing
         let options = AstSerdeOptions::read_from(&mut source)?;
 
-        let mut imports = BTreeMap::<String, LibraryPath>::new();
-        if options.serialize_imports {
-            let num_imports = source.read_u16()?;
-            for _ in 0..num_imports {
-                let path = LibraryPath::read_from(&mut source)?;
-                imports.insert(path.last().to_string(), path);
-            }
-        }
+        Self::read_from(&mut source, options)
+    }
 
-        let num_local_procs = source.read_u16()?;
-        let local_procs = Deserializable::read_batch_from(&mut source, num_local_procs as usize)?;
+    /// Returns byte representation of this [ProgramAst] followed by its source locations.
+    ///
+    /// The serde options are serialized as header information for the purposes of deserialization.
+    /// The source locations are appended after the program via
+    /// [ProgramAst::write_source_locations], and thus the prefix of the returned bytes can still be
+    /// read via [ProgramAst::from_bytes].
+    pub fn to_bytes_with_locations(&self, options: AstSerdeOptions) -> Vec<u8> {
+        let mut target = self.to_bytes(options);
+        self.write_source_locations(&mut target, options);
+        target
+    }
 
-        let body_len = source.read_u16()? as usize;
-        let nodes = Deserializable::read_batch_from(&mut source, body_len)?;
-        match Self::new(nodes, local_procs, imports) {
-            Err(err) => Err(DeserializationError::UnknownError(err.message().clone())),
-            Ok(res) => Ok(res),
-        }
+    /// Returns a [ProgramAst] struct deserialized from the provided bytes, together with its
+    /// source locations.
+    ///
+    /// This function assumes that the bytes were produced by [ProgramAst::to_bytes_with_locations].
+    pub fn from_bytes_with_locations(bytes: &[u8]) -> Result<Self, DeserializationError> {
+        let mut source = SliceReader::new(bytes);
+
+        // Deserialize the serialization options used when serializing
+        let options = AstSerdeOptions::read_from(&mut source)?;
+
+        let mut program = Self::read_from(&mut source, options)?;
+        program.load_source_locations(&mut source, options)?;
+        Ok(program)
     }
 
     /// Loads the [SourceLocation] from the `source`.
@@ -274,13 +757,18 @@ impl ProgramAst {
     /// It expects the `start` location at the first position, and will subsequently load the
     /// body via [CodeBody::load_source_locations]. Finally, it will load the local procedures via
     /// [ProcedureAst::load_source_locations].
+    ///
+    /// The serde options must correspond to the options used to write the locations.
     pub fn load_source_locations<R: ByteReader>(
         &mut self,
         source: &mut R,
+        options: AstSerdeOptions,
     ) -> Result<(), DeserializationError> {
         self.start = SourceLocation::read_from(source)?;
-        self.body.load_source_locations(source)?;
-        self.local_procs.iter_mut().try_for_each(|p| p.load_source_locations(source))
+        self.body.load_source_locations(source, options)?;
+        self.local_procs
+            .iter_mut()
+            .try_for_each(|p| p.load_source_locations(source, options))
     }
 
     /// Writes the [SourceLocation] into `target`.
@@ -288,10 +776,10 @@ impl ProgramAst {
     /// It will write the `start` location, and then execute the body serialization via
     /// [CodeBlock::write_source_locations]. Finally, it will write the local procedures via
     /// [ProcedureAst::write_source_locations].
-    pub fn write_source_locations<W: ByteWriter>(&self, target: &mut W) {
+    pub fn write_source_locations<W: ByteWriter>(&self, target: &mut W, options: AstSerdeOptions) {
         self.start.write_into(target);
-        self.body.write_source_locations(target);
-        self.local_procs.iter().for_each(|p| p.write_source_locations(target))
+        self.body.write_source_locations(target, options);
+        self.local_procs.iter().for_each(|p| p.write_source_locations(target, options))
     }
 
     // DESTRUCTURING
@@ -301,6 +789,57 @@ impl ProgramAst {
     pub fn into_parts(self) -> (Vec<ProcedureAst>, Vec<Node>) {
         (self.local_procs, self.body.into_parts().0)
     }
+
+    /// Returns all parts of this program, including the source locations of its body and the
+    /// declarations of its imports and constants.
+    ///
+    /// The program can be rebuilt from these parts via [Self::from_program_parts].
+    pub fn into_program_parts(self) -> ProgramParts {
+        ProgramParts {
+            imports: self.imports,
+            local_procs: self.local_procs,
+            body: self.body,
+            start: self.start,
+            declarations: self.declarations,
+        }
+    }
+}
+
+/// Two programs are equal if they have the same imports, local procedures, and body.
+///
+/// The declarations of imports and constants are collected only when a program is parsed from
+/// source, and thus are not compared; otherwise, a parsed program would never be equal to the
+/// same program read back from bytes.
+impl PartialEq for ProgramAst {
+    fn eq(&self, other: &Self) -> bool {
+        self.body == other.body
+            && self.local_procs == other.local_procs
+            && self.imports == other.imports
+            && self.start == other.start
+    }
+}
+
+impl Eq for ProgramAst {}
+
+// PROGRAM PARTS
+// ================================================================================================
+
+/// All parts of a [ProgramAst], as returned by [ProgramAst::into_program_parts].
+///
+/// Unlike the parts returned by [ProgramAst::into_parts], these include everything needed to
+/// rebuild the program via [ProgramAst::from_program_parts] without losing any information.
+#[derive(Debug, Clone)]
+pub struct ProgramParts {
+    /// Modules imported by the program, keyed by the names under which they are imported.
+    pub imports: BTreeMap<String, LibraryPath>,
+    /// Internal procedures of the program.
+    pub local_procs: Vec<ProcedureAst>,
+    /// Body of the program, together with its source locations.
+    pub body: CodeBody,
+    /// Location of the `begin` token of the program.
+    pub start: SourceLocation,
+    /// Imports and constants declared in the source of the program.
+    pub declarations: Vec<Symbol>,
 }
 
 // MODULE AST
@@ -310,15 +849,28 @@ impl ProgramAst {
 ///
 /// A module AST consists of a list of imports, a list of procedure ASTs, a list of re-exported
 /// procedures and module documentation. Local procedures could be internal or exported.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub struct ModuleAst {
     local_procs: Vec<ProcedureAst>,
     reexported_procs: Vec<ProcReExport>,
     imports: BTreeMap<String, LibraryPath>,
     docs: Option<String>,
+    constants: BTreeMap<String, u64>,
+    const_docs: BTreeMap<String, String>,
+    /// Names and module paths of the imported and kernel procedures invoked or re-exported in the
+    /// source of this module, keyed by procedure ID; kernel procedures are located at
+    /// [LibraryPath::kernel_path]. These are collected only when the module is parsed from source,
+    /// and are neither serialized nor compared.
+    invoked_procs: InvokedProcsMap,
 }
 
 impl ModuleAst {
+    // CONSTANTS
+    // --------------------------------------------------------------------------------------------
+
+    /// Marker of a line of module docs which starts a new docs section, e.g. `#! @section safety`.
+    pub const DOC_SECTION_MARKER: &'static str = "@section";
+
     // AST
     // --------------------------------------------------------------------------------------------
     /// Returns a new [ModuleAst].
@@ -343,8 +895,8 @@ impl ModuleAst {
             ));
         }
         if let Some(ref docs) = docs {
-            if docs.len() > MAX_DOCS_LEN {
-                return Err(ParsingError::module_docs_too_long(docs.len(), MAX_DOCS_LEN));
+            if docs.len() > MAX_MODULE_DOCS_LEN {
+                return Err(ParsingError::module_docs_too_long(docs.len(), MAX_MODULE_DOCS_LEN));
             }
         }
         Ok(Self {
@@ -352,24 +904,137 @@ impl ModuleAst {
             reexported_procs,
             imports,
             docs,
+            constants: BTreeMap::new(),
+            const_docs: BTreeMap::new(),
+            invoked_procs: InvokedProcsMap::new(),
         })
     }
 
+    /// Sets the values of the constants declared in this module.
+    ///
+    /// The `constants` map is keyed by constant name.
+    ///
+    /// # Panics
+    /// Panics if there are more constants than can be serialized.
+    pub fn with_constants(mut self, constants: BTreeMap<String, u64>) -> Self {
+        assert!(constants.len() <= u16::MAX as usize, "too many constants");
+        self.constants = constants;
+        self
+    }
+
+    /// Sets the doc comments of the constants declared in this module.
+    ///
+    /// The `const_docs` map is keyed by constant name.
+    ///
+    /// # Panics
+    /// Panics if doc comments of any constant exceed the allowed limit.
+    pub fn with_const_docs(mut self, const_docs: BTreeMap<String, String>) -> Self {
+        assert!(
+            const_docs.values().all(|docs| docs.len() <= MAX_DOCS_LEN),
+            "constant docs too long"
+        );
+        self.const_docs = const_docs;
+        self
+    }
+
+    /// Records the names and module paths of the imported and kernel procedures which are
+    /// invoked or re-exported in the source of this module.
+    fn with_invoked_procs(mut self, invoked_procs: InvokedProcsMap) -> Self {
+        self.invoked_procs = invoked_procs;
+        self
+    }
+
     // PARSER
     // --------------------------------------------------------------------------------------------
     /// Parses the provided source into a [ModuleAst].
     ///
     /// A module consists of internal and exported procedures but does not contain a body.
     pub fn parse(source: &str) -> Result<Self, ParsingError> {
+        Self::parse_with_options(source, ParsingOptions::default()).map(|(module, _)| module)
+    }
+
+    /// Parses the provided source into a [ModuleAst] using the specified parsing options.
+    ///
+    /// Returns the parsed module together with a list of non-fatal diagnostics collected while
+    /// parsing. In addition to the diagnostics reported for programs, exported procedures which
+    /// contain no instructions are reported as warnings.
+    pub fn parse_with_options(
+        source: &str,
+        options: ParsingOptions,
+    ) -> Result<(Self, Vec<ParsingWarning>), ParsingError> {
+        Self::parse_with_imported_constants(source, options, &BTreeMap::new())
+    }
+
+    /// Parses the provided source into a [ModuleAst] using the specified parsing options, and
+    /// resolves constant imports against the provided modules.
+    ///
+    /// Constants are imported via `use.const.<module path>` statements, which make all constants
+    /// declared in the module at the specified path available in the parsed module. The modules
+    /// are looked up in `const_modules` by their paths. Imported constants are not re-exported
+    /// from the parsed module, i.e., they are not included in [ModuleAst::constants].
+    ///
+    /// # Errors
+    /// In addition to the errors returned by [ModuleAst::parse_with_options], returns an error
+    /// if:
+    /// - A module from which constants are imported is not present in `const_modules`.
+    /// - Constants imported from different modules have the same name.
+    /// - A constant declared in the parsed module has the same name as an imported constant.
+    pub fn parse_with_imported_constants(
+        source: &str,
+        options: ParsingOptions,
+        const_modules: &BTreeMap<LibraryPath, ModuleAst>,
+    ) -> Result<(Self, Vec<ParsingWarning>), ParsingError> {
+        Self::parse_internal(source, options, const_modules, &BTreeSet::new())
+    }
+
+    /// Parses the provided source into a [ModuleAst] using the specified parsing options, and
+    /// includes the conditionally compiled blocks guarded by the specified `features`.
+    ///
+    /// Procedures can be compiled conditionally by wrapping them in a block of the form
+    /// `@cfg.<feature> begin ... end`. The procedures in such a block are included in the module
+    /// only if `<feature>` is present in `features`; otherwise, the block is skipped entirely
+    /// and produces no procedures. Blocks guarded by features which are not known to the caller
+    /// are thus always left out. Blocks can be nested, in which case procedures of the inner
+    /// block are included only if features of both blocks are enabled.
+    ///
+    /// Parsing via any other method of [ModuleAst] or [ProgramAst] is equivalent to parsing
+    /// with no enabled features.
+    pub fn parse_with_features(
+        source: &str,
+        options: ParsingOptions,
+        features: &BTreeSet<String>,
+    ) -> Result<(Self, Vec<ParsingWarning>), ParsingError> {
+        Self::parse_internal(source, options, &BTreeMap::new(), features)
+    }
+
+    /// Parses the provided source into a [ModuleAst] resolving constant imports against the
+    /// provided `const_modules` and enabling conditionally compiled blocks guarded by the
+    /// provided `features`.
+    fn parse_internal(
+        source: &str,
+        options: ParsingOptions,
+        const_modules: &BTreeMap<LibraryPath, ModuleAst>,
+        features: &BTreeSet<String>,
+    ) -> Result<(Self, Vec<ParsingWarning>), ParsingError> {
         let mut tokens = TokenStream::new(source)?;
 
-        let imports = parse_imports(&mut tokens)?;
-        let local_constants = parse_constants(&mut tokens)?;
+        // indentation diagnostics are advisory only
+        let mut warnings = tokens
+            .mixed_indentation()
+            .iter()
+            .map(|location| ParsingWarning::mixed_indentation(*location))
+            .collect::<Vec<_>>();
+
+        let (imports, imported_constants) =
+            parse_imports(&mut tokens, &options, const_modules, &mut warnings)?;
+        let local_constants = parse_constants(&mut tokens, &imported_constants)?;
         let mut context = ParserContext {
             imports: &imports,
             local_procs: LocalProcMap::default(),
             reexported_procs: ReExportedProcMap::default(),
             local_constants,
+            features,
+            invoked_procs: Default::default(),
         };
         context.parse_procedures(&mut tokens, true)?;
 
@@ -388,10 +1053,88 @@ impl ModuleAst {
         // build a list of re-exported procedures sorted by procedure name
         let reexported_procs = context.reexported_procs.into_values().collect();
 
+        // exported procedures without instructions are likely a mistake
+        warnings.extend(
+            local_procs
+                .iter()
+                .filter(|proc| proc.is_empty_export())
+                .map(|proc| ParsingWarning::empty_exported_proc(&proc.name, proc.start)),
+        );
+
+        // collect values and doc comments of the constants declared in this module
+        let mut constants = BTreeMap::new();
+        let mut const_docs = BTreeMap::new();
+        for (name, (value, docs, _)) in context.local_constants {
+            if imported_constants.contains_key(&name) {
+                continue;
+            }
+            if let Some(docs) = docs {
+                const_docs.insert(name.clone(), docs);
+            }
+            constants.insert(name, value);
+        }
+
         // get module docs and make sure the size is within the limit
         let docs = tokens.take_module_comments();
 
-        Self::new(local_procs, reexported_procs, imports, docs)
+        let invoked_procs = context.invoked_procs.into_inner();
+        let module = Self::new(local_procs, reexported_procs, imports, docs)?
+            .with_constants(constants)
+            .with_const_docs(const_docs)
+            .with_invoked_procs(invoked_procs);
+        Ok((module, warnings))
+    }
+
+    /// Parses the provided source into a map of constants, where each constant name is mapped to
+    /// its value and the location of its declaration.
+    ///
+    /// This is intended for shared configuration files which consist of `const` declarations
+    /// only.
+    ///
+    /// # Errors
+    /// Returns an error if parsing of any of the constants fails, or if the source contains
+    /// anything other than constant declarations (e.g., imports, procedures, or a program body).
+    pub fn parse_constants_only(
+        source: &str,
+    ) -> Result<BTreeMap<String, (u64, SourceLocation)>, ParsingError> {
+        let mut tokens = TokenStream::new(source)?;
+        let constants = parse_constants(&mut tokens, &LocalConstMap::new())?;
+
+        // make sure there is nothing besides constant declarations
+        if let Some(token) = tokens.read() {
+            return Err(ParsingError::unexpected_token(token, Token::CONST));
+        }
+
+        Ok(constants
+            .into_iter()
+            .map(|(name, (value, _, location))| (name, (value, location)))
+            .collect())
+    }
+
+    /// Renders this module into canonical Miden assembly source.
+    ///
+    /// Parsing the returned source via [ModuleAst::parse] produces a module equal to this one,
+    /// save for the source locations and the docs of non-exported procedures, which are not
+    /// retained by the parser and thus are not emitted. The source is canonical: imports,
+    /// constants, re-exported procedures, and local procedures are emitted in this order, with
+    /// constants and re-exported procedures sorted by name and local procedures in their
+    /// declaration order. Constants are emitted with their resolved values, and docs are emitted
+    /// as blocks of `#!` lines with surrounding whitespace and empty lines removed, as done by the
+    /// parser.
+    ///
+    /// Imported procedures are identified in the AST only by their IDs, and are rendered as
+    /// `<alias>::<name>` using the names recorded when this module was parsed and the import
+    /// aliases of this module.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - The module invokes or re-exports procedures whose names are not known (e.g., because the
+    ///   module was not parsed from source, or does not import the module of a procedure).
+    /// - The module invokes a local procedure which does not exist.
+    /// - The module has no imports, constants, or procedures, as such a module has no source
+    ///   representation.
+    pub fn to_source(&self) -> Result<String, RenderError> {
+        source::render_module(self)
     }
 
     // PUBLIC ACCESSORS
@@ -402,19 +1145,287 @@ impl ModuleAst {
         &self.local_procs
     }
 
-    /// Returns a list of re-exported procedures in this module.
-    pub fn reexported_procs(&self) -> &[ProcReExport] {
-        &self.reexported_procs
+    /// Returns the local procedure with the specified name, or None if this module does not
+    /// contain a local procedure with such name.
+    ///
+    /// Procedures are looked up via a linear scan, and thus this takes O(n) time in the number of
+    /// local procedures.
+    pub fn get_proc(&self, name: &str) -> Option<&ProcedureAst> {
+        self.local_procs.iter().find(|proc| proc.name.as_ref() == name)
+    }
+
+    /// Returns the index of the local procedure with the specified name, or None if this module
+    /// does not contain a local procedure with such name.
+    ///
+    /// Procedures are looked up via a linear scan, and thus this takes O(n) time in the number of
+    /// local procedures.
+    pub fn get_proc_index(&self, name: &str) -> Option<usize> {
+        self.local_procs.iter().position(|proc| proc.name.as_ref() == name)
+    }
+
+    /// Returns a list of procedures in this module together with their fully-qualified paths,
+    /// assuming this module is located at `module_path`.
+    ///
+    /// Procedures are listed in the order of their declaration. Procedures for which the
+    /// qualified path is not a valid [LibraryPath] (e.g., because it is too long) are skipped.
+    pub fn qualified_procedures(
+        &self,
+        module_path: &LibraryPath,
+    ) -> Vec<(LibraryPath, &ProcedureAst)> {
+        self.local_procs
+            .iter()
+            .filter_map(|proc| module_path.append(&proc.name).ok().map(|path| (path, proc)))
+            .collect()
+    }
+
+    /// Returns the names and IDs of all procedures in this module, assuming this module is located
+    /// at `module_path`.
+    ///
+    /// Local procedures are listed first in the order of their declaration, followed by the
+    /// re-exported procedures sorted by name. IDs are derived from procedure names via
+    /// [ProcedureName::to_id]; for re-exported procedures, this is the ID of the alias rather than
+    /// the ID of the procedure it refers to.
+    pub fn procedure_ids(&self, module_path: &LibraryPath) -> Vec<(ProcedureName, ProcedureId)> {
+        let local_names = self.local_procs.iter().map(|proc| &proc.name);
+        let reexported_names = self.reexported_procs.iter().map(|proc| proc.name());
+        local_names
+            .chain(reexported_names)
+            .map(|name| (name.clone(), name.to_id(module_path)))
+            .collect()
+    }
+
+    /// Returns a list of re-exported procedures in this module.
+    pub fn reexported_procs(&self) -> &[ProcReExport] {
+        &self.reexported_procs
+    }
+
+    /// Returns an iterator over the names of the procedures re-exported from this module paired
+    /// with the IDs of the procedures they refer to.
+    pub fn reexports(&self) -> impl Iterator<Item = (&ProcedureName, ProcedureId)> + '_ {
+        self.reexported_procs.iter().map(|proc| (proc.name(), proc.proc_id()))
+    }
+
+    /// Returns doc comments for this module.
+    pub fn docs(&self) -> Option<&String> {
+        self.docs.as_ref()
+    }
+
+    /// Returns the doc comments for this module split into sections, as pairs of section names
+    /// and section contents.
+    ///
+    /// A section starts with a line of the form `@section <name>` (i.e., `#! @section <name>` in
+    /// the source), and extends until the next such line or the end of the docs. The docs
+    /// preceding the first section, or the whole docs if there are no section markers, form a
+    /// default section with an empty name; the default section is omitted if it is empty.
+    ///
+    /// The section markers are retained in the combined docs returned by [ModuleAst::docs], and
+    /// thus sections are preserved through serialization.
+    pub fn doc_sections(&self) -> Vec<(String, String)> {
+        let docs = match &self.docs {
+            Some(docs) => docs,
+            None => return Vec::new(),
+        };
+
+        // the default section collects the docs preceding the first section marker
+        let mut sections = vec![(String::new(), Vec::new())];
+        for line in docs.lines() {
+            let section_name = line
+                .trim()
+                .strip_prefix(Self::DOC_SECTION_MARKER)
+                .filter(|rest| rest.starts_with(char::is_whitespace))
+                .map(str::trim)
+                .filter(|rest| !rest.is_empty());
+            match section_name {
+                Some(name) => sections.push((name.to_string(), Vec::new())),
+                None => sections.last_mut().expect("no docs section").1.push(line),
+            }
+        }
+
+        sections
+            .into_iter()
+            .map(|(name, lines)| (name, lines.join("\n").trim().to_string()))
+            .filter(|(name, contents)| !name.is_empty() || !contents.is_empty())
+            .collect()
+    }
+
+    /// Returns a map of imported modules in this module.
+    pub fn imports(&self) -> &BTreeMap<String, LibraryPath> {
+        &self.imports
+    }
+
+    /// Returns the full path of the module imported under the specified alias, or None if no
+    /// module is imported under this alias.
+    ///
+    /// The alias of an imported module is the last component of its path (e.g., `u64` for
+    /// `std::math::u64`).
+    pub fn resolve_import(&self, alias: &str) -> Option<&LibraryPath> {
+        self.imports.get(alias)
+    }
+
+    /// Returns the values of the constants declared in this module, keyed by constant name.
+    pub fn constants(&self) -> &BTreeMap<String, u64> {
+        &self.constants
+    }
+
+    /// Returns doc comments of the constants declared in this module, keyed by constant name.
+    ///
+    /// Constants without doc comments are not included in the map.
+    pub fn const_docs(&self) -> &BTreeMap<String, String> {
+        &self.const_docs
+    }
+
+    // TRANSFORMATIONS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns this module with all internal (i.e., not exported) procedures removed, except for
+    /// the ones which can be invoked from the exported procedures.
+    ///
+    /// Internal procedures invoked via `exec` or `call` from an exported procedure, or from
+    /// another retained procedure, are required to compile the module, and thus are retained with
+    /// their docs removed. The order of the retained procedures, as well as re-exported
+    /// procedures, imports, and docs of the module are preserved. Local invocations (e.g.,
+    /// `exec.foo`) are updated to reflect the new procedure indexes.
+    ///
+    /// This is intended for publishing the interface of a module.
+    pub fn strip_internal(mut self) -> Self {
+        let reachable = self.reachable_procs();
+
+        // map indexes of the retained procedures to their indexes in the stripped module
+        let index_map = reachable
+            .iter()
+            .enumerate()
+            .filter(|(_, reachable)| **reachable)
+            .enumerate()
+            .map(|(new_idx, (old_idx, _))| (old_idx as u16, new_idx as u16))
+            .collect::<BTreeMap<_, _>>();
+
+        self.local_procs = self
+            .local_procs
+            .into_iter()
+            .zip(reachable)
+            .filter_map(|(proc, reachable)| reachable.then_some(proc))
+            .collect();
+        for proc in self.local_procs.iter_mut() {
+            if !proc.is_export {
+                proc.docs = None;
+            }
+            proc.body.visit_mut(&mut |node| {
+                if let Node::Instruction(
+                    Instruction::ExecLocal(idx) | Instruction::CallLocal(idx),
+                ) = node
+                {
+                    if let Some(new_idx) = index_map.get(idx) {
+                        *idx = *new_idx;
+                    }
+                }
+            });
+        }
+        self
+    }
+
+    /// Returns the internal procedures of this module which cannot be invoked from any of the
+    /// exported procedures, in their declaration order.
+    ///
+    /// A procedure is considered reachable if it is invoked via `exec` or `call` from an exported
+    /// procedure, or from another reachable procedure.
+    pub fn unreachable_procs(&self) -> Vec<&ProcedureAst> {
+        self.local_procs
+            .iter()
+            .zip(self.reachable_procs())
+            .filter(|(_, reachable)| !reachable)
+            .map(|(proc, _)| proc)
+            .collect()
     }
 
-    /// Returns doc comments for this module.
-    pub fn docs(&self) -> Option<&String> {
-        self.docs.as_ref()
-    }
+    /// Returns this module with the bodies of small internal procedures inlined into the places
+    /// where these procedures are invoked via `exec`.
+    ///
+    /// An internal procedure is inlined if its body, after inlining the procedures it invokes,
+    /// consists of at most `max_nodes` nodes, including the nodes of nested control flow blocks.
+    /// The locals of an inlined procedure are renumbered to follow the locals of the invoking
+    /// procedure, whose number of locals is increased accordingly. Invocations via `call` are
+    /// never inlined, as they execute the procedure in a new context.
+    ///
+    /// Inlined procedures which are no longer invoked are removed, and local invocations are
+    /// updated to reflect the new procedure indexes. Exported procedures are always preserved.
+    pub fn inline_small_procs(mut self, max_nodes: usize) -> Self {
+        // a procedure can invoke only procedures declared before it, and thus inlining in the
+        // declaration order takes care of nested invocations
+        let mut inlinable = vec![false; self.local_procs.len()];
+        for idx in 0..self.local_procs.len() {
+            let (preceding, remaining) = self.local_procs.split_at_mut(idx);
+            let proc = &mut remaining[0];
+            let base = proc.num_locals;
+            let mut num_locals = proc.num_locals;
+            proc.body.inline_execs(&mut |callee_idx| {
+                let callee = preceding
+                    .get(callee_idx as usize)
+                    .filter(|_| inlinable[callee_idx as usize])?;
+                num_locals = num_locals.max(base.checked_add(callee.num_locals)?);
+                let mut body = callee.body.clone();
+                body.visit_mut(&mut |node| {
+                    if let Node::Instruction(
+                        Instruction::Locaddr(index)
+                        | Instruction::LocLoad(index)
+                        | Instruction::LocLoadW(index)
+                        | Instruction::LocStore(index)
+                        | Instruction::LocStoreW(index),
+                    ) = node
+                    {
+                        *index = index.saturating_add(base);
+                    }
+                });
+                Some(body)
+            });
+            proc.num_locals = num_locals;
+
+            let mut num_nodes = 0;
+            proc.body.visit(&mut |_| num_nodes += 1);
+            inlinable[idx] = !proc.is_export && num_nodes > 0 && num_nodes <= max_nodes;
+        }
 
-    /// Returns a map of imported modules in this module.
-    pub fn imports(&self) -> &BTreeMap<String, LibraryPath> {
-        &self.imports
+        // remove the inlined procedures which are not invoked anymore
+        let mut invoked = vec![false; self.local_procs.len()];
+        for proc in self.local_procs.iter() {
+            proc.body.visit(&mut |node| {
+                if let Node::Instruction(
+                    Instruction::ExecLocal(idx) | Instruction::CallLocal(idx),
+                ) = node
+                {
+                    if let Some(invoked) = invoked.get_mut(*idx as usize) {
+                        *invoked = true;
+                    }
+                }
+            });
+        }
+        let retained = inlinable
+            .iter()
+            .zip(invoked.iter())
+            .map(|(inlined, invoked)| !inlined || *invoked)
+            .collect::<Vec<_>>();
+        let index_map = retained
+            .iter()
+            .enumerate()
+            .filter(|(_, retained)| **retained)
+            .enumerate()
+            .map(|(new_idx, (old_idx, _))| (old_idx as u16, new_idx as u16))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut retained = retained.into_iter();
+        self.local_procs.retain(|_| retained.next().unwrap_or(true));
+        for proc in self.local_procs.iter_mut() {
+            proc.body.visit_mut(&mut |node| {
+                if let Node::Instruction(
+                    Instruction::ExecLocal(idx) | Instruction::CallLocal(idx),
+                ) = node
+                {
+                    if let Some(new_idx) = index_map.get(idx) {
+                        *idx = *new_idx;
+                    }
+                }
+            });
+        }
+        self
     }
 
     // STATE MUTATORS
@@ -425,6 +1436,51 @@ impl ModuleAst {
         self.local_procs.iter_mut().for_each(|p| p.clear_locations())
     }
 
+    /// Removes the module doc comments as well as the doc comments of all procedures and
+    /// constants of this module.
+    pub fn strip_docs(&mut self) {
+        self.docs = None;
+        self.const_docs.clear();
+        self.local_procs.iter_mut().for_each(|proc| proc.docs = None);
+    }
+
+    /// Adds the imports of the `other` module to the imports of this module.
+    ///
+    /// Imports of the same module under the same name are deduplicated. This module is left
+    /// unchanged if an error is returned.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - The modules import different modules under the same name.
+    /// - The merged imports would exceed the maximum number of imports in a module.
+    pub fn merge_imports(&mut self, other: &ModuleAst) -> Result<(), ParsingError> {
+        let mut num_imports = self.imports.len();
+        for (alias, path) in other.imports.iter() {
+            match self.imports.get(alias) {
+                Some(existing_path) if existing_path != path => {
+                    let statement = format!("{}.{}", Token::USE, path.as_str());
+                    let token = Token::new(&statement, SourceLocation::default());
+                    return Err(ParsingError::conflicting_import_alias(
+                        &token,
+                        existing_path,
+                        path.as_str(),
+                    ));
+                }
+                Some(_) => {}
+                None => num_imports += 1,
+            }
+        }
+
+        if num_imports > MAX_IMPORTS {
+            return Err(ParsingError::too_many_imports(num_imports, MAX_IMPORTS));
+        }
+
+        for (alias, path) in other.imports.iter() {
+            self.imports.entry(alias.clone()).or_insert_with(|| path.clone());
+        }
+        Ok(())
+    }
+
     // SERIALIZATION / DESERIALIZATION
     // --------------------------------------------------------------------------------------------
 
@@ -432,13 +1488,22 @@ impl ModuleAst {
     ///
     /// The serde options are NOT serialized - the caller must keep track of the serialization
     /// options used.
+    ///
+    /// # Panics
+    /// Panics if the module docs are longer than `u16::MAX` bytes and the module is written in
+    /// format version V0, which cannot hold such docs.
     pub fn write_into<R: ByteWriter>(&self, target: &mut R, options: AstSerdeOptions) {
         // asserts below are OK because we enforce limits on the number of procedure and length of
-        // module docs in the module parser
+        // module docs in the module parser; only the unversioned format has a lower docs limit
 
         match &self.docs {
+            Some(docs) if options.version >= AstFormatVersion::V1 => {
+                assert!(docs.len() <= MAX_MODULE_DOCS_LEN, "docs too long");
+                write_docs_chunked(target, docs);
+            }
+            // the unversioned format supports only docs which fit into a single u16-length chunk
             Some(docs) => {
-                assert!(docs.len() <= u16::MAX as usize, "docs too long");
+                assert!(docs.len() <= MAX_DOCS_LEN, "docs too long for the unversioned format");
                 target.write_u16(docs.len() as u16);
                 target.write_bytes(docs.as_bytes());
             }
@@ -447,6 +1512,20 @@ impl ModuleAst {
             }
         }
 
+        if options.version >= AstFormatVersion::V1 {
+            write_constants(target, &self.constants);
+
+            assert!(self.const_docs.len() <= u16::MAX as usize, "too many constant docs");
+            target.write_u16(self.const_docs.len() as u16);
+            for (name, docs) in self.const_docs.iter() {
+                assert!(name.len() <= MAX_LABEL_LEN, "constant name too long");
+                target.write_u8(name.len() as u8);
+                target.write_bytes(name.as_bytes());
+                target.write_u16(docs.len() as u16);
+                target.write_bytes(docs.as_bytes());
+            }
+        }
+
         if options.serialize_imports {
             assert!(self.imports.len() <= MAX_IMPORTS, "too many imports");
             target.write_u16(self.imports.len() as u16);
@@ -464,7 +1543,9 @@ impl ModuleAst {
         target.write_u16((self.reexported_procs.len()) as u16);
         self.reexported_procs.write_into(target);
         target.write_u16(self.local_procs.len() as u16);
-        self.local_procs.write_into(target);
+        self.local_procs
+            .iter()
+            .for_each(|proc| proc.write_with_options(target, options));
     }
 
     /// Returns a [ModuleAst] struct deserialized from the provided source.
@@ -475,16 +1556,35 @@ impl ModuleAst {
         options: AstSerdeOptions,
     ) -> Result<Self, DeserializationError> {
         // deserialize docs
-        let docs_len = source.read_u16()? as usize;
-        let docs = if docs_len != 0 {
-            let str = source.read_vec(docs_len)?;
-            let str =
-                from_utf8(&str).map_err(|e| DeserializationError::InvalidValue(e.to_string()))?;
-            Some(str.to_string())
+        let docs = if options.version >= AstFormatVersion::V1 {
+            read_docs_chunked(source)?
         } else {
-            None
+            let docs_len = source.read_u16()? as usize;
+            Some(read_utf8(source, docs_len)?).filter(|docs| !docs.is_empty())
         };
 
+        // deserialize constants and their docs
+        let mut constants = BTreeMap::new();
+        let mut const_docs = BTreeMap::new();
+        if options.version >= AstFormatVersion::V1 {
+            constants = read_constants(source)?;
+
+            // deserialize constant docs
+            let num_const_docs = source.read_u16()?;
+            for _ in 0..num_const_docs {
+                let name_len = source.read_u8()? as usize;
+                let name = read_utf8(source, name_len)?;
+                let docs_len = source.read_u16()? as usize;
+                if docs_len > MAX_DOCS_LEN {
+                    return Err(DeserializationError::InvalidValue(
+                        "constant docs too long".into(),
+                    ));
+                }
+                let docs = read_utf8(source, docs_len)?;
+                const_docs.insert(name, docs);
+            }
+        }
+
         // deserialize imports if required
         let mut imports = BTreeMap::<String, LibraryPath>::new();
         if options.serialize_imports {
@@ -501,15 +1601,21 @@ impl ModuleAst {
 
         // deserialize local procs
         let num_local_procs = source.read_u16()? as usize;
-        let local_procs = Deserializable::read_batch_from(source, num_local_procs)?;
+        let local_procs = (0..num_local_procs)
+            .map(|_| ProcedureAst::read_with_options(source, options))
+            .collect::<Result<_, _>>()?;
 
         Self::new(local_procs, reexported_procs, imports, docs)
+            .map(|module| module.with_constants(constants).with_const_docs(const_docs))
             .map_err(|err| DeserializationError::UnknownError(err.message().clone()))
     }
 
     /// Returns byte representation of this [ModuleAst].
     ///
     /// The serde options are serialized as header information for the purposes of deserialization.
+    ///
+    /// # Panics
+    /// Panics under the same conditions as [Self::write_into].
     pub fn to_bytes(&self, options: AstSerdeOptions) -> Vec<u8> {
         let mut target = Vec::<u8>::default();
 
@@ -533,26 +1639,119 @@ impl ModuleAst {
         Self::read_from(&mut source, options)
     }
 
+    /// Returns a compressed byte representation of this [ModuleAst].
+    ///
+    /// The bytes produced by [ModuleAst::to_bytes] are compressed via deflate, and prefixed with a
+    /// magic byte which allows [ModuleAst::from_bytes_compressed] to tell compressed and
+    /// uncompressed serializations apart.
+    #[cfg(feature = "compress")]
+    pub fn to_bytes_compressed(&self, options: AstSerdeOptions) -> Vec<u8> {
+        let bytes = self.to_bytes(options);
+        let compressed = miniz_oxide::deflate::compress_to_vec(&bytes, MODULE_COMPRESSION_LEVEL);
+
+        let mut target = Vec::with_capacity(compressed.len() + 1);
+        target.push(COMPRESSED_MODULE_MAGIC);
+        target.extend_from_slice(&compressed);
+        target
+    }
+
+    /// Returns a [ModuleAst] struct deserialized from the provided bytes, which may or may not be
+    /// compressed.
+    ///
+    /// Bytes produced by [ModuleAst::to_bytes_compressed] are decompressed before deserialization;
+    /// all other bytes are deserialized as if via [ModuleAst::from_bytes].
+    #[cfg(feature = "compress")]
+    pub fn from_bytes_compressed(bytes: &[u8]) -> Result<Self, DeserializationError> {
+        match bytes.split_first() {
+            Some((&COMPRESSED_MODULE_MAGIC, compressed)) => {
+                let bytes = miniz_oxide::inflate::decompress_to_vec(compressed).map_err(|err| {
+                    DeserializationError::InvalidValue(format!(
+                        "failed to decompress module: {err}"
+                    ))
+                })?;
+                Self::from_bytes(&bytes)
+            }
+            _ => Self::from_bytes(bytes),
+        }
+    }
+
     /// Loads the [SourceLocation] of the procedures via [ProcedureAst::load_source_locations].
     ///
     /// The local procedures are expected to have deterministic order from parse. This way, the
     /// serialization can be simplified into a contiguous sequence of locations.
+    ///
+    /// The serde options must correspond to the options used to write the locations.
     pub fn load_source_locations<R: ByteReader>(
         &mut self,
         source: &mut R,
+        options: AstSerdeOptions,
     ) -> Result<(), DeserializationError> {
-        self.local_procs.iter_mut().try_for_each(|p| p.load_source_locations(source))
+        self.local_procs
+            .iter_mut()
+            .try_for_each(|p| p.load_source_locations(source, options))
     }
 
     /// Writes the [SourceLocation] of the procedures via [ProcedureAst::write_source_locations].
     ///
     /// The local procedures are expected to have deterministic order from parse. This way, the
     /// serialization can be simplified into a contiguous sequence of locations.
-    pub fn write_source_locations<W: ByteWriter>(&self, target: &mut W) {
-        self.local_procs.iter().for_each(|p| p.write_source_locations(target))
+    pub fn write_source_locations<W: ByteWriter>(&self, target: &mut W, options: AstSerdeOptions) {
+        self.local_procs.iter().for_each(|p| p.write_source_locations(target, options))
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns a flag for each local procedure of this module, indicating whether the procedure is
+    /// exported or can be invoked from any of the exported procedures.
+    fn reachable_procs(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.local_procs.len()];
+        let mut pending = Vec::new();
+        for (idx, proc) in self.local_procs.iter().enumerate() {
+            if proc.is_export {
+                reachable[idx] = true;
+                pending.push(proc);
+            }
+        }
+
+        while let Some(proc) = pending.pop() {
+            proc.body.visit(&mut |node| {
+                if let Node::Instruction(
+                    Instruction::ExecLocal(idx) | Instruction::CallLocal(idx),
+                ) = node
+                {
+                    let idx = *idx as usize;
+                    // out of bounds indexes can only come from malformed deserialized modules
+                    if idx < reachable.len() && !reachable[idx] {
+                        reachable[idx] = true;
+                        pending.push(&self.local_procs[idx]);
+                    }
+                }
+            });
+        }
+        reachable
+    }
+}
+
+/// Two modules are equal if they have the same procedures, re-exports, imports, docs, and
+/// constants.
+///
+/// The names of invoked procedures are collected only when a module is parsed from source, and
+/// thus are not compared; otherwise, a parsed module would never be equal to the same module read
+/// back from bytes.
+impl PartialEq for ModuleAst {
+    fn eq(&self, other: &Self) -> bool {
+        self.local_procs == other.local_procs
+            && self.reexported_procs == other.reexported_procs
+            && self.imports == other.imports
+            && self.docs == other.docs
+            && self.constants == other.constants
+            && self.const_docs == other.const_docs
     }
 }
 
+impl Eq for ModuleAst {}
+
 // PROCEDURE AST
 // ================================================================================================
 
@@ -569,6 +1768,8 @@ pub struct ProcedureAst {
     pub body: CodeBody,
     pub start: SourceLocation,
     pub is_export: bool,
+    budget: Option<u32>,
+    flags: ProcedureFlags,
 }
 
 impl ProcedureAst {
@@ -577,7 +1778,8 @@ impl ProcedureAst {
     /// Constructs a [ProcedureAst].
     ///
     /// A procedure consists of a name, a number of locals, a body, and a flag to signal whether
-    /// the procedure is exported.
+    /// the procedure is exported. The stack effect of the procedure is parsed from its docs; see
+    /// [StackEffect] for details.
     pub fn new(
         name: ProcedureName,
         num_locals: u16,
@@ -594,9 +1796,61 @@ impl ProcedureAst {
             body,
             is_export,
             start,
+            budget: None,
+            flags: ProcedureFlags::default(),
         }
     }
 
+    /// Returns a [ProcedureAstBuilder] for constructing a procedure step by step.
+    pub fn builder() -> ProcedureAstBuilder {
+        ProcedureAstBuilder::default()
+    }
+
+    // TRANSFORMATIONS
+    // --------------------------------------------------------------------------------------------
+
+    /// Applies a conservative set of peephole optimizations to the body of this procedure and
+    /// returns the number of rewrites applied.
+    ///
+    /// See [CodeBody::peephole_optimize] for the list of applied rewrites.
+    pub fn peephole_optimize(&mut self) -> usize {
+        self.body.peephole_optimize()
+    }
+
+    /// Returns this procedure renamed to `new_name`.
+    ///
+    /// All other properties of the procedure (i.e., its body, locals, docs, source locations, and
+    /// the export flag) are preserved unchanged.
+    pub fn rename(mut self, new_name: ProcedureName) -> Self {
+        self.name = new_name;
+        self
+    }
+
+    /// Returns a copy of this procedure with the name set to `new_name`.
+    ///
+    /// This is the borrowing counterpart of [ProcedureAst::rename].
+    pub fn with_name(&self, new_name: ProcedureName) -> Self {
+        self.clone().rename(new_name)
+    }
+
+    /// Sets the execution budget hint of this procedure.
+    ///
+    /// The budget is metadata only: it is preserved through serialization but is not otherwise
+    /// interpreted by the assembler.
+    pub fn with_budget(mut self, budget: u32) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Sets the flags of this procedure.
+    ///
+    /// Similarly to the budget, the flags are hints which are preserved through serialization but
+    /// are not otherwise interpreted by the assembler.
+    pub fn with_flags(mut self, flags: ProcedureFlags) -> Self {
+        self.flags = flags;
+        self
+    }
+
     /// Binds the provided `locations` into the ast nodes.
     ///
     /// The `start` location points to the first node of this block.
@@ -617,6 +1871,100 @@ impl ProcedureAst {
         iter::once(&self.start).chain(self.body.source_locations().iter())
     }
 
+    /// Returns true if this procedure is exported but its body, including the bodies of nested
+    /// control flow blocks, does not contain any instructions.
+    pub fn is_empty_export(&self) -> bool {
+        self.is_export && self.body.instruction_count() == 0
+    }
+
+    /// Returns the execution budget hint of this procedure, if one was specified via the
+    /// `@[budget=<value>]` attribute.
+    pub fn budget(&self) -> Option<u32> {
+        self.budget
+    }
+
+    /// Returns the flags of this procedure, specified via the `@[inline]` and `@[noinline]`
+    /// attributes.
+    pub fn flags(&self) -> ProcedureFlags {
+        self.flags
+    }
+
+    /// Returns the stack effect of this procedure declared in its docs, if any.
+    ///
+    /// The stack effect is parsed from the current docs on every call, and thus always reflects
+    /// changes made to the docs (e.g., via [ModuleAst::strip_docs]).
+    pub fn stack_effect(&self) -> Option<StackEffect> {
+        self.docs.as_deref().and_then(StackEffect::parse_docs)
+    }
+
+    /// Returns the number of locals actually used by this procedure, i.e., the highest local
+    /// index accessed by its body plus one, or 0 if no locals are accessed.
+    ///
+    /// Unlike `num_locals`, which is declared by the author, this reflects the real footprint of
+    /// the procedure, and thus can be used to shrink over-declared frames.
+    pub fn locals_used(&self) -> u16 {
+        self.body.max_local_access().map_or(0, |(index, ..)| index.saturating_add(1))
+    }
+
+    /// Returns a Blake3 hash of the serialized body of this procedure.
+    ///
+    /// This is an AST-level hash which can be used to cheaply detect procedures with identical
+    /// bodies before compilation. It is not the MAST root of the procedure: procedures with
+    /// different bodies may still compile to the same MAST. The name, number of locals, docs, and
+    /// source locations of the procedure do not affect the hash.
+    pub fn body_hash(&self) -> [u8; 32] {
+        let mut bytes = Vec::new();
+        self.body.nodes().write_into(&mut bytes);
+        Blake3_256::hash(&bytes).into()
+    }
+
+    // VALIDATION
+    // --------------------------------------------------------------------------------------------
+
+    /// Makes sure that the body of this procedure accesses only the locals declared by this
+    /// procedure.
+    ///
+    /// # Errors
+    /// Returns an error pointing at the instruction accessing the highest local index if this
+    /// index is not smaller than the number of procedure locals.
+    pub fn validate(&self) -> Result<(), ParsingError> {
+        match self.body.max_local_access() {
+            Some((index, instruction, location)) if index >= self.num_locals => {
+                Err(ParsingError::proc_local_out_of_bounds(
+                    &instruction.to_string(),
+                    location,
+                    index,
+                    self.num_locals,
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the body of this procedure does not consume more stack elements than the
+    /// procedure declares as its inputs.
+    ///
+    /// The number of inputs is taken from the [StackEffect] declared in the procedure docs; if no
+    /// stack effect is declared, nothing is checked. The analysis tracks the depth of the stack
+    /// using a static table of instruction effects, and covers only the straight-line prefix of
+    /// the body: it stops at the first control flow block or procedure invocation.
+    ///
+    /// # Errors
+    /// Returns a warning for every instruction which consumes more elements than are available
+    /// at that point.
+    pub fn check_stack_balance(&self) -> Result<(), Vec<StackWarning>> {
+        let inputs = match self.stack_effect() {
+            Some(stack_effect) => stack_effect.inputs.len(),
+            None => return Ok(()),
+        };
+        let warnings = stack_balance::check_body(&self.body, inputs);
+        if warnings.is_empty() {
+            Ok(())
+        } else {
+            Err(warnings)
+        }
+    }
+
     // STATE MUTATORS
     // --------------------------------------------------------------------------------------------
 
@@ -629,31 +1977,13 @@ impl ProcedureAst {
     // SERIALIZATION / DESERIALIZATION
     // --------------------------------------------------------------------------------------------
 
-    /// Loads the [SourceLocation] from the `source`.
-    ///
-    /// It expects the `start` location at the first position, and will subsequently load the
-    /// body via [CodeBody::load_source_locations].
-    pub fn load_source_locations<R: ByteReader>(
-        &mut self,
-        source: &mut R,
-    ) -> Result<(), DeserializationError> {
-        self.start = SourceLocation::read_from(source)?;
-        self.body.load_source_locations(source)?;
-        Ok(())
-    }
-
-    /// Writes the [SourceLocation] into `target`.
+    /// Writes byte representation of this [ProcedureAst] into the specified target.
     ///
-    /// It will write the `start` location, and then execute the body serialization via
-    /// [CodeBlock::write_source_locations].
-    pub fn write_source_locations<W: ByteWriter>(&self, target: &mut W) {
-        self.start.write_into(target);
-        self.body.write_source_locations(target);
-    }
-}
-
-impl Serializable for ProcedureAst {
-    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+    /// The procedure is written in the format version of the serde options; parts of the
+    /// procedure which are not supported by this version are skipped. This is the format used by
+    /// [ModuleAst::write_into] and [ProgramAst::write_into]. The [Serializable] implementation
+    /// writes procedures in the current format version.
+    pub fn write_with_options<W: ByteWriter>(&self, target: &mut W, options: AstSerdeOptions) {
         // asserts below are OK because we enforce limits on the procedure body size and length of
         // procedure docs in the procedure parser
 
@@ -671,16 +2001,31 @@ impl Serializable for ProcedureAst {
 
         target.write_bool(self.is_export);
         target.write_u16(self.num_locals);
-        assert!(self.body.nodes().len() <= MAX_BODY_LEN, "too many body instructions");
-        target.write_u16(self.body.nodes().len() as u16);
-        self.body.nodes().write_into(target);
+        if options.version >= AstFormatVersion::V1 {
+            target.write_bool(self.budget.is_some());
+            if let Some(budget) = self.budget {
+                target.write_u32(budget);
+            }
+            target.write_u8(self.flags.bits());
+        }
+        self.body.write_nodes(target);
     }
-}
 
-impl Deserializable for ProcedureAst {
-    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+    /// Returns a [ProcedureAst] deserialized from the provided source.
+    ///
+    /// The serde options must correspond to the options used for serialization.
+    pub fn read_with_options<R: ByteReader>(
+        source: &mut R,
+        options: AstSerdeOptions,
+    ) -> Result<Self, DeserializationError> {
         let name = ProcedureName::read_from(source)?;
         let docs_len = source.read_u16()? as usize;
+        // fail early with a descriptive error on a corrupt or malicious length
+        source.check_eor(docs_len).map_err(|_| {
+            DeserializationError::InvalidValue(format!(
+                "unexpected end of input: expected {docs_len} bytes of procedure docs"
+            ))
+        })?;
         let docs = if docs_len != 0 {
             let str = source.read_vec(docs_len)?;
             let str =
@@ -692,6 +2037,20 @@ impl Deserializable for ProcedureAst {
 
         let is_export = source.read_bool()?;
         let num_locals = source.read_u16()?;
+        let (budget, flags) = if options.version >= AstFormatVersion::V1 {
+            let budget = if source.read_bool()? {
+                Some(source.read_u32()?)
+            } else {
+                None
+            };
+            let flags = source.read_u8()?;
+            let flags = ProcedureFlags::from_bits(flags).ok_or_else(|| {
+                DeserializationError::InvalidValue(format!("invalid procedure flags: {flags:#04x}"))
+            })?;
+            (budget, flags)
+        } else {
+            (None, ProcedureFlags::default())
+        };
         let body_len = source.read_u16()? as usize;
         let nodes = Deserializable::read_batch_from(source, body_len)?;
         let body = CodeBody::new(nodes);
@@ -703,8 +2062,94 @@ impl Deserializable for ProcedureAst {
             start,
             is_export,
             docs,
+            budget,
+            flags,
         })
     }
+
+    /// Loads the [SourceLocation] from the `source`.
+    ///
+    /// It expects the `start` location at the first position, and will subsequently load the
+    /// body via [CodeBody::load_source_locations].
+    ///
+    /// The serde options must correspond to the options used to write the locations.
+    pub fn load_source_locations<R: ByteReader>(
+        &mut self,
+        source: &mut R,
+        options: AstSerdeOptions,
+    ) -> Result<(), DeserializationError> {
+        self.start = SourceLocation::read_from(source)?;
+        self.body.load_source_locations(source, options)?;
+        Ok(())
+    }
+
+    /// Writes the [SourceLocation] into `target`.
+    ///
+    /// It will write the `start` location, and then execute the body serialization via
+    /// [CodeBlock::write_source_locations].
+    pub fn write_source_locations<W: ByteWriter>(&self, target: &mut W, options: AstSerdeOptions) {
+        self.start.write_into(target);
+        self.body.write_source_locations(target, options);
+    }
+}
+
+impl Serializable for ProcedureAst {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        self.write_with_options(target, AstSerdeOptions::new(false));
+    }
+}
+
+impl Deserializable for ProcedureAst {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        Self::read_with_options(source, AstSerdeOptions::new(false))
+    }
+}
+
+// PROCEDURE FLAGS
+// ================================================================================================
+
+/// A set of flags attached to a procedure via procedure attributes.
+///
+/// The flags are hints for optimizers: a procedure marked with `@[inline]` is a candidate for
+/// inlining, while a procedure marked with `@[noinline]` should never be inlined. A procedure
+/// cannot be marked with both attributes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProcedureFlags(u8);
+
+impl ProcedureFlags {
+    /// The procedure is a candidate for inlining.
+    pub const INLINE: Self = Self(0b01);
+    /// The procedure should not be inlined.
+    pub const NOINLINE: Self = Self(0b10);
+
+    /// Bits of all defined flags.
+    const ALL_BITS: u8 = Self::INLINE.0 | Self::NOINLINE.0;
+
+    /// Returns the flags represented by the provided bits, or None if any of the bits does not
+    /// correspond to a defined flag.
+    pub fn from_bits(bits: u8) -> Option<Self> {
+        (bits & !Self::ALL_BITS == 0).then_some(Self(bits))
+    }
+
+    /// Returns the bits representing these flags.
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Returns true if no flags are set.
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns true if all of the `other` flags are set.
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Sets all of the `other` flags.
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
 }
 
 /// Represents a re-exported procedure.
@@ -740,7 +2185,7 @@ impl ProcReExport {
 
     /// Returns the ID of the re-exported procedure using the specified module.
     pub fn get_alias_id(&self, module_path: &LibraryPath) -> ProcedureId {
-        ProcedureId::from_name(&self.name, module_path)
+        self.name.to_id(module_path)
     }
 }
 
@@ -762,6 +2207,203 @@ impl Deserializable for ProcReExport {
 // HELPER FUNCTIONS
 // ================================================================================================
 
+/// Reads a UTF-8 string of the specified length in bytes from the source.
+fn read_utf8<R: ByteReader>(source: &mut R, len: usize) -> Result<String, DeserializationError> {
+    let bytes = source.read_vec(len)?;
+    from_utf8(&bytes)
+        .map(|str| str.to_string())
+        .map_err(|e| DeserializationError::InvalidValue(e.to_string()))
+}
+
+/// Writes the names and values of the provided constants into the target, prefixed with the
+/// number of constants as a `u16`.
+fn write_constants<W: ByteWriter>(target: &mut W, constants: &BTreeMap<String, u64>) {
+    assert!(constants.len() <= u16::MAX as usize, "too many constants");
+    target.write_u16(constants.len() as u16);
+    for (name, value) in constants.iter() {
+        assert!(name.len() <= MAX_LABEL_LEN, "constant name too long");
+        target.write_u8(name.len() as u8);
+        target.write_bytes(name.as_bytes());
+        target.write_u64(*value);
+    }
+}
+
+/// Reads constants written via [write_constants] from the source.
+fn read_constants<R: ByteReader>(
+    source: &mut R,
+) -> Result<BTreeMap<String, u64>, DeserializationError> {
+    let num_constants = source.read_u16()?;
+    let mut constants = BTreeMap::new();
+    for _ in 0..num_constants {
+        let name_len = source.read_u8()? as usize;
+        let name = read_utf8(source, name_len)?;
+        let value = source.read_u64()?;
+        constants.insert(name, value);
+    }
+    Ok(constants)
+}
+
+/// Writes the docs into the target as a sequence of chunks, each prefixed with its length as a
+/// `u16`.
+///
+/// All chunks except for the last one have the maximum length of `u16::MAX` bytes, and thus a
+/// chunk shorter than that (possibly an empty one) marks the end of the docs. Docs shorter than
+/// `u16::MAX` bytes are written as a single chunk.
+fn write_docs_chunked<W: ByteWriter>(target: &mut W, docs: &str) {
+    const CHUNK_LEN: usize = u16::MAX as usize;
+    let mut remainder = docs.as_bytes();
+    loop {
+        let chunk_len = remainder.len().min(CHUNK_LEN);
+        target.write_u16(chunk_len as u16);
+        target.write_bytes(&remainder[..chunk_len]);
+        remainder = &remainder[chunk_len..];
+        if chunk_len < CHUNK_LEN {
+            break;
+        }
+    }
+}
+
+/// Reads docs written via [write_docs_chunked] from the source, returning None if the docs are
+/// empty.
+fn read_docs_chunked<R: ByteReader>(
+    source: &mut R,
+) -> Result<Option<String>, DeserializationError> {
+    const CHUNK_LEN: usize = u16::MAX as usize;
+    let mut bytes = Vec::new();
+    loop {
+        let chunk_len = source.read_u16()? as usize;
+        if bytes.len() + chunk_len > MAX_MODULE_DOCS_LEN {
+            return Err(DeserializationError::InvalidValue("module docs too long".into()));
+        }
+        bytes.extend(source.read_vec(chunk_len)?);
+        if chunk_len < CHUNK_LEN {
+            break;
+        }
+    }
+
+    if bytes.is_empty() {
+        return Ok(None);
+    }
+    // chunks are not aligned with character boundaries, so they are decoded only once joined
+    String::from_utf8(bytes)
+        .map(Some)
+        .map_err(|e| DeserializationError::InvalidValue(e.to_string()))
+}
+
+/// Reads `count` items from the source via `read_item`.
+///
+/// If the end of the source is reached before all items have been read, an error naming the
+/// number of expected and read items of the specified `kind` is returned.
+fn read_batch_checked<R: ByteReader, D>(
+    source: &mut R,
+    count: usize,
+    kind: &str,
+    read_item: impl Fn(&mut R) -> Result<D, DeserializationError>,
+) -> Result<Vec<D>, DeserializationError> {
+    let mut items = Vec::with_capacity(count);
+    for _ in 0..count {
+        match read_item(source) {
+            Ok(item) => items.push(item),
+            Err(DeserializationError::UnexpectedEOF) => {
+                return Err(DeserializationError::InvalidValue(format!(
+                    "unexpected end of input: expected {count} {kind}, but only {} could be read",
+                    items.len()
+                )));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(items)
+}
+
+/// Parses the `begin ... end` block of a program from the provided token stream, and returns the
+/// body of the program together with the location of the `begin` token.
+///
+/// # Errors
+/// Returns an error if the block is missing or malformed, or if there are any tokens after it.
+fn parse_program_body(
+    context: &ParserContext,
+    tokens: &mut TokenStream,
+) -> Result<(CodeBody, SourceLocation), ParsingError> {
+    // make sure program body is present
+    let next_token = tokens
+        .read()
+        .ok_or_else(|| ParsingError::unexpected_eof(*tokens.eof_location()))?;
+    if next_token.parts()[0] != Token::BEGIN {
+        return Err(ParsingError::unexpected_token(next_token, Token::BEGIN));
+    }
+
+    let program_start = tokens.pos();
+    // consume the 'begin' token
+    let header = tokens.read().expect("missing program header");
+    let start = *header.location();
+    header.validate_begin()?;
+    tokens.advance();
+
+    // make sure there is something to be read
+    if tokens.eof() {
+        return Err(ParsingError::unexpected_eof(*tokens.eof_location()));
+    }
+
+    // parse the sequence of nodes and add each node to the list
+    let body = context.parse_body(tokens, false)?;
+
+    // consume the 'end' token
+    match tokens.read() {
+        None => Err(ParsingError::unmatched_begin(
+            tokens.read_at(program_start).expect("no begin token"),
+        )),
+        Some(token) => match token.parts()[0] {
+            Token::END => token.validate_end(),
+            Token::ELSE => Err(ParsingError::dangling_else(token)),
+            _ => Err(ParsingError::unmatched_begin(
+                tokens.read_at(program_start).expect("no begin token"),
+            )),
+        },
+    }?;
+    tokens.advance();
+
+    // make sure there are no instructions after the end
+    if let Some(token) = tokens.read() {
+        return Err(ParsingError::dangling_ops_after_program(token));
+    }
+
+    Ok((body, start))
+}
+
+/// Advances the provided token stream past the current token and all subsequent tokens until a
+/// token which may start a procedure declaration or the program body, or until the end of the
+/// stream.
+fn skip_to_next_declaration(tokens: &mut TokenStream) {
+    tokens.advance();
+    while let Some(token) = tokens.read() {
+        match token.parts()[0] {
+            Token::PROC | Token::EXPORT | Token::BEGIN | Token::CFG => break,
+            _ if token.is_proc_attribute() => break,
+            _ => tokens.advance(),
+        }
+    }
+}
+
+/// Returns a symbol for every import statement read from the `tokens` since position `start`.
+///
+/// Exact duplicates of an import are reported only once.
+fn collect_import_symbols(
+    tokens: &mut TokenStream,
+    start: usize,
+) -> Result<Vec<Symbol>, ParsingError> {
+    let mut symbols = Vec::<Symbol>::new();
+    for pos in start..tokens.pos() {
+        let token = tokens.read_at(pos).expect("no import token");
+        let module_path = token.parse_use()?;
+        if symbols.iter().all(|symbol| symbol.name != module_path.last()) {
+            let name = module_path.last().to_string();
+            symbols.push(Symbol::new(name, SymbolKind::Import, *token.location()));
+        }
+    }
+    Ok(symbols)
+}
+
 /// Sort a map of procedures into a vec, respecting the order set in the map
 fn sort_procs_into_vec(proc_map: LocalProcMap) -> Vec<ProcedureAst> {
     let mut procedures: Vec<_> = proc_map.into_values().collect();
@@ -5,6 +5,7 @@ mod advice;
 pub use advice::AdviceInjectorNode;
 
 mod serde;
+pub use self::serde::OPCODE_NAMES;
 
 // NODES
 // ================================================================================================
@@ -25,6 +26,12 @@ pub enum Node {
     While {
         body: CodeBody,
     },
+    /// A placeholder which does not correspond to any instruction.
+    ///
+    /// This node can be used by transformation passes to mark nodes for deletion without
+    /// reindexing the body. Nops are skipped when a body is serialized, and can be removed from
+    /// the AST via [CodeBody::strip_nops].
+    Nop,
 }
 
 /// An instruction of Miden assembly program, excluding control flow instruction.
@@ -285,6 +292,7 @@ pub enum Instruction {
     // ----- exec / call --------------------------------------------------------------------------
     ExecLocal(u16),
     ExecImported(ProcedureId),
+    ExecMastRoot(RpoDigest),
     CallLocal(u16),
     CallMastRoot(RpoDigest),
     CallImported(ProcedureId),
@@ -559,6 +567,10 @@ impl fmt::Display for Instruction {
             // TODO: print exec/call instructions with procedures names, not indexes or id's
             Self::ExecLocal(index) => write!(f, "exec.{index}"),
             Self::ExecImported(proc_id) => write!(f, "exec.{proc_id}"),
+            Self::ExecMastRoot(root) => {
+                write!(f, "exec.")?;
+                display_hex_bytes(f, &root.as_bytes())
+            }
             Self::CallLocal(index) => write!(f, "call.{index}"),
             Self::CallMastRoot(root) => {
                 write!(f, "call.")?;
@@ -622,3 +634,58 @@ fn test_instruction_display() {
     let instruction = format!("{}", Instruction::ExecImported(proc_id));
     assert_eq!("exec.0x0707070707070707070707070707070707070707", instruction);
 }
+
+#[test]
+fn test_instruction_opcode() {
+    use vm_core::utils::Serializable;
+
+    // operation codes are unique and sorted
+    assert!(OPCODE_NAMES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    for (code, name) in OPCODE_NAMES {
+        let opcode = serde::OpCode::try_from(*code).unwrap();
+        assert_eq!(format!("{opcode:?}"), *name);
+    }
+
+    // every operation code has an entry in the table
+    let opcodes = (0..=u8::MAX).filter_map(|code| serde::OpCode::try_from(code).ok());
+    assert_eq!(opcodes.clone().count(), OPCODE_NAMES.len());
+    for opcode in opcodes {
+        let entry = OPCODE_NAMES.iter().find(|(code, _)| *code == opcode as u8);
+        assert_eq!(entry.map(|(_, name)| name.to_string()), Some(format!("{opcode:?}")));
+    }
+
+    let instructions = [
+        Instruction::Assert,
+        Instruction::Add,
+        Instruction::AddImm(Felt::new(5)),
+        Instruction::U32CheckedAddImm(7),
+        Instruction::PushU8(3),
+        Instruction::PushFeltList(vec![Felt::new(3), Felt::new(4)]),
+        Instruction::LocStore(2),
+        Instruction::SwapDw,
+        Instruction::ExecLocal(1),
+        Instruction::ExecImported(ProcedureId::from([7; 20])),
+    ];
+    for instruction in instructions.iter() {
+        let opcode = instruction.opcode().unwrap();
+        assert_eq!(opcode, instruction.to_bytes()[0]);
+        assert!(OPCODE_NAMES.iter().any(|(code, _)| *code == opcode));
+    }
+
+    // distinct instructions have distinct operation codes
+    for (i, a) in instructions.iter().enumerate() {
+        for b in instructions[i + 1..].iter() {
+            assert_ne!(a.opcode(), b.opcode(), "{a} and {b} share an operation code");
+        }
+    }
+
+    let name = |instruction: Instruction| {
+        let opcode = instruction.opcode()?;
+        OPCODE_NAMES.iter().find(|(code, _)| *code == opcode).map(|(_, name)| *name)
+    };
+    assert_eq!(name(Instruction::AddImm(Felt::new(5))), Some("AddImm"));
+    assert_eq!(name(Instruction::SysCall(ProcedureId::from([7; 20]))), Some("SysCall"));
+
+    // breakpoints are not serialized, and thus have no operation code
+    assert_eq!(Instruction::Breakpoint.opcode(), None);
+}
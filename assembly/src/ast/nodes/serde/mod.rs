@@ -10,9 +10,27 @@ mod serialization;
 // OPERATION CODES ENUM
 // ================================================================================================
 
-#[repr(u8)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq, TryFromPrimitive)]
-pub enum OpCode {
+/// Defines the [OpCode] enum together with the [OPCODE_NAMES] table, so that the table is
+/// generated from the same list of operation codes as the enum.
+macro_rules! define_opcodes {
+    ($($name:ident = $code:literal,)*) => {
+        #[repr(u8)]
+        #[derive(Copy, Clone, Debug, PartialEq, Eq, TryFromPrimitive)]
+        pub enum OpCode {
+            $($name = $code,)*
+        }
+
+        /// Names of all operation codes used in the serialized representation of [Node]s, sorted
+        /// by the operation code.
+        ///
+        /// Tools which need to mirror the binary encoding of the AST can use this table to map
+        /// operation codes to names. Operation codes of instructions are returned by
+        /// [Instruction::opcode].
+        pub const OPCODE_NAMES: &[(u8, &str)] = &[$(($code, stringify!($name)),)*];
+    };
+}
+
+define_opcodes! {
     Assert = 0,
     AssertEq = 1,
     AssertEqw = 2,
@@ -272,6 +290,7 @@ pub enum OpCode {
     CallMastRoot = 237,
     CallImported = 238,
     SysCall = 239,
+    ExecMastRoot = 240,
 
     // ----- control flow -------------------------------------------------------------------------
     IfElse = 253,
@@ -293,3 +312,36 @@ impl Deserializable for OpCode {
         })
     }
 }
+
+// INSTRUCTION OPCODE
+// ================================================================================================
+
+impl Instruction {
+    /// Returns the operation code which identifies this instruction in its serialized
+    /// representation.
+    ///
+    /// The operation code is the first byte written by [Serializable::write_into] for this
+    /// instruction. The names of all operation codes are listed in [OPCODE_NAMES].
+    ///
+    /// Returns None for instructions which are not serialized at all (i.e., `breakpoint`).
+    pub fn opcode(&self) -> Option<u8> {
+        /// A writer which records the first written byte and discards all other bytes.
+        struct OpCodeWriter(Option<u8>);
+
+        impl ByteWriter for OpCodeWriter {
+            fn write_u8(&mut self, value: u8) {
+                self.0.get_or_insert(value);
+            }
+
+            fn write_bytes(&mut self, values: &[u8]) {
+                if let Some(value) = values.first() {
+                    self.write_u8(*value);
+                }
+            }
+        }
+
+        let mut writer = OpCodeWriter(None);
+        self.write_into(&mut writer);
+        writer.0
+    }
+}
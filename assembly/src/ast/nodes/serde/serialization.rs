@@ -1,14 +1,10 @@
 use super::{ByteWriter, Instruction, Node, OpCode, Serializable};
-use crate::ast::MAX_BODY_LEN;
 
 // NODE SERIALIZATION
 // ================================================================================================
 
 impl Serializable for Node {
     fn write_into<W: ByteWriter>(&self, target: &mut W) {
-        // asserts below are OK because we enforce limits on the number of body instructions in
-        // the body parser
-
         match self {
             // TODO this initial implementation will store location only for in-memory compilation
             // and will not serialize it.
@@ -18,30 +14,20 @@ impl Serializable for Node {
                 false_case,
             } => {
                 OpCode::IfElse.write_into(target);
-
-                assert!(true_case.nodes().len() <= MAX_BODY_LEN, "too many body nodes");
-                target.write_u16(true_case.nodes().len() as u16);
-                true_case.nodes().write_into(target);
-
-                assert!(false_case.nodes().len() <= MAX_BODY_LEN, "too many body nodes");
-                target.write_u16(false_case.nodes().len() as u16);
-                false_case.nodes().write_into(target);
+                true_case.write_nodes(target);
+                false_case.write_nodes(target);
             }
             Self::Repeat { times, body } => {
                 OpCode::Repeat.write_into(target);
                 target.write_u32(*times);
-
-                assert!(body.nodes().len() <= MAX_BODY_LEN, "too many body nodes");
-                target.write_u16(body.nodes().len() as u16);
-                body.nodes().write_into(target);
+                body.write_nodes(target);
             }
             Self::While { body } => {
                 OpCode::While.write_into(target);
-
-                assert!(body.nodes().len() <= MAX_BODY_LEN, "too many body nodes");
-                target.write_u16(body.nodes().len() as u16);
-                body.nodes().write_into(target);
+                body.write_nodes(target);
             }
+            // nops are skipped when a code body is serialized, and thus are not written at all
+            Self::Nop => {}
         }
     }
 }
@@ -475,6 +461,10 @@ impl Serializable for Instruction {
                 OpCode::ExecImported.write_into(target);
                 imported.write_into(target)
             }
+            Self::ExecMastRoot(root) => {
+                OpCode::ExecMastRoot.write_into(target);
+                root.write_into(target);
+            }
             Self::CallLocal(v) => {
                 OpCode::CallLocal.write_into(target);
                 target.write_u16(*v);
@@ -0,0 +1,21 @@
+//! Options which control the behavior of the assembly parser.
+
+/// Parsing options
+/// Used to relax or tighten how the parser treats certain source constructs. Conditions which are
+/// relaxed by these options are reported as [ParsingWarning](crate::ParsingWarning)s instead of
+/// [ParsingError](crate::ParsingError)s.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct ParsingOptions {
+    /// When set, importing the same module path more than once under the same name is reported as
+    /// a warning, and only a single import is kept. Imports which bind different paths to the
+    /// same name are always rejected.
+    pub allow_duplicate_imports: bool,
+}
+
+impl ParsingOptions {
+    pub fn new(allow_duplicate_imports: bool) -> Self {
+        Self {
+            allow_duplicate_imports,
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use super::{
     super::ProcReExport, adv_ops, field_ops, io_ops, stack_ops, u32_ops, CodeBody, Instruction,
-    InvocationTarget, LibraryPath, LocalConstMap, LocalProcMap, Node, ParsingError, ProcedureAst,
-    ProcedureId, ReExportedProcMap, Token, TokenStream, MAX_BODY_LEN, MAX_DOCS_LEN,
+    InvocationTarget, InvokedProcsMap, LibraryPath, LocalConstMap, LocalProcMap, Node,
+    ParsingError, ProcedureAst, ProcedureFlags, ProcedureId, ProcedureName, ReExportedProcMap,
+    SourceLocation, Token, TokenStream, MAX_BODY_LEN, MAX_DOCS_LEN,
 };
+use core::cell::RefCell;
 use vm_core::utils::{
-    collections::{BTreeMap, Vec},
+    collections::{BTreeMap, BTreeSet, Vec},
     string::{String, ToString},
 };
 
@@ -17,6 +19,11 @@ pub struct ParserContext<'a> {
     pub local_procs: LocalProcMap,
     pub reexported_procs: ReExportedProcMap,
     pub local_constants: LocalConstMap,
+    /// Features which enable the `@cfg.<feature>` blocks guarded by them.
+    pub features: &'a BTreeSet<String>,
+    /// Names and module paths of the imported and kernel procedures invoked or re-exported in
+    /// the parsed source, keyed by procedure ID.
+    pub invoked_procs: RefCell<InvokedProcsMap>,
 }
 
 impl ParserContext<'_> {
@@ -166,7 +173,10 @@ impl ParserContext<'_> {
     /// Parse an `exec` token into an instruction node.
     fn parse_exec(&self, token: &Token) -> Result<Node, ParsingError> {
         match token.parse_invocation(token.parts()[0])? {
-            InvocationTarget::MastRoot(_) => Err(ParsingError::exec_with_mast_root(token)),
+            InvocationTarget::MastRoot(root_hash) => {
+                let inner = Instruction::ExecMastRoot(root_hash);
+                Ok(Node::Instruction(inner))
+            }
             InvocationTarget::ProcedureName(proc_name) => {
                 let index = self.get_local_proc_index(proc_name, token)?;
                 let inner = Instruction::ExecLocal(index);
@@ -206,6 +216,7 @@ impl ParserContext<'_> {
             InvocationTarget::MastRoot(_) => Err(ParsingError::syscall_with_mast_root(token)),
             InvocationTarget::ProcedureName(proc_name) => {
                 let proc_id = ProcedureId::from_kernel_name(proc_name);
+                self.record_invoked_proc(proc_id, proc_name, &LibraryPath::kernel_path());
                 let inner = Instruction::SysCall(proc_id);
                 Ok(Node::Instruction(inner))
             }
@@ -225,7 +236,26 @@ impl ParserContext<'_> {
         allow_export: bool,
     ) -> Result<(), ParsingError> {
         // parse procedures until all `proc` or `exec` tokens have been consumed
-        while let Some(token) = tokens.read() {
+        loop {
+            // parse conditionally compiled procedures, if any
+            if tokens.read().map_or(false, |token| token.parts()[0] == Token::CFG) {
+                self.parse_cfg_block(tokens, allow_export)?;
+                continue;
+            }
+
+            // parse procedure attributes, if any
+            let attributes_start = tokens.pos();
+            let (budget, flags) = parse_proc_attributes(tokens)?;
+            let has_attributes = tokens.pos() != attributes_start;
+
+            let token = match tokens.read() {
+                Some(token) => token,
+                None if has_attributes => {
+                    let token = tokens.read_at(attributes_start).expect("no attribute token");
+                    return Err(ParsingError::dangling_proc_attribute(token));
+                }
+                None => break,
+            };
             let is_reexport = match token.parts()[0] {
                 Token::EXPORT => {
                     if !allow_export {
@@ -238,16 +268,26 @@ impl ParserContext<'_> {
                     // no validation needed, parse the procedure below
                     false
                 }
+                _ if has_attributes => {
+                    let token = tokens.read_at(attributes_start).expect("no attribute token");
+                    return Err(ParsingError::dangling_proc_attribute(token));
+                }
                 _ => break,
             };
 
             if is_reexport {
+                // attributes cannot be attached to re-exported procedures
+                if has_attributes {
+                    let token = tokens.read_at(attributes_start).expect("no attribute token");
+                    return Err(ParsingError::dangling_proc_attribute(token));
+                }
+
                 // parse procedure re-export and add it to the list of re-exported procedures
                 let proc = self.parse_reexported_procedure(tokens)?;
                 self.reexported_procs.insert(proc.name.to_string(), proc);
             } else {
                 // parse the procedure body and add it to the list of local procedures
-                let proc = self.parse_procedure(tokens)?;
+                let proc = self.parse_procedure(tokens, attributes_start, budget, flags)?;
                 let proc_idx = self.local_procs.len() as u16;
                 self.local_procs.insert(proc.name.to_string(), (proc_idx, proc));
             }
@@ -256,15 +296,97 @@ impl ParserContext<'_> {
         Ok(())
     }
 
+    /// Parses a conditionally compiled block of procedures of the form
+    /// `@cfg.<feature> begin ... end`.
+    ///
+    /// If the feature is enabled in this context, the procedures in the block are parsed as if
+    /// they were declared outside of it. Otherwise, the tokens of the block are skipped without
+    /// being parsed, and thus the block produces no procedures.
+    ///
+    /// # Errors
+    /// Returns an error if the directive is malformed, is not followed by `begin`, or the block
+    /// is not terminated with a matching `end`.
+    fn parse_cfg_block(
+        &mut self,
+        tokens: &mut TokenStream,
+        allow_export: bool,
+    ) -> Result<(), ParsingError> {
+        // record start of the cfg block and consume the directive
+        let cfg_start = tokens.pos();
+        let cfg_token = tokens.read().expect("no cfg token");
+        let is_enabled = self.features.contains(cfg_token.parse_cfg()?);
+        tokens.advance();
+
+        // consume the `begin` token
+        match tokens.read() {
+            None => return Err(ParsingError::unexpected_eof(*tokens.eof_location())),
+            Some(token) if token.parts()[0] == Token::BEGIN => token.validate_begin()?,
+            Some(token) => return Err(ParsingError::unexpected_token(token, Token::BEGIN)),
+        }
+        tokens.advance();
+
+        if is_enabled {
+            self.parse_procedures(tokens, allow_export)?;
+            match tokens.read() {
+                Some(token) if token.parts()[0] == Token::END => token.validate_end()?,
+                _ => {
+                    let token = tokens.read_at(cfg_start).expect("no cfg token");
+                    return Err(ParsingError::unmatched_cfg(token));
+                }
+            }
+            tokens.advance();
+        } else {
+            // skip all tokens up to and including the `end` matching the `begin` of the block
+            let mut depth = 1_usize;
+            while depth > 0 {
+                let token = match tokens.read() {
+                    Some(token) => token,
+                    None => {
+                        let token = tokens.read_at(cfg_start).expect("no cfg token");
+                        return Err(ParsingError::unmatched_cfg(token));
+                    }
+                };
+                match token.parts()[0] {
+                    Token::BEGIN | Token::PROC | Token::IF | Token::WHILE | Token::REPEAT => {
+                        depth += 1
+                    }
+                    // re-exported procedures have no body, and thus are not terminated by `end`
+                    Token::EXPORT
+                        if !token
+                            .parts()
+                            .get(1)
+                            .map_or(false, |name| name.contains(LibraryPath::PATH_DELIM)) =>
+                    {
+                        depth += 1
+                    }
+                    Token::END => depth -= 1,
+                    _ => {}
+                }
+                tokens.advance();
+            }
+        }
+
+        Ok(())
+    }
+
     /// Parses a procedure from token stream and add it to the set of local procedures defined
     /// in this context.
     ///
+    /// The `attributes_start` is the position of the first attribute of the procedure, or the
+    /// position of the procedure declaration if the procedure has no attributes.
+    ///
     /// # Errors
     /// Returns an error if:
     /// - Procedure declaration or procedure body is malformed.
     /// - A procedure with the same name has already been either declared or re-exported from this
     ///   context.
-    fn parse_procedure(&self, tokens: &mut TokenStream) -> Result<ProcedureAst, ParsingError> {
+    fn parse_procedure(
+        &self,
+        tokens: &mut TokenStream,
+        attributes_start: usize,
+        budget: Option<u32>,
+        flags: ProcedureFlags,
+    ) -> Result<ProcedureAst, ParsingError> {
         let proc_start = tokens.pos();
 
         // parse procedure declaration, make sure the procedure with the same name hasn't been
@@ -277,9 +399,12 @@ impl ParserContext<'_> {
         let start = *header.location();
         tokens.advance();
 
-        // attach doc comments (if any) to exported procedures
+        // attach doc comments (if any) to exported procedures; doc comments may precede either
+        // the procedure attributes or the procedure declaration
         let docs = if is_export {
-            let docs = tokens.take_doc_comment_at(proc_start);
+            let docs = tokens
+                .take_doc_comment_at(attributes_start)
+                .or_else(|| tokens.take_doc_comment_at(proc_start));
             // make sure procedure docs don't exceed the allowed limit
             if let Some(ref docs) = docs {
                 if docs.len() > MAX_DOCS_LEN {
@@ -312,9 +437,16 @@ impl ParserContext<'_> {
         tokens.advance();
 
         // build and return the procedure
+        let ends = body.end_locations().to_vec();
         let (nodes, locations) = body.into_parts();
-        Ok(ProcedureAst::new(name, num_locals, nodes, is_export, docs)
-            .with_source_locations(locations, start))
+        let mut proc = ProcedureAst::new(name, num_locals, nodes, is_export, docs)
+            .with_source_locations(locations, start)
+            .with_flags(flags);
+        proc.body = proc.body.with_end_locations(ends);
+        if let Some(budget) = budget {
+            proc = proc.with_budget(budget);
+        }
+        Ok(proc)
     }
 
     /// Parses procedure re-export from the token stream and adds it to the set of procedures
@@ -347,6 +479,7 @@ impl ParserContext<'_> {
         tokens.advance();
 
         let proc_id = ProcedureId::from_name(&ref_name, module_path);
+        self.record_invoked_proc(proc_id, &ref_name, module_path);
         Ok(ProcReExport::new(proc_id, proc_name))
     }
 
@@ -364,6 +497,7 @@ impl ParserContext<'_> {
         let start_pos = tokens.pos();
         let mut nodes = Vec::new();
         let mut locations = Vec::new();
+        let mut ends = Vec::new();
 
         while let Some(token) = tokens.read() {
             match token.parts()[0] {
@@ -371,6 +505,7 @@ impl ParserContext<'_> {
                     locations.push(*token.location());
                     let body = self.parse_if(tokens)?;
                     nodes.push(body);
+                    ends.push(last_token_end(tokens));
                 }
                 Token::ELSE => {
                     token.validate_else()?;
@@ -383,11 +518,13 @@ impl ParserContext<'_> {
                     locations.push(*token.location());
                     let body = self.parse_while(tokens)?;
                     nodes.push(body);
+                    ends.push(last_token_end(tokens));
                 }
                 Token::REPEAT => {
                     locations.push(*token.location());
                     let body = self.parse_repeat(tokens)?;
                     nodes.push(body);
+                    ends.push(last_token_end(tokens));
                 }
                 Token::END => {
                     locations.push(*token.location());
@@ -404,6 +541,7 @@ impl ParserContext<'_> {
                 }
                 _ => {
                     locations.push(*token.location());
+                    ends.push(token.end_location());
                     nodes.push(self.parse_op_token(token)?);
                     tokens.advance();
                 }
@@ -415,7 +553,7 @@ impl ParserContext<'_> {
             return Err(ParsingError::body_too_long(token, nodes.len(), MAX_BODY_LEN));
         }
 
-        Ok(CodeBody::new(nodes).with_source_locations(locations))
+        Ok(CodeBody::new(nodes).with_source_locations(locations).with_end_locations(ends))
     }
 
     // HELPER METHODS
@@ -488,6 +626,8 @@ impl ParserContext<'_> {
             "u32wrapping_mul" => u32_ops::parse_u32wrapping_mul(op),
             "u32overflowing_mul" => u32_ops::parse_u32overflowing_mul(op),
 
+            "u32add" | "u32sub" | "u32mul" => u32_ops::parse_u32_arithmetic(op),
+
             "u32overflowing_madd" => simple_instruction(op, U32OverflowingMadd),
             "u32wrapping_madd" => simple_instruction(op, U32WrappingMadd),
 
@@ -643,9 +783,26 @@ impl ParserContext<'_> {
             .get(module_name)
             .ok_or_else(|| ParsingError::procedure_module_not_imported(token, module_name))?;
         let proc_id = ProcedureId::from_name(proc_name, module_path);
+        self.record_invoked_proc(proc_id, proc_name, module_path);
         Ok(proc_id)
     }
 
+    /// Records the name and module path of an invoked or re-exported procedure with the
+    /// specified ID.
+    ///
+    /// Procedures whose names are not valid procedure names are not recorded, as their names
+    /// could not be reproduced from the AST anyway.
+    fn record_invoked_proc(
+        &self,
+        proc_id: ProcedureId,
+        proc_name: &str,
+        module_path: &LibraryPath,
+    ) {
+        if let Ok(name) = ProcedureName::try_from(proc_name.to_string()) {
+            self.invoked_procs.borrow_mut().insert(proc_id, (name, module_path.clone()));
+        }
+    }
+
     /// Returns true if a procedure with the specified name is present in the set of local or
     /// re-exported procedures.
     fn contains_proc_name(&self, proc_name: &str) -> bool {
@@ -656,6 +813,49 @@ impl ParserContext<'_> {
 // HELPER FUNCTIONS
 // ================================================================================================
 
+/// Parses the attributes preceding a procedure declaration, consuming the attribute tokens, and
+/// returns the execution budget specified by them, if any.
+///
+/// # Errors
+/// Returns an error if an attribute is malformed or is specified more than once, or if both
+/// `@[inline]` and `@[noinline]` are specified.
+fn parse_proc_attributes(
+    tokens: &mut TokenStream,
+) -> Result<(Option<u32>, ProcedureFlags), ParsingError> {
+    let mut budget = None;
+    let mut flags = ProcedureFlags::default();
+    while let Some(token) = tokens.read() {
+        if !token.is_proc_attribute() {
+            break;
+        }
+        if let Some(flag) = token.parse_proc_flag() {
+            if flags.contains(flag) {
+                return Err(ParsingError::invalid_proc_attribute(token, "duplicate attribute"));
+            }
+            flags.insert(flag);
+            if flags.contains(ProcedureFlags::INLINE) && flags.contains(ProcedureFlags::NOINLINE) {
+                return Err(ParsingError::invalid_proc_attribute(
+                    token,
+                    "inline and noinline attributes are mutually exclusive",
+                ));
+            }
+        } else if budget.replace(token.parse_proc_budget()?).is_some() {
+            return Err(ParsingError::invalid_proc_attribute(token, "duplicate attribute"));
+        }
+        tokens.advance();
+    }
+    Ok((budget, flags))
+}
+
+/// Returns the location immediately past the end of the most recently consumed token, i.e., the
+/// end of the span of a block which has just been parsed.
+fn last_token_end(tokens: &mut TokenStream) -> SourceLocation {
+    tokens
+        .read_at(tokens.pos().saturating_sub(1))
+        .map(|token| token.end_location())
+        .unwrap_or_default()
+}
+
 /// Validates that the provided token does not contain any immediate parameters and returns a node
 /// for the specified instruction.
 ///
@@ -20,6 +20,9 @@ const MAX_PUSH_PARTS: usize = MAX_PUSH_INPUTS + 1;
 
 /// Returns one of the `Push` instruction nodes.
 ///
+/// Values can be specified in decimal, in hexadecimal with a `0x` (or `0X`) prefix, or as
+/// constant names.
+///
 /// # Errors
 /// Returns an error if the instruction token has invalid values or inappropriate number of
 /// values.
@@ -30,7 +33,7 @@ pub fn parse_push(op: &Token, constants: &LocalConstMap) -> Result<Node, Parsing
         1 => Err(ParsingError::missing_param(op)),
         2 => {
             let param_str = op.parts()[1];
-            match param_str.strip_prefix("0x") {
+            match strip_hex_prefix(param_str) {
                 // if we have only one hex parameter
                 Some(param_str) if param_str.len() <= HEX_CHUNK_SIZE => {
                     let value = parse_hex_value(op, param_str, 1)?;
@@ -247,8 +250,8 @@ pub fn parse_loc_storew(op: &Token, constants: &LocalConstMap) -> Result<Node, P
 /// an appropriate push instruction node.
 fn parse_param_list(op: &Token, constants: &LocalConstMap) -> Result<Node, ParsingError> {
     let values =
-        op.parts().iter().enumerate().skip(1).map(|(param_idx, &param_str)| {
-            match param_str.strip_prefix("0x") {
+        op.parts().iter().enumerate().skip(1).map(
+            |(param_idx, &param_str)| match strip_hex_prefix(param_str) {
                 Some(param_str) => parse_hex_value(op, param_str, param_idx),
                 None => parse_non_hex_param_with_constants_lookup(
                     op,
@@ -256,12 +259,18 @@ fn parse_param_list(op: &Token, constants: &LocalConstMap) -> Result<Node, Parsi
                     param_idx,
                     0..Felt::MODULUS,
                 ),
-            }
-        });
+            },
+        );
 
     build_push_many_instruction(values)
 }
 
+/// Returns the provided parameter without its `0x` (or `0X`) prefix, or None if the parameter is
+/// not a hexadecimal value.
+fn strip_hex_prefix(param_str: &str) -> Option<&str> {
+    param_str.strip_prefix("0x").or_else(|| param_str.strip_prefix("0X"))
+}
+
 /// Parses a non hexadecimal parameter and returns the value. Takes as argument a constant map
 /// for constant lookup.
 fn parse_non_hex_param_with_constants_lookup<R: RangeBounds<u64>>(
@@ -275,7 +284,7 @@ fn parse_non_hex_param_with_constants_lookup<R: RangeBounds<u64>>(
     match CONSTANT_LABEL_PARSER.parse_label(param_str) {
         Ok(_) => constants
             .get(param_str)
-            .cloned()
+            .map(|(value, ..)| *value)
             .ok_or_else(|| ParsingError::const_not_found(op)),
         Err(_) => parse_checked_param(op, param_idx, range),
     }
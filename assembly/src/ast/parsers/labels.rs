@@ -44,32 +44,61 @@ impl LabelParser {
     ///
     /// Returns an error if label violates the rules.
     pub fn parse_label<'a>(&'a self, label: &'a str) -> Result<&str, LabelError> {
+        match self.find_violated_rule(label) {
+            None => Ok(label),
+            Some(LabelRule::NotEmpty) => Err(LabelError::empty_label()),
+            Some(LabelRule::MaxLen) => Err(LabelError::label_too_long(label, self.max_len)),
+            Some(LabelRule::StartWithLetter) => Err(LabelError::invalid_fist_letter(label)),
+            Some(LabelRule::NumbersLettersUnderscore) => Err(LabelError::invalid_label(label)),
+            Some(LabelRule::Caps) => Err(LabelError::must_be_uppercase(label)),
+        }
+    }
+
+    /// Returns true if the label passes label conventions.
+    ///
+    /// This applies the same rules as [LabelParser::parse_label], but does not allocate.
+    pub fn is_valid_label(&self, label: &str) -> bool {
+        self.find_violated_rule(label).is_none()
+    }
+
+    /// Returns the first rule violated by the label, or None if the label complies with all rules.
+    fn find_violated_rule(&self, label: &str) -> Option<LabelRule> {
         if label.is_empty() {
             // label cannot be empty
-            return Err(LabelError::empty_label());
+            Some(LabelRule::NotEmpty)
         } else if label.len() > self.max_len {
             // label cannot be more than `max_len` characters long
-            return Err(LabelError::label_too_long(label, self.max_len));
+            Some(LabelRule::MaxLen)
         } else if self.start_with_letter && !label.chars().next().unwrap().is_ascii_alphabetic() {
             // label must start with a letter
-            return Err(LabelError::invalid_fist_letter(label));
+            Some(LabelRule::StartWithLetter)
         } else if self.numbers_letters_underscore
             && !label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
         {
             // label can consists only of numbers, letters, and underscores
-            return Err(LabelError::invalid_label(label));
+            Some(LabelRule::NumbersLettersUnderscore)
         } else if self.caps
             && !label
                 .chars()
                 .all(|c| !c.is_alphabetic() || (c.is_alphabetic() && c.is_uppercase()))
         {
             // all letters must be uppercase
-            return Err(LabelError::must_be_uppercase(label));
+            Some(LabelRule::Caps)
+        } else {
+            None
         }
-        Ok(label)
     }
 }
 
+/// Rules which can be enforced by a [LabelParser].
+enum LabelRule {
+    NotEmpty,
+    MaxLen,
+    StartWithLetter,
+    NumbersLettersUnderscore,
+    Caps,
+}
+
 // HEX LABEL PARSER
 // ================================================================================================.
 /// Parses an [RpoDigest] from a hex representation. Verifies that the hex string is 66 characters
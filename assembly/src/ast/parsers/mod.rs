@@ -1,11 +1,12 @@
 use super::{
-    bound_into_included_u64, AdviceInjectorNode, BTreeMap, CodeBody, Deserializable, Felt,
-    Instruction, InvocationTarget, LabelError, LibraryPath, LocalConstMap, LocalProcMap, Node,
-    ParsingError, ProcedureAst, ProcedureId, ReExportedProcMap, RpoDigest, SliceReader, StarkField,
-    String, ToString, Token, TokenStream, Vec, MAX_BODY_LEN, MAX_DOCS_LEN, MAX_IMPORTS,
-    MAX_LABEL_LEN, MAX_STACK_WORD_OFFSET,
+    bound_into_included_u64, AdviceInjectorNode, BTreeMap, BTreeSet, CodeBody, Deserializable,
+    Felt, Instruction, InvocationTarget, InvokedProcsMap, LabelError, LibraryPath, LocalConstMap,
+    LocalProcMap, ModuleAst, Node, ParsingError, ParsingOptions, ParsingWarning, ProcedureAst,
+    ProcedureFlags, ProcedureId, ProcedureName, ReExportedProcMap, RpoDigest, SliceReader,
+    SourceLocation, StarkField, String, Symbol, SymbolKind, ToString, Token, TokenStream, Vec,
+    MAX_BODY_LEN, MAX_DOCS_LEN, MAX_IMPORTS, MAX_LABEL_LEN, MAX_STACK_WORD_OFFSET,
 };
-use core::{fmt::Display, ops::RangeBounds};
+use core::{fmt::Display, num::IntErrorKind, ops::RangeBounds};
 
 pub mod adv_ops;
 pub mod field_ops;
@@ -26,23 +27,62 @@ pub use labels::{
 // ================================================================================================
 
 /// Parses all `use` statements into a map of imports which maps a module name (e.g., "u64") to
-/// its fully-qualified path (e.g., "std::math::u64").
+/// its fully-qualified path (e.g., "std::math::u64"), and a map of constants imported via
+/// `use.const` statements.
+///
+/// The modules from which constants are imported are looked up in `const_modules`, and all
+/// constants declared in these modules are imported.
+///
+/// If duplicate imports are allowed by the `options`, importing the same path more than once is
+/// recorded in `warnings` and only the first import is kept.
 pub fn parse_imports(
     tokens: &mut TokenStream,
-) -> Result<BTreeMap<String, LibraryPath>, ParsingError> {
+    options: &ParsingOptions,
+    const_modules: &BTreeMap<LibraryPath, ModuleAst>,
+    warnings: &mut Vec<ParsingWarning>,
+) -> Result<(BTreeMap<String, LibraryPath>, LocalConstMap), ParsingError> {
     let mut imports = BTreeMap::<String, LibraryPath>::new();
+    let mut imported_constants = LocalConstMap::new();
     // read tokens from the token stream until all `use` tokens are consumed
     while let Some(token) = tokens.read() {
         match token.parts()[0] {
+            Token::USE if token.is_const_use() => {
+                let module_path = token.parse_const_use()?;
+                let module = const_modules.get(&module_path).ok_or_else(|| {
+                    ParsingError::const_module_not_found(token, module_path.as_str())
+                })?;
+                for (name, value) in module.constants() {
+                    if imported_constants.contains_key(name) {
+                        return Err(ParsingError::imported_const_conflict(token, name));
+                    }
+                    imported_constants.insert(name.clone(), (*value, None, *token.location()));
+                }
+
+                // consume the `use` token
+                tokens.advance();
+            }
             Token::USE => {
                 let module_path = token.parse_use()?;
                 let module_name = module_path.last();
-                if imports.contains_key(module_name) {
-                    return Err(ParsingError::duplicate_module_import(token, &module_path));
+                match imports.get(module_name) {
+                    Some(path) if *path == module_path && options.allow_duplicate_imports => {
+                        warnings.push(ParsingWarning::duplicate_module_import(token, &module_path));
+                    }
+                    Some(path) if *path == module_path => {
+                        return Err(ParsingError::duplicate_module_import(token, &module_path));
+                    }
+                    Some(path) => {
+                        return Err(ParsingError::conflicting_import_alias(
+                            token,
+                            path,
+                            &module_path,
+                        ));
+                    }
+                    None => {
+                        imports.insert(module_name.to_string(), module_path);
+                    }
                 }
 
-                imports.insert(module_name.to_string(), module_path);
-
                 // consume the `use` token
                 tokens.advance();
             }
@@ -53,25 +93,99 @@ pub fn parse_imports(
     if imports.len() > MAX_IMPORTS {
         return Err(ParsingError::too_many_imports(imports.len(), MAX_IMPORTS));
     }
-    Ok(imports)
+    Ok((imports, imported_constants))
 }
 
-/// Parses all `const` statements into a map which maps a const name to a value
-pub fn parse_constants(tokens: &mut TokenStream) -> Result<LocalConstMap, ParsingError> {
+/// Returns warnings for the imports and constants among `symbols` which are not referenced by any
+/// token starting at position `start` of the stream.
+///
+/// The stream must have been fully consumed. References are detected syntactically: an import is
+/// referenced by an invocation target qualified with its name, and a constant is referenced by
+/// any identifier equal to its name (including in the values of other constants).
+pub fn unused_symbol_warnings(
+    tokens: &mut TokenStream,
+    start: usize,
+    symbols: &[Symbol],
+) -> Vec<ParsingWarning> {
+    let mut identifiers = BTreeSet::new();
+    let mut pos = start;
+    while let Some(token) = tokens.read_at(pos) {
+        if token.parts()[0] != Token::USE {
+            for part in token.parts().iter().skip(1) {
+                // only the value of a constant declaration may refer to other symbols
+                let part = match token.parts()[0] {
+                    Token::CONST => part.split_once('=').map_or("", |(_, value)| value),
+                    _ => part,
+                };
+                identifiers.extend(
+                    part.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == ':'))
+                        .map(String::from),
+                );
+            }
+        }
+        pos += 1;
+    }
+
+    let is_referenced = |symbol: &Symbol| match symbol.kind {
+        SymbolKind::Import => identifiers.iter().any(|identifier| {
+            identifier
+                .strip_prefix(symbol.name.as_str())
+                .map_or(false, |rest| rest.starts_with(LibraryPath::PATH_DELIM))
+        }),
+        SymbolKind::Constant => identifiers.contains(&symbol.name),
+        SymbolKind::Procedure => true,
+    };
+    symbols
+        .iter()
+        .filter(|symbol| !is_referenced(symbol))
+        .map(|symbol| match symbol.kind {
+            SymbolKind::Import => ParsingWarning::unused_import(&symbol.name, symbol.location),
+            _ => ParsingWarning::unreferenced_constant(&symbol.name, symbol.location),
+        })
+        .collect()
+}
+
+/// Parses all `const` statements into a map which maps a const name to its value, doc comments,
+/// and the location of its declaration.
+///
+/// The returned map also contains the provided `imported_constants`, which can be referred to by
+/// the declared constants.
+pub fn parse_constants(
+    tokens: &mut TokenStream,
+    imported_constants: &LocalConstMap,
+) -> Result<LocalConstMap, ParsingError> {
     // instantiate new constant map for this module
-    let mut constants = LocalConstMap::new();
+    let mut constants = imported_constants.clone();
 
     // iterate over tokens until we find a const declaration
     while let Some(token) = tokens.read() {
         match token.parts()[0] {
             Token::CONST => {
-                let (name, value) = parse_constant(token)?;
+                let (name, value) = parse_constant(token, &constants)?;
+                let location = *token.location();
 
+                if imported_constants.contains_key(&name) {
+                    return Err(ParsingError::imported_const_conflict(token, &name));
+                }
                 if constants.contains_key(&name) {
                     return Err(ParsingError::duplicate_const_name(token, &name));
                 }
 
-                constants.insert(name, value);
+                // attach doc comments (if any) to the constant
+                let const_start = tokens.pos();
+                let docs = tokens.take_doc_comment_at(const_start);
+                if let Some(ref docs) = docs {
+                    if docs.len() > MAX_DOCS_LEN {
+                        let token = tokens.read_at(const_start).expect("no const token");
+                        return Err(ParsingError::const_docs_too_long(
+                            token,
+                            docs.len(),
+                            MAX_DOCS_LEN,
+                        ));
+                    }
+                }
+
+                constants.insert(name, (value, docs, location));
                 tokens.advance();
             }
             _ => break,
@@ -81,8 +195,10 @@ pub fn parse_constants(tokens: &mut TokenStream) -> Result<LocalConstMap, Parsin
     Ok(constants)
 }
 
-/// Parses a constant token and returns a (constant_name, constant_value) tuple
-fn parse_constant(token: &Token) -> Result<(String, u64), ParsingError> {
+/// Parses a constant token and returns a (constant_name, constant_value) tuple.
+///
+/// The value of the constant may be an expression referring to previously defined `constants`.
+fn parse_constant(token: &Token, constants: &LocalConstMap) -> Result<(String, u64), ParsingError> {
     match token.num_parts() {
         0 => unreachable!(),
         1 => Err(ParsingError::missing_param(token)),
@@ -95,7 +211,7 @@ fn parse_constant(token: &Token) -> Result<(String, u64), ParsingError> {
                     let name = CONSTANT_LABEL_PARSER
                         .parse_label(const_declaration[0])
                         .map_err(|err| ParsingError::invalid_const_name(token, err))?;
-                    let value = parse_const_value(token, const_declaration[1])?;
+                    let value = parse_const_expression(token, const_declaration[1], constants)?;
                     Ok((name.to_string(), value))
                 }
                 _ => Err(ParsingError::extra_param(token)),
@@ -108,18 +224,74 @@ fn parse_constant(token: &Token) -> Result<(String, u64), ParsingError> {
 // HELPER FUNCTIONS
 // ================================================================================================
 
-/// Parses a constant value and ensures it falls within bounds specified by the caller
+/// Evaluates a constant expression consisting of integer literals and previously defined
+/// constants combined via `+`, `-`, and `*` operators.
+///
+/// Operators have no precedence: the expression is evaluated left-to-right, with all arithmetic
+/// performed in the field.
+fn parse_const_expression(
+    op: &Token,
+    expression: &str,
+    constants: &LocalConstMap,
+) -> Result<u64, ParsingError> {
+    const OPERATORS: [char; 3] = ['+', '-', '*'];
+
+    let mut result = Felt::new(0);
+    let mut operator = '+';
+    let mut remainder = expression;
+    loop {
+        let operand_len = remainder.find(OPERATORS).unwrap_or(remainder.len());
+        let operand = &remainder[..operand_len];
+        let value = if CONSTANT_LABEL_PARSER.is_valid_label(operand) {
+            constants
+                .get(operand)
+                .map(|(value, ..)| *value)
+                .ok_or_else(|| ParsingError::const_not_found(op))?
+        } else {
+            parse_const_value(op, operand)?
+        };
+
+        result = match operator {
+            '+' => result + Felt::new(value),
+            '-' => result - Felt::new(value),
+            '*' => result * Felt::new(value),
+            _ => unreachable!(),
+        };
+
+        match remainder[operand_len..].chars().next() {
+            Some(next) => {
+                operator = next;
+                remainder = &remainder[operand_len + 1..];
+            }
+            None => return Ok(result.as_int()),
+        }
+    }
+}
+
+/// Parses a constant value and ensures it falls within bounds specified by the caller.
+///
+/// The value can be specified in decimal, in hexadecimal with a `0x` (or `0X`) prefix, or in
+/// binary with a `0b` (or `0B`) prefix.
 fn parse_const_value(op: &Token, const_value: &str) -> Result<u64, ParsingError> {
-    let result = const_value
-        .parse::<u64>()
-        .map_err(|err| ParsingError::invalid_const_value(op, const_value, &err.to_string()))?;
-
-    let range = 0..Felt::MODULUS;
-    range.contains(&result).then_some(result).ok_or_else(|| ParsingError::invalid_const_value(op, const_value, format!(
-        "constant value must be greater than or equal to {lower_bound} and less than or equal to {upper_bound}", lower_bound = bound_into_included_u64(range.start_bound(), true),
-        upper_bound = bound_into_included_u64(range.end_bound(), false)
-    )
-    .as_str(),))
+    let max_value = Felt::MODULUS - 1;
+    let result = if let Some(hex) =
+        const_value.strip_prefix("0x").or(const_value.strip_prefix("0X"))
+    {
+        u64::from_str_radix(hex, 16)
+    } else if let Some(bin) = const_value.strip_prefix("0b").or(const_value.strip_prefix("0B")) {
+        u64::from_str_radix(bin, 2)
+    } else {
+        const_value.parse::<u64>()
+    }
+    .map_err(|err| match err.kind() {
+        IntErrorKind::PosOverflow => ParsingError::const_value_overflow(op, const_value, max_value),
+        _ => ParsingError::invalid_const_value(op, const_value, &err.to_string()),
+    })?;
+
+    if result > max_value {
+        return Err(ParsingError::const_value_overflow(op, const_value, max_value));
+    }
+    Ok(result)
 }
 
 /// Parses a param from the op token with the specified type and index. If the param is a constant
@@ -137,7 +309,7 @@ where
         Ok(_) => {
             let constant = constants
                 .get(param_str)
-                .cloned()
+                .map(|(value, ..)| *value)
                 .ok_or_else(|| ParsingError::const_not_found(op))?;
             constant
                 .try_into()
@@ -199,6 +199,58 @@ pub fn parse_u32overflowing_mul(op: &Token) -> Result<Node, ParsingError> {
     }
 }
 
+/// Returns an addition, subtraction, or multiplication instruction node for the generic form of
+/// the operation, in which the overflow behavior is specified as a suffix of the mnemonic, e.g.
+/// `u32add.wrapping` or `u32mul.checked.5`.
+///
+/// The suffix must be one of `checked`, `wrapping`, or `overflowing`; the resulting instruction
+/// is the same as the one produced by the respective specific mnemonic, e.g. `u32wrapping_add`
+/// or `u32checked_mul.5`.
+///
+/// # Errors
+/// Returns an error if the instruction token contains wrong number of parameters, if the suffix
+/// is not one of the supported modes, or if the provided parameter is not a u32 value.
+pub fn parse_u32_arithmetic(op: &Token) -> Result<Node, ParsingError> {
+    const MODES: &str = "expected one of: checked, wrapping, overflowing";
+
+    let mode = match op.num_parts() {
+        0 => unreachable!(),
+        1 => return Err(ParsingError::missing_param(op)),
+        2 | 3 => op.parts()[1],
+        _ => return Err(ParsingError::extra_param(op)),
+    };
+    let value = match op.num_parts() {
+        3 => Some(parse_param::<u32>(op, 2)?),
+        _ => None,
+    };
+
+    let instruction = match (op.parts()[0], mode, value) {
+        ("u32add", "checked", None) => U32CheckedAdd,
+        ("u32add", "checked", Some(value)) => U32CheckedAddImm(value),
+        ("u32add", "wrapping", None) => U32WrappingAdd,
+        ("u32add", "wrapping", Some(value)) => U32WrappingAddImm(value),
+        ("u32add", "overflowing", None) => U32OverflowingAdd,
+        ("u32add", "overflowing", Some(value)) => U32OverflowingAddImm(value),
+        ("u32sub", "checked", None) => U32CheckedSub,
+        ("u32sub", "checked", Some(value)) => U32CheckedSubImm(value),
+        ("u32sub", "wrapping", None) => U32WrappingSub,
+        ("u32sub", "wrapping", Some(value)) => U32WrappingSubImm(value),
+        ("u32sub", "overflowing", None) => U32OverflowingSub,
+        ("u32sub", "overflowing", Some(value)) => U32OverflowingSubImm(value),
+        ("u32mul", "checked", None) => U32CheckedMul,
+        ("u32mul", "checked", Some(value)) => U32CheckedMulImm(value),
+        ("u32mul", "wrapping", None) => U32WrappingMul,
+        ("u32mul", "wrapping", Some(value)) => U32WrappingMulImm(value),
+        ("u32mul", "overflowing", None) => U32OverflowingMul,
+        ("u32mul", "overflowing", Some(value)) => U32OverflowingMulImm(value),
+        ("u32add" | "u32sub" | "u32mul", ..) => {
+            return Err(ParsingError::invalid_param_with_reason(op, 1, MODES))
+        }
+        _ => unreachable!("not a generic u32 arithmetic operation"),
+    };
+    Ok(Instruction(instruction))
+}
+
 /// Returns one of four possible instructions:
 /// - checked without parameter: `U32CheckedDiv`
 /// - unchecked without parameter: `U32UncheckedDiv`
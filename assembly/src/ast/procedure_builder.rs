@@ -0,0 +1,68 @@
+use super::{Node, ParsingError, ProcedureAst, ProcedureName, String, Vec};
+
+// PROCEDURE BUILDER
+// ================================================================================================
+
+/// A builder of [ProcedureAst]s.
+///
+/// Unlike [ProcedureAst::new], the builder accepts the name of the procedure as a plain string,
+/// and validates both the name and the accesses to procedure locals when the procedure is built.
+/// Properties which are not set default to a non-exported procedure without locals, docs, or
+/// instructions.
+#[derive(Debug, Default, Clone)]
+pub struct ProcedureAstBuilder {
+    name: Option<String>,
+    num_locals: u16,
+    body: Vec<Node>,
+    is_export: bool,
+    docs: Option<String>,
+}
+
+impl ProcedureAstBuilder {
+    /// Sets the name of the procedure.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Sets the number of locals of the procedure.
+    pub fn num_locals(mut self, num_locals: u16) -> Self {
+        self.num_locals = num_locals;
+        self
+    }
+
+    /// Sets the body of the procedure.
+    pub fn body(mut self, body: Vec<Node>) -> Self {
+        self.body = body;
+        self
+    }
+
+    /// Sets whether the procedure is exported.
+    pub fn export(mut self, is_export: bool) -> Self {
+        self.is_export = is_export;
+        self
+    }
+
+    /// Sets the doc comments of the procedure.
+    pub fn docs(mut self, docs: impl Into<String>) -> Self {
+        self.docs = Some(docs.into());
+        self
+    }
+
+    /// Builds the procedure.
+    ///
+    /// # Errors
+    /// Returns an error if:
+    /// - The name of the procedure was not set or is not a valid procedure name.
+    /// - The body of the procedure accesses a local with an index not smaller than the number of
+    ///   procedure locals.
+    pub fn build(self) -> Result<ProcedureAst, ParsingError> {
+        let name = self.name.ok_or_else(ParsingError::missing_proc_name)?;
+        let name = ProcedureName::try_from(name.clone())
+            .map_err(|err| ParsingError::invalid_proc_label(&name, err))?;
+
+        let proc = ProcedureAst::new(name, self.num_locals, self.body, self.is_export, self.docs);
+        proc.validate()?;
+        Ok(proc)
+    }
+}
@@ -4,29 +4,101 @@
 
 use super::{ByteReader, ByteWriter, Deserializable, DeserializationError, Serializable};
 
+// SERIALIZATION OPTIONS
+// ================================================================================================
+
 /// Serialization options
 /// Used to enable or disable serialization of parts of the AST.  Serialization options are
 /// serialized along with the AST to make the serialization format self-contained.
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct AstSerdeOptions {
     pub serialize_imports: bool,
+    pub version: AstFormatVersion,
 }
 
 impl AstSerdeOptions {
+    /// Returns new options for serializing ASTs in the current format version.
     pub fn new(serialize_imports: bool) -> Self {
-        Self { serialize_imports }
+        Self {
+            serialize_imports,
+            version: AstFormatVersion::CURRENT,
+        }
+    }
+
+    /// Returns these options with the format version set to `version`.
+    ///
+    /// Serializing into an older version drops the parts of the AST which the older format does
+    /// not support.
+    pub fn with_version(mut self, version: AstFormatVersion) -> Self {
+        self.version = version;
+        self
     }
 }
 
 impl Serializable for AstSerdeOptions {
     fn write_into<W: ByteWriter>(&self, target: &mut W) {
-        target.write_bool(self.serialize_imports);
+        // the lowest bit holds the import flag, and the remaining bits hold the format version;
+        // for the unversioned format, this is the same as writing the import flag as a boolean
+        target.write_u8(self.serialize_imports as u8 | (self.version as u8) << 1);
     }
 }
 
 impl Deserializable for AstSerdeOptions {
     fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
-        let serialize_imports = source.read_bool()?;
-        Ok(Self::new(serialize_imports))
+        let header = source.read_u8()?;
+        let serialize_imports = header & 1 == 1;
+        let version = AstFormatVersion::try_from(header >> 1)?;
+        Ok(Self {
+            serialize_imports,
+            version,
+        })
+    }
+}
+
+// FORMAT VERSION
+// ================================================================================================
+
+/// Version of the binary format of serialized ASTs.
+///
+/// Newer versions extend the format with additional parts of the AST. ASTs serialized in any of
+/// the versions below can be deserialized.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum AstFormatVersion {
+    /// The format used before the serialization format was versioned.
+    V0 = 0,
+    /// Adds execution budgets and flags of procedures, constants of modules together with their
+    /// docs, module docs longer than `u16::MAX` bytes, and end locations of nodes.
+    V1 = 1,
+}
+
+impl AstFormatVersion {
+    /// The version in which ASTs are serialized by default.
+    pub const CURRENT: Self = Self::V1;
+}
+
+impl TryFrom<u8> for AstFormatVersion {
+    type Error = DeserializationError;
+
+    fn try_from(version: u8) -> Result<Self, Self::Error> {
+        match version {
+            0 => Ok(Self::V0),
+            1 => Ok(Self::V1),
+            _ => Err(DeserializationError::InvalidValue(format!(
+                "unsupported AST format version: {version}"
+            ))),
+        }
+    }
+}
+
+impl Serializable for AstFormatVersion {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write_u8(*self as u8);
+    }
+}
+
+impl Deserializable for AstFormatVersion {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        Self::try_from(source.read_u8()?)
     }
 }
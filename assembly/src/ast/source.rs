@@ -0,0 +1,273 @@
+use super::{
+    CodeBody, Instruction, ModuleAst, Node, ProcedureAst, ProcedureFlags, ProcedureId, RenderError,
+    String, Token, Vec,
+};
+use core::fmt::{Result, Write};
+
+// CONSTANTS
+// ================================================================================================
+
+/// The number of spaces by which the nodes of every nested block are indented.
+const INDENT_WIDTH: usize = 4;
+
+// SOURCE RENDERING
+// ================================================================================================
+
+/// Renders the provided module into canonical Miden assembly source.
+///
+/// See [ModuleAst::to_source] for details.
+pub(super) fn render_module(module: &ModuleAst) -> core::result::Result<String, RenderError> {
+    // the parser requires at least one statement besides the module docs
+    if module.imports.is_empty()
+        && module.constants.is_empty()
+        && module.reexported_procs.is_empty()
+        && module.local_procs.is_empty()
+    {
+        return Err(RenderError::empty_module());
+    }
+    check_invoked_procs(module)?;
+
+    let mut sections = Vec::new();
+
+    if let Some(docs) = &module.docs {
+        let mut section = String::new();
+        write_docs(&mut section, docs);
+        sections.push(section);
+    }
+
+    if !module.imports.is_empty() {
+        sections.push(render_section(|section| {
+            for path in module.imports.values() {
+                writeln!(section, "{}.{}", Token::USE, path.as_str())?;
+            }
+            Ok(())
+        }));
+    }
+
+    if !module.constants.is_empty() {
+        sections.push(render_section(|section| {
+            for (name, value) in module.constants.iter() {
+                if let Some(docs) = module.const_docs.get(name) {
+                    write_docs(section, docs);
+                }
+                writeln!(section, "{}.{name}={value}", Token::CONST)?;
+            }
+            Ok(())
+        }));
+    }
+
+    if !module.reexported_procs.is_empty() {
+        sections.push(render_section(|section| render_reexports(section, module)));
+    }
+
+    for proc in module.local_procs.iter() {
+        sections.push(render_section(|section| render_procedure(section, proc, module)));
+    }
+
+    Ok(sections.join("\n"))
+}
+
+/// Returns an error if the provided module invokes or re-exports a procedure which cannot be
+/// rendered by name.
+fn check_invoked_procs(module: &ModuleAst) -> core::result::Result<(), RenderError> {
+    if let Some(proc) = module
+        .reexported_procs
+        .iter()
+        .find(|proc| imported_proc_name(module, &proc.proc_id()).is_none())
+    {
+        return Err(RenderError::unknown_procedure(proc.proc_id()));
+    }
+
+    let mut error = None;
+    for proc in module.local_procs.iter() {
+        proc.body.visit(&mut |node| {
+            let instruction = match node {
+                Node::Instruction(instruction) if error.is_none() => instruction,
+                _ => return,
+            };
+            error = match instruction {
+                Instruction::ExecLocal(index) | Instruction::CallLocal(index)
+                    if *index as usize >= module.local_procs.len() =>
+                {
+                    Some(RenderError::unknown_local_procedure(*index))
+                }
+                Instruction::ExecImported(proc_id) | Instruction::CallImported(proc_id)
+                    if imported_proc_name(module, proc_id).is_none() =>
+                {
+                    Some(RenderError::unknown_procedure(*proc_id))
+                }
+                Instruction::SysCall(proc_id) if kernel_proc_name(module, proc_id).is_none() => {
+                    Some(RenderError::unknown_procedure(*proc_id))
+                }
+                _ => None,
+            };
+        });
+    }
+    error.map_or(Ok(()), Err)
+}
+
+/// Returns a section of the source rendered by `render`.
+fn render_section(render: impl FnOnce(&mut String) -> Result) -> String {
+    let mut section = String::new();
+    render(&mut section).expect("writing to a string cannot fail");
+    section
+}
+
+/// Renders the procedures re-exported by the provided module into `source`.
+///
+/// The names of the re-exported procedures must have been checked via [check_invoked_procs].
+fn render_reexports(source: &mut String, module: &ModuleAst) -> Result {
+    for proc in module.reexported_procs.iter() {
+        let name = proc.name().as_str();
+        let (alias, ref_name) =
+            imported_proc_name(module, &proc.proc_id()).expect("unknown re-exported procedure");
+        if ref_name == name {
+            writeln!(source, "{}.{alias}::{name}", Token::EXPORT)?;
+        } else {
+            let delim = Token::EXPORT_ALIAS_DELIM;
+            writeln!(source, "{}.{alias}::{ref_name}{delim}{name}", Token::EXPORT)?;
+        }
+    }
+    Ok(())
+}
+
+/// Renders the provided procedure, including its docs and attributes, into `source`.
+///
+/// The `module` is used to resolve the names of invoked procedures.
+fn render_procedure(source: &mut String, proc: &ProcedureAst, module: &ModuleAst) -> Result {
+    // only the docs of exported procedures are retained by the parser
+    if let (true, Some(docs)) = (proc.is_export, &proc.docs) {
+        write_docs(source, docs);
+    }
+
+    if let Some(budget) = proc.budget {
+        writeln!(
+            source,
+            "{}{}={budget}{}",
+            Token::ATTRIBUTE_PREFIX,
+            Token::BUDGET,
+            Token::ATTRIBUTE_SUFFIX
+        )?;
+    }
+
+    for (flag, name) in [
+        (ProcedureFlags::INLINE, Token::INLINE),
+        (ProcedureFlags::NOINLINE, Token::NOINLINE),
+    ] {
+        if proc.flags.contains(flag) {
+            writeln!(source, "{}{name}{}", Token::ATTRIBUTE_PREFIX, Token::ATTRIBUTE_SUFFIX)?;
+        }
+    }
+
+    let keyword = if proc.is_export { Token::EXPORT } else { Token::PROC };
+    write!(source, "{keyword}.{}", proc.name.as_str())?;
+    if proc.num_locals > 0 {
+        write!(source, ".{}", proc.num_locals)?;
+    }
+    source.push('\n');
+
+    render_body(source, &proc.body, module, 1)?;
+    writeln!(source, "{}", Token::END)
+}
+
+/// Renders the nodes of the provided code body into `source`, indenting them by `depth` levels.
+fn render_body(source: &mut String, body: &CodeBody, module: &ModuleAst, depth: usize) -> Result {
+    let indent = " ".repeat(depth * INDENT_WIDTH);
+    for node in body.nodes() {
+        match node {
+            Node::Instruction(instruction) => {
+                source.push_str(&indent);
+                render_instruction(source, instruction, module)?;
+                source.push('\n');
+            }
+            Node::IfElse {
+                true_case,
+                false_case,
+            } => {
+                writeln!(source, "{indent}{}.true", Token::IF)?;
+                render_body(source, true_case, module, depth + 1)?;
+                if !false_case.nodes().is_empty() {
+                    writeln!(source, "{indent}{}", Token::ELSE)?;
+                    render_body(source, false_case, module, depth + 1)?;
+                }
+                writeln!(source, "{indent}{}", Token::END)?;
+            }
+            Node::Repeat { times, body } => {
+                writeln!(source, "{indent}{}.{times}", Token::REPEAT)?;
+                render_body(source, body, module, depth + 1)?;
+                writeln!(source, "{indent}{}", Token::END)?;
+            }
+            Node::While { body } => {
+                writeln!(source, "{indent}{}.true", Token::WHILE)?;
+                render_body(source, body, module, depth + 1)?;
+                writeln!(source, "{indent}{}", Token::END)?;
+            }
+            // no-op nodes have no source representation
+            Node::Nop => {}
+        }
+    }
+    Ok(())
+}
+
+/// Renders the provided instruction into `source`.
+///
+/// Invoked procedures are rendered by name; their names must have been checked via
+/// [check_invoked_procs].
+fn render_instruction(
+    source: &mut String,
+    instruction: &Instruction,
+    module: &ModuleAst,
+) -> Result {
+    match instruction {
+        Instruction::ExecLocal(index) | Instruction::CallLocal(index) => {
+            let keyword = match instruction {
+                Instruction::ExecLocal(_) => Token::EXEC,
+                _ => Token::CALL,
+            };
+            let name = module.local_procs[*index as usize].name.as_str();
+            write!(source, "{keyword}.{name}")
+        }
+        Instruction::ExecImported(proc_id) | Instruction::CallImported(proc_id) => {
+            let keyword = match instruction {
+                Instruction::ExecImported(_) => Token::EXEC,
+                _ => Token::CALL,
+            };
+            let (alias, name) =
+                imported_proc_name(module, proc_id).expect("unknown imported procedure");
+            write!(source, "{keyword}.{alias}::{name}")
+        }
+        Instruction::SysCall(proc_id) => {
+            let name = kernel_proc_name(module, proc_id).expect("unknown kernel procedure");
+            write!(source, "{}.{name}", Token::SYSCALL)
+        }
+        _ => write!(source, "{instruction}"),
+    }
+}
+
+/// Returns the import alias of the module and the name of the procedure with the specified ID,
+/// or None if the procedure is not known to the `module`, or its module is not imported.
+fn imported_proc_name<'a>(
+    module: &'a ModuleAst,
+    proc_id: &ProcedureId,
+) -> Option<(&'a str, &'a str)> {
+    let (name, path) = module.invoked_procs.get(proc_id)?;
+    let (alias, _) = module.imports.iter().find(|(_, import)| *import == path)?;
+    Some((alias.as_str(), name.as_str()))
+}
+
+/// Returns the name of the kernel procedure with the specified ID, or None if the procedure is
+/// not known to the `module` as a kernel procedure.
+fn kernel_proc_name<'a>(module: &'a ModuleAst, proc_id: &ProcedureId) -> Option<&'a str> {
+    let (name, path) = module.invoked_procs.get(proc_id)?;
+    path.is_kernel_path().then_some(name.as_str())
+}
+
+/// Renders the provided docs as a block of doc comments.
+fn write_docs(source: &mut String, docs: &str) {
+    for line in docs.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        source.push_str(Token::DOC_COMMENT_PREFIX);
+        source.push(' ');
+        source.push_str(line);
+        source.push('\n');
+    }
+}
@@ -0,0 +1,223 @@
+use super::{CodeBody, Instruction, Node, SourceLocation, Vec};
+use core::fmt;
+
+// STACK WARNING
+// ================================================================================================
+
+/// A warning about an instruction which may consume more elements than are available on the
+/// stack, as reported by
+/// [ProcedureAst::check_stack_balance](super::ProcedureAst::check_stack_balance).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackWarning {
+    /// The instruction which consumes more elements than are available.
+    pub instruction: Instruction,
+    /// Location of the instruction; this is the default location if the source locations of the
+    /// procedure are not present.
+    pub location: SourceLocation,
+    /// The number of stack elements the instruction consumes.
+    pub required: usize,
+    /// The number of stack elements available before the instruction is executed.
+    pub available: usize,
+}
+
+impl fmt::Display for StackWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "stack underflow at {}: '{}' consumes {} elements, but only {} are available",
+            self.location, self.instruction, self.required, self.available
+        )
+    }
+}
+
+// STACK BALANCE ANALYSIS
+// ================================================================================================
+
+/// Tracks the depth of the stack through the straight-line prefix of the `body`, starting with
+/// `inputs` elements, and returns warnings for all instructions which consume more elements than
+/// are available.
+///
+/// The analysis stops at the first control flow block or instruction with an effect which cannot
+/// be determined statically (e.g., procedure invocations), as nothing is known about the depth of
+/// the stack after it.
+pub(super) fn check_body(body: &CodeBody, inputs: usize) -> Vec<StackWarning> {
+    let mut warnings = Vec::new();
+    let mut depth = inputs;
+    for (i, node) in body.nodes().iter().enumerate() {
+        let instruction = match node {
+            Node::Instruction(instruction) => instruction,
+            Node::Nop => continue,
+            Node::IfElse { .. } | Node::Repeat { .. } | Node::While { .. } => break,
+        };
+        let (required, produced) = match stack_effect(instruction) {
+            Some(effect) => effect,
+            None => break,
+        };
+        if required > depth {
+            warnings.push(StackWarning {
+                instruction: instruction.clone(),
+                location: body.source_locations().get(i).copied().unwrap_or_default(),
+                required,
+                available: depth,
+            });
+            // continue as if the missing elements were present
+            depth = required;
+        }
+        depth = depth - required + produced;
+    }
+    warnings
+}
+
+/// Returns the number of elements consumed from the top of the stack by the `instruction`,
+/// together with the number of elements it puts onto the stack in their place.
+///
+/// Elements which an instruction only inspects (e.g., the elements copied via `dup`) count as both
+/// consumed and produced. Returns None if the effect cannot be determined statically.
+fn stack_effect(instruction: &Instruction) -> Option<(usize, usize)> {
+    use Instruction::*;
+
+    let effect = match instruction {
+        // ----- assertions and tests -------------------------------------------------------------
+        Assert | Assertz => (1, 0),
+        AssertEq => (2, 0),
+        AssertEqw => (8, 0),
+        U32Test => (1, 2),
+        U32TestW => (4, 5),
+        U32Assert | U32Cast => (1, 1),
+        U32Assert2 => (2, 2),
+        U32AssertW => (4, 4),
+
+        // ----- field and u32 operations ---------------------------------------------------------
+        Add | Sub | Mul | Div | Exp | ExpBitLength(_) | And | Or | Xor | Eq | Neq | Lt | Lte
+        | Gt | Gte => (2, 1),
+        AddImm(_) | SubImm(_) | MulImm(_) | DivImm(_) | ExpImm(_) | EqImm(_) | NeqImm(_) | Neg
+        | Inv | Incr | Pow2 | Not | IsOdd => (1, 1),
+        Eqw => (8, 9),
+        Ext2Add | Ext2Sub | Ext2Mul | Ext2Div => (4, 2),
+        Ext2Neg | Ext2Inv => (2, 2),
+        U32Split => (1, 2),
+        U32CheckedAdd | U32WrappingAdd | U32CheckedSub | U32WrappingSub | U32CheckedMul
+        | U32WrappingMul | U32CheckedDiv | U32UncheckedDiv | U32CheckedMod | U32UncheckedMod
+        | U32CheckedAnd | U32CheckedOr | U32CheckedXor | U32CheckedShr | U32UncheckedShr
+        | U32CheckedShl | U32UncheckedShl | U32CheckedRotr | U32UncheckedRotr | U32CheckedRotl
+        | U32UncheckedRotl | U32CheckedEq | U32CheckedNeq | U32CheckedLt | U32UncheckedLt
+        | U32CheckedLte | U32UncheckedLte | U32CheckedGt | U32UncheckedGt | U32CheckedGte
+        | U32UncheckedGte | U32CheckedMin | U32UncheckedMin | U32CheckedMax | U32UncheckedMax => {
+            (2, 1)
+        }
+        U32CheckedAddImm(_)
+        | U32WrappingAddImm(_)
+        | U32CheckedSubImm(_)
+        | U32WrappingSubImm(_)
+        | U32CheckedMulImm(_)
+        | U32WrappingMulImm(_)
+        | U32CheckedDivImm(_)
+        | U32UncheckedDivImm(_)
+        | U32CheckedModImm(_)
+        | U32UncheckedModImm(_)
+        | U32CheckedShrImm(_)
+        | U32UncheckedShrImm(_)
+        | U32CheckedShlImm(_)
+        | U32UncheckedShlImm(_)
+        | U32CheckedRotrImm(_)
+        | U32UncheckedRotrImm(_)
+        | U32CheckedRotlImm(_)
+        | U32UncheckedRotlImm(_)
+        | U32CheckedEqImm(_)
+        | U32CheckedNeqImm(_)
+        | U32CheckedNot
+        | U32CheckedPopcnt
+        | U32UncheckedPopcnt => (1, 1),
+        U32OverflowingAdd | U32OverflowingSub | U32OverflowingMul | U32CheckedDivMod
+        | U32UncheckedDivMod => (2, 2),
+        U32OverflowingAddImm(_)
+        | U32OverflowingSubImm(_)
+        | U32OverflowingMulImm(_)
+        | U32CheckedDivModImm(_)
+        | U32UncheckedDivModImm(_) => (1, 2),
+        U32OverflowingAdd3 | U32OverflowingMadd => (3, 2),
+        U32WrappingAdd3 | U32WrappingMadd => (3, 1),
+
+        // ----- stack manipulation ---------------------------------------------------------------
+        Drop => (1, 0),
+        DropW => (4, 0),
+        PadW => (0, 4),
+        Dup0 => (1, 2),
+        Dup1 => (2, 3),
+        Dup2 => (3, 4),
+        Dup3 => (4, 5),
+        Dup4 => (5, 6),
+        Dup5 => (6, 7),
+        Dup6 => (7, 8),
+        Dup7 => (8, 9),
+        Dup8 => (9, 10),
+        Dup9 => (10, 11),
+        Dup10 => (11, 12),
+        Dup11 => (12, 13),
+        Dup12 => (13, 14),
+        Dup13 => (14, 15),
+        Dup14 => (15, 16),
+        Dup15 => (16, 17),
+        DupW0 => (4, 8),
+        DupW1 => (8, 12),
+        DupW2 => (12, 16),
+        DupW3 => (16, 20),
+        Swap1 => (2, 2),
+        Swap2 | MovUp2 | MovDn2 => (3, 3),
+        Swap3 | MovUp3 | MovDn3 => (4, 4),
+        Swap4 | MovUp4 | MovDn4 => (5, 5),
+        Swap5 | MovUp5 | MovDn5 => (6, 6),
+        Swap6 | MovUp6 | MovDn6 => (7, 7),
+        Swap7 | MovUp7 | MovDn7 => (8, 8),
+        Swap8 | MovUp8 | MovDn8 => (9, 9),
+        Swap9 | MovUp9 | MovDn9 => (10, 10),
+        Swap10 | MovUp10 | MovDn10 => (11, 11),
+        Swap11 | MovUp11 | MovDn11 => (12, 12),
+        Swap12 | MovUp12 | MovDn12 => (13, 13),
+        Swap13 | MovUp13 | MovDn13 => (14, 14),
+        Swap14 | MovUp14 | MovDn14 => (15, 15),
+        Swap15 | MovUp15 | MovDn15 => (16, 16),
+        SwapW1 => (8, 8),
+        SwapW2 | MovUpW2 | MovDnW2 => (12, 12),
+        SwapW3 | MovUpW3 | MovDnW3 | SwapDw => (16, 16),
+        CSwap => (3, 2),
+        CSwapW => (9, 8),
+        CDrop => (3, 1),
+        CDropW => (9, 4),
+
+        // ----- input / output operations --------------------------------------------------------
+        PushU8(_) | PushU16(_) | PushU32(_) | PushFelt(_) => (0, 1),
+        PushWord(_) => (0, 4),
+        PushU8List(values) => (0, values.len()),
+        PushU16List(values) => (0, values.len()),
+        PushU32List(values) => (0, values.len()),
+        PushFeltList(values) => (0, values.len()),
+        Locaddr(_) | Sdepth | Clk | MemLoadImm(_) | LocLoad(_) => (0, 1),
+        Caller => (4, 4),
+        MemLoad => (1, 1),
+        MemLoadW => (5, 4),
+        MemLoadWImm(_) | LocLoadW(_) | MemStoreWImm(_) | LocStoreW(_) | AdvLoadW => (4, 4),
+        MemStore => (2, 0),
+        MemStoreImm(_) | LocStore(_) => (1, 0),
+        MemStoreW => (5, 4),
+        MemStream | AdvPipe => (13, 13),
+        AdvPush(n) => (0, *n as usize),
+
+        // ----- cryptographic operations ---------------------------------------------------------
+        Hash => (4, 4),
+        HMerge => (8, 4),
+        HPerm => (12, 12),
+        MTreeGet => (6, 8),
+        MTreeSet => (10, 8),
+        MTreeMerge => (8, 4),
+        MTreeVerify => (10, 10),
+
+        // ----- debugging ------------------------------------------------------------------------
+        Breakpoint => (0, 0),
+
+        // ----- effects which depend on runtime state --------------------------------------------
+        AdvInject(_) | FriExt2Fold4 | ExecLocal(_) | ExecImported(_) | ExecMastRoot(_)
+        | CallLocal(_) | CallMastRoot(_) | CallImported(_) | SysCall(_) => return None,
+    };
+    Some(effect)
+}
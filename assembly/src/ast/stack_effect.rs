@@ -0,0 +1,75 @@
+use super::{String, ToString, Vec};
+
+// STACK EFFECT
+// ================================================================================================
+
+/// The effect of a procedure on the operand stack, as documented by the author of the procedure.
+///
+/// A stack effect is declared via a line of procedure doc comments of the form:
+///
+/// ```text
+/// #! inputs: [a, b] outputs: [c]
+/// ```
+///
+/// The elements of both lists are listed starting from the top of the stack. Element names may
+/// consist only of ASCII letters, numbers, and underscores.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StackEffect {
+    /// Names of the elements which the procedure expects on the stack.
+    pub inputs: Vec<String>,
+    /// Names of the elements which the procedure leaves on the stack.
+    pub outputs: Vec<String>,
+}
+
+impl StackEffect {
+    // CONSTANTS
+    // --------------------------------------------------------------------------------------------
+
+    /// Prefix of the list of stack inputs.
+    pub const INPUTS_PREFIX: &'static str = "inputs:";
+    /// Prefix of the list of stack outputs.
+    pub const OUTPUTS_PREFIX: &'static str = "outputs:";
+
+    // PARSER
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns the stack effect declared by the first line of `docs` which is a well-formed stack
+    /// effect declaration, or None if there is no such line.
+    ///
+    /// Malformed declarations are not an error: such lines are treated as plain docs.
+    pub fn parse_docs(docs: &str) -> Option<Self> {
+        docs.lines().find_map(|line| Self::parse_line(line.trim()))
+    }
+
+    /// Parses a single line of the form `inputs: [a, b] outputs: [c]`.
+    fn parse_line(line: &str) -> Option<Self> {
+        let rest = line.strip_prefix(Self::INPUTS_PREFIX)?;
+        let (inputs, rest) = parse_list(rest.trim_start())?;
+        let rest = rest.trim_start().strip_prefix(Self::OUTPUTS_PREFIX)?;
+        let (outputs, rest) = parse_list(rest.trim_start())?;
+        rest.trim().is_empty().then_some(Self { inputs, outputs })
+    }
+}
+
+/// Parses a bracketed, comma-separated list of element names at the start of `source`, and
+/// returns the names together with the remainder of `source`.
+fn parse_list(source: &str) -> Option<(Vec<String>, &str)> {
+    let (list, rest) = source.strip_prefix('[')?.split_once(']')?;
+    if list.trim().is_empty() {
+        return Some((Vec::new(), rest));
+    }
+
+    let names = list
+        .split(',')
+        .map(str::trim)
+        .map(|name| is_valid_name(name).then(|| name.to_string()))
+        .collect::<Option<Vec<_>>>()?;
+    (names.len() <= u8::MAX as usize).then_some((names, rest))
+}
+
+/// Returns true if `name` is a valid name of a stack element.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= u8::MAX as usize
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -0,0 +1,39 @@
+use super::{SourceLocation, String};
+
+// SYMBOL
+// ================================================================================================
+
+/// A named item defined in Miden assembly source, such as a constant, a procedure, or an import.
+///
+/// Symbols are intended for tooling which needs an outline of the source (e.g., IDEs).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// Name of the symbol; for imports, this is the name under which the module is imported.
+    pub name: String,
+    /// Kind of the item this symbol refers to.
+    pub kind: SymbolKind,
+    /// Location of the declaration of this symbol.
+    pub location: SourceLocation,
+}
+
+impl Symbol {
+    /// Returns a new [Symbol] instantiated with the specified properties.
+    pub fn new(name: String, kind: SymbolKind, location: SourceLocation) -> Self {
+        Self {
+            name,
+            kind,
+            location,
+        }
+    }
+}
+
+/// The kind of item a [Symbol] refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// A constant declared via a `const` statement.
+    Constant = 0,
+    /// A procedure declared via a `proc` or `export` statement.
+    Procedure = 1,
+    /// A module imported via a `use` statement.
+    Import = 2,
+}
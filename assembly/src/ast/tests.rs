@@ -1,8 +1,11 @@
 use super::{
-    AstSerdeOptions, BTreeMap, CodeBody, Felt, Instruction, LocalProcMap, ModuleAst, Node,
-    ParsingError, ProcedureAst, ProcedureId, ProgramAst, SourceLocation, Token,
+    assert_codebody_roundtrips, walk_body, AstFormatVersion, AstSerdeOptions, BTreeMap, BTreeSet,
+    CallTarget, CodeBody, CstNode, Difference, Felt, Instruction, LabelError, LibraryPath,
+    LocalProcMap, ModuleAst, Node, ParsingError, ParsingOptions, ParsingWarning, ProcedureAst,
+    ProcedureFlags, ProcedureId, ProcedureName, ProgramAst, ProgramParts, RenderError,
+    SourceLocation, SourceSpan, StackEffect, StackWarning, Symbol, SymbolKind, Token, Visitor,
 };
-use vm_core::utils::SliceReader;
+use vm_core::utils::{Deserializable, DeserializationError, Serializable, SliceReader};
 
 // UNIT TESTS
 // ================================================================================================
@@ -50,6 +53,58 @@ fn test_ast_parsing_program_push() {
     assert_program_output(source, BTreeMap::new(), nodes);
 }
 
+#[test]
+fn test_ast_parsing_program_push_hex() {
+    let source = "begin push.0xDEADBEEF push.0X0a push.0xffffffff00000000 push.10.0x0b.12.0X0D end";
+    let nodes: Vec<Node> = vec![
+        Node::Instruction(Instruction::PushU32(0xdeadbeef)),
+        Node::Instruction(Instruction::PushU8(10)),
+        Node::Instruction(Instruction::PushFelt(Felt::new(0xffffffff00000000))),
+        Node::Instruction(Instruction::PushU8List(vec![10, 11, 12, 13])),
+    ];
+    assert_program_output(source, BTreeMap::new(), nodes);
+
+    // hex values must be less than the field modulus
+    let source = "begin push.0xffffffff00000001 end";
+    let err = ProgramAst::parse(source).unwrap_err();
+    let token = Token::new("push.0xffffffff00000001", SourceLocation::new(1, 7));
+    let reason = "hex string 'ffffffff00000001' contains value greater than field modulus";
+    assert_eq!(err, ParsingError::invalid_param_with_reason(&token, 1, reason));
+
+    let source = "begin push.1.0xffffffff00000001 end";
+    let err = ProgramAst::parse(source).unwrap_err();
+    let token = Token::new("push.1.0xffffffff00000001", SourceLocation::new(1, 7));
+    assert_eq!(err, ParsingError::invalid_param_with_reason(&token, 2, reason));
+}
+
+#[test]
+fn test_ast_parsing_exec_mast_root() {
+    let root = "0xc2545da99d3a1f3f38d957c7893c44d78998d8ea8b11aba7e22c8c2b2a21b3b4";
+    let source = format!("begin exec.{root} call.{root} end");
+    let program = ProgramAst::parse(&source).unwrap();
+    let digest = match program.body_nodes() {
+        [Node::Instruction(Instruction::ExecMastRoot(a)), Node::Instruction(Instruction::CallMastRoot(b))] =>
+        {
+            assert_eq!(a, b);
+            *a
+        }
+        nodes => panic!("unexpected nodes: {nodes:?}"),
+    };
+    assert_eq!(Instruction::ExecMastRoot(digest).to_string(), format!("exec.{root}"));
+
+    // the root must be preserved through serialization
+    let bytes = program.to_bytes(AstSerdeOptions::new(false));
+    assert_eq!(ProgramAst::from_bytes(&bytes).unwrap().body_nodes(), program.body_nodes());
+
+    // the root must be exactly 32 bytes long
+    let source = "begin exec.0x1234 end";
+    let err = ProgramAst::parse(source).unwrap_err();
+    assert_eq!(
+        err.message(),
+        "invalid procedure root invocation: 0x1234 - rpo digest hex label must have 66 characters, but was 6"
+    );
+}
+
 #[test]
 fn test_ast_parsing_program_u32() {
     let source = "\
@@ -85,6 +140,50 @@ fn test_ast_parsing_program_u32() {
     assert_program_output(source, BTreeMap::new(), nodes);
 }
 
+#[test]
+fn test_ast_parsing_program_u32_mode_suffix() {
+    let source = "\
+    begin
+        u32add.checked u32add.wrapping u32add.overflowing.5
+        u32sub.checked.1 u32sub.wrapping u32sub.overflowing
+        u32mul.checked u32mul.wrapping.2 u32mul.overflowing
+    end";
+    let nodes: Vec<Node> = vec![
+        Node::Instruction(Instruction::U32CheckedAdd),
+        Node::Instruction(Instruction::U32WrappingAdd),
+        Node::Instruction(Instruction::U32OverflowingAddImm(5)),
+        Node::Instruction(Instruction::U32CheckedSubImm(1)),
+        Node::Instruction(Instruction::U32WrappingSub),
+        Node::Instruction(Instruction::U32OverflowingSub),
+        Node::Instruction(Instruction::U32CheckedMul),
+        Node::Instruction(Instruction::U32WrappingMulImm(2)),
+        Node::Instruction(Instruction::U32OverflowingMul),
+    ];
+    assert_program_output(source, BTreeMap::new(), nodes);
+
+    // the generic form is equivalent to the specific mnemonic
+    let generic = ProgramAst::parse("begin u32add.wrapping.7 end").unwrap();
+    let specific = ProgramAst::parse("begin u32wrapping_add.7 end").unwrap();
+    assert_eq!(generic.body_nodes(), specific.body_nodes());
+
+    // the mode must be one of the supported ones
+    let err = ProgramAst::parse("begin u32add.saturating end").unwrap_err();
+    assert_eq!(
+        err.message(),
+        "malformed instruction 'u32add.saturating', parameter saturating is invalid: \
+        expected one of: checked, wrapping, overflowing"
+    );
+
+    // the mode is required, and at most one immediate value can be provided
+    let err = ProgramAst::parse("begin u32mul end").unwrap_err();
+    assert_eq!(err.message(), "malformed instruction 'u32mul': missing required parameter");
+    let err = ProgramAst::parse("begin u32sub.wrapping.1.2 end").unwrap_err();
+    assert_eq!(
+        err.message(),
+        "malformed instruction 'u32sub.wrapping.1.2': too many parameters provided"
+    );
+}
+
 #[test]
 fn test_ast_parsing_program_proc() {
     let source = "\
@@ -439,176 +538,1870 @@ fn parsed_while_if_body() {
     assert_eq!(body, expected);
 }
 
-// PROCEDURE IMPORTS
-// ================================================================================================
-
 #[test]
-fn test_missing_import() {
+fn test_code_body_visit_mut() {
     let source = "\
     begin
-        exec.u64::add
+        push.1
+        if.true
+            push.2
+            while.true
+                push.3
+            end
+        else
+            repeat.2
+                push.4
+            end
+        end
     end";
 
-    let result = ProgramAst::parse(source);
-    match result {
-        Ok(_) => assert!(false),
-        Err(err) => assert!(err.to_string().contains("module 'u64' was not imported")),
-    }
-}
+    let program = ProgramAst::parse(source).unwrap();
+    let mut body = program.body().clone();
+    let locations = body.source_locations().to_vec();
+
+    let mut num_visited = 0;
+    body.visit_mut(&mut |node| {
+        num_visited += 1;
+        if let Node::Instruction(Instruction::PushU8(value)) = node {
+            *value *= 10;
+        }
+    });
+    assert_eq!(num_visited, 7);
 
-// INVALID BODY TESTS
-// ================================================================================================
+    let expected = "\
+    begin
+        push.10
+        if.true
+            push.20
+            while.true
+                push.30
+            end
+        else
+            repeat.2
+                push.40
+            end
+        end
+    end";
+    let expected = ProgramAst::parse(expected).unwrap();
+    assert_eq!(&body, expected.body());
+    assert_eq!(body.source_locations(), locations);
+}
 
 #[test]
-fn test_use_in_proc_body() {
+fn test_code_body_visitor() {
+    struct OpcodeCounter {
+        opcode: Instruction,
+        num_instructions: usize,
+        num_nodes: usize,
+    }
+
+    impl Visitor for OpcodeCounter {
+        fn visit_node(&mut self, _node: &Node) {
+            self.num_nodes += 1;
+        }
+
+        fn visit_instruction(&mut self, instruction: &Instruction) {
+            if *instruction == self.opcode {
+                self.num_instructions += 1;
+            }
+        }
+    }
+
     let source = "\
-    export.foo.1
-        loc_load.0
-        use
+    begin
+        add
+        if.true
+            add
+            while.true
+                mul
+                add
+            end
+        else
+            repeat.4
+                add
+            end
+        end
+        mul
     end";
+    let program = ProgramAst::parse(source).unwrap();
 
-    let result = ModuleAst::parse(source);
-    match result {
-        Ok(_) => assert!(false),
-        Err(err) => assert!(err.to_string().contains("import in procedure body")),
-    }
+    let mut counter = OpcodeCounter {
+        opcode: Instruction::Add,
+        num_instructions: 0,
+        num_nodes: 0,
+    };
+    walk_body(program.body(), &mut counter);
+    assert_eq!(counter.num_instructions, 4);
+    assert_eq!(counter.num_nodes, 9);
 }
 
 #[test]
-fn test_unterminated_proc() {
-    let source = "proc.foo add mul begin push.1 end";
+fn test_code_body_strip_nops() {
+    let source = "\
+    begin
+        push.1
+        push.2
+        if.true
+            push.3
+            add
+        end
+        push.4
+    end";
 
-    let result = ModuleAst::parse(source);
-    match result {
-        Ok(_) => assert!(false),
-        Err(err) => assert!(err.to_string().contains("procedure 'foo' has no matching end")),
+    let program = ProgramAst::parse(source).unwrap();
+    let mut body = program.body().clone();
+    let locations = body.source_locations().to_vec();
+    let (if_true_locations, if_true_end) = match &body.nodes()[2] {
+        Node::IfElse { true_case, .. } => {
+            let locations = true_case.source_locations();
+            (locations.to_vec(), locations[2])
+        }
+        _ => panic!("expected an if-else node"),
+    };
+
+    // replace `push.2` and `push.3` with nops
+    body.visit_mut(&mut |node| {
+        if matches!(node, Node::Instruction(Instruction::PushU8(2 | 3))) {
+            *node = Node::Nop;
+        }
+    });
+    assert_eq!(body.nodes()[1], Node::Nop);
+    let nop_body = body.clone();
+
+    body.strip_nops();
+    let expected = CodeBody::new([
+        Node::Instruction(Instruction::PushU8(1)),
+        Node::IfElse {
+            true_case: CodeBody::new([Node::Instruction(Instruction::Add)]),
+            false_case: CodeBody::default(),
+        },
+        Node::Instruction(Instruction::PushU8(4)),
+    ]);
+    assert_eq!(body, expected);
+
+    // the remaining nodes must stay aligned with their original locations
+    let expected_locations = [locations[0], locations[2], locations[3], locations[4]];
+    assert_eq!(body.source_locations(), expected_locations);
+    match &body.nodes()[1] {
+        Node::IfElse { true_case, .. } => {
+            assert_eq!(true_case.source_locations(), [if_true_locations[1], if_true_end]);
+        }
+        _ => panic!("expected an if-else node"),
     }
+
+    // nops are skipped during serialization, together with their source locations
+    let mut with_nops = program.clone();
+    with_nops.body = nop_body;
+    let mut stripped = program;
+    stripped.body = body;
+    let options = AstSerdeOptions::new(true);
+    let bytes = with_nops.to_bytes_with_locations(options);
+    assert_eq!(bytes, stripped.to_bytes_with_locations(options));
+    assert_eq!(ProgramAst::from_bytes_with_locations(&bytes).unwrap(), stripped);
+    assert_codebody_roundtrips(with_nops.body());
 }
 
 #[test]
-fn test_unterminated_if() {
-    let source = "proc.foo add mul if.true add.2 begin push.1 end";
+fn test_ast_procedure_rename() {
+    let source = "\
+    #! Module docs
 
-    let result = ModuleAst::parse(source);
-    match result {
-        Ok(_) => assert!(false),
-        Err(err) => assert!(err.to_string().contains("if without matching else/end")),
-    }
+    #! Procedure docs
+    export.foo.2
+        loc_store.0
+        if.true
+            loc_load.1
+        end
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+    let proc = module.procs()[0].clone();
+
+    let renamed = proc.with_name("bar".to_string().try_into().unwrap());
+    assert_eq!(renamed.name.as_ref(), "bar");
+    assert_eq!(proc.name.as_ref(), "foo");
+
+    let renamed = proc.clone().rename("baz".to_string().try_into().unwrap());
+    assert_eq!(renamed.name.as_ref(), "baz");
+    assert_eq!(renamed.body, proc.body);
+    assert_eq!(renamed.docs, proc.docs);
+    assert_eq!(renamed.num_locals, proc.num_locals);
+    assert_eq!(renamed.is_export, proc.is_export);
+    assert!(renamed.source_locations().eq(proc.source_locations()));
 }
 
-// DOCUMENTATION PARSING TESTS
-// ================================================================================================
-
 #[test]
-fn test_ast_parsing_simple_docs() {
+fn test_ast_procedure_peephole_optimize() {
     let source = "\
-    #! proc doc
-    export.foo.1
-        loc_load.0
+    export.foo
+        push.1
+        push.2
+        drop
+        drop
+        push.3
+        push.3
+        add
     end";
+    let module = ModuleAst::parse(source).unwrap();
+    let mut proc = module.procs()[0].clone();
+    let locations = proc.body.source_locations().to_vec();
 
-    let docs_foo = "proc doc".to_string();
-    let procedure = ProcedureAst::new(
-        String::from("foo").try_into().unwrap(),
-        1,
-        [Node::Instruction(Instruction::LocLoad(0))].to_vec(),
-        true,
-        Some(docs_foo),
-    )
-    .with_source_locations(
-        [SourceLocation::new(3, 9), SourceLocation::new(4, 5)],
-        SourceLocation::new(2, 5),
-    );
+    // both push/drop pairs are cancelled and the repeated push is replaced with a `dup.0`
+    assert_eq!(proc.peephole_optimize(), 3);
+    let expected = CodeBody::new([
+        Node::Instruction(Instruction::PushU8(3)),
+        Node::Instruction(Instruction::Dup0),
+        Node::Instruction(Instruction::Add),
+    ]);
+    assert_eq!(proc.body, expected);
+    assert_eq!(proc.body.source_locations(), &locations[4..]);
+
+    // nothing is left to optimize
+    assert_eq!(proc.peephole_optimize(), 0);
+}
 
+#[test]
+fn test_ast_procedure_peephole_optimize_control_flow() {
+    let source = "\
+    export.foo
+        push.1
+        if.true
+            drop
+            push.2
+            push.2
+            drop
+        end
+        push.3
+        add
+    end";
     let module = ModuleAst::parse(source).unwrap();
+    let mut proc = module.procs()[0].clone();
 
-    assert_eq!(module.local_procs.len(), 1);
-    assert_eq!(procedure, module.local_procs[0]);
+    // the push before the `if.true` block must not be cancelled by the `drop` inside of it
+    assert_eq!(proc.peephole_optimize(), 2);
+    let expected = CodeBody::new([
+        Node::Instruction(Instruction::PushU8(1)),
+        Node::IfElse {
+            true_case: CodeBody::new([
+                Node::Instruction(Instruction::Drop),
+                Node::Instruction(Instruction::PushU8(2)),
+            ]),
+            false_case: CodeBody::default(),
+        },
+        Node::Instruction(Instruction::PushU8(3)),
+        Node::Instruction(Instruction::Add),
+    ]);
+    assert_eq!(proc.body, expected);
 }
 
 #[test]
-fn test_ast_parsing_module_docs() {
+fn test_ast_procedure_validate_locals() {
     let source = "\
-#! Test documentation for the whole module in parsing test. Lorem ipsum dolor sit amet,
-#! consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.
-#! This comment is intentionally longer than 256 characters, since we need to be sure that the size
-#! of the comments is correctly parsed. There was a bug here earlier.
+    export.foo.2
+        loc_store.0
+        if.true
+            loc_loadw.1
+        else
+            repeat.2
+                locaddr.1
+            end
+        end
+    end
 
-#! Test documentation for export procedure foo in parsing test. Lorem ipsum dolor sit amet,
-#! consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.
-#! This comment is intentionally longer than 256 characters, since we need to be sure that the size
-#! of the comments is correctly parsed. There was a bug here earlier.
-export.foo.1
-    loc_load.0
-end
+    export.bar.2
+        loc_store.0
+        if.true
+            loc_load.3
+            loc_storew.2
+        end
+        loc_load.3
+    end
 
-#! Test documentation for internal procedure bar in parsing test. Lorem ipsum dolor sit amet,
-#! consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna
-#! aliqua.
-proc.bar.2
-    padw
-end
+    export.baz
+        locaddr.0
+    end";
+    let module = ModuleAst::parse(source).unwrap();
 
-#! Test documentation for export procedure baz in parsing test. Lorem ipsum dolor sit amet,
-#! consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna
-#! aliqua.
-export.baz.3
-    padw
-    push.0
-end";
-    let mut procedures: LocalProcMap = BTreeMap::new();
-    let docs_foo =
-        "Test documentation for export procedure foo in parsing test. Lorem ipsum dolor sit amet,
-consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.
-This comment is intentionally longer than 256 characters, since we need to be sure that the size
-of the comments is correctly parsed. There was a bug here earlier."
-            .to_string();
-    procedures.insert(
-        String::from("foo"),
-        (
-            0,
-            ProcedureAst::new(
-                String::from("foo").try_into().unwrap(),
-                1,
-                [Node::Instruction(Instruction::LocLoad(0))].to_vec(),
-                true,
-                Some(docs_foo),
-            )
-            .with_source_locations(
-                [SourceLocation::new(11, 5), SourceLocation::new(12, 1)],
-                SourceLocation::new(10, 1),
-            ),
-        ),
-    );
+    // all accesses are in bounds
+    assert_eq!(module.procs()[0].validate(), Ok(()));
 
-    procedures.insert(
-        String::from("bar"),
-        (
-            1,
-            ProcedureAst::new(
-                String::from("bar").try_into().unwrap(),
-                2,
-                [Node::Instruction(Instruction::PadW)].to_vec(),
-                false,
-                None,
-            )
-            .with_source_locations(
-                [SourceLocation::new(18, 5), SourceLocation::new(19, 1)],
-                SourceLocation::new(17, 1),
-            ),
-        ),
-    );
+    // the first access to the highest index is reported
+    let err = module.procs()[1].validate().unwrap_err();
+    let expected =
+        ParsingError::proc_local_out_of_bounds("loc_load.3", SourceLocation::new(15, 13), 3, 2);
+    assert_eq!(err, expected);
 
-    let docs_baz =
-        "Test documentation for export procedure baz in parsing test. Lorem ipsum dolor sit amet,
-consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna
-aliqua."
-            .to_string();
-    procedures.insert(
-        String::from("baz"),
-        (
-            2,
-            ProcedureAst::new(
+    // procedures without locals cannot access any locals
+    let err = module.procs()[2].validate().unwrap_err();
+    let expected =
+        ParsingError::proc_local_out_of_bounds("locaddr.0", SourceLocation::new(22, 9), 0, 0);
+    assert_eq!(err, expected);
+}
+
+#[test]
+fn test_ast_procedure_builder() {
+    let docs = "Stores the top element.\ninputs: [a] outputs: []";
+    let body = vec![
+        Node::Instruction(Instruction::LocStore(1)),
+        Node::Instruction(Instruction::LocLoad(0)),
+    ];
+    let proc = ProcedureAst::builder()
+        .name("foo")
+        .num_locals(2)
+        .body(body.clone())
+        .export(true)
+        .docs(docs)
+        .build()
+        .unwrap();
+    let name = ProcedureName::try_from("foo".to_string()).unwrap();
+    let expected = ProcedureAst::new(name, 2, body, true, Some(docs.to_string()));
+    assert_eq!(proc, expected);
+    assert!(proc.stack_effect().is_some());
+
+    // unset properties take their defaults
+    let proc = ProcedureAst::builder().name("bar").build().unwrap();
+    let name = ProcedureName::try_from("bar".to_string()).unwrap();
+    assert_eq!(proc, ProcedureAst::new(name, 0, Vec::new(), false, None));
+
+    // the name is required and must be valid
+    let err = ProcedureAst::builder().build().unwrap_err();
+    assert_eq!(err, ParsingError::missing_proc_name());
+    let err = ProcedureAst::builder().name("1foo").build().unwrap_err();
+    let label_err = ProcedureName::try_from("1foo".to_string()).unwrap_err();
+    assert_eq!(err, ParsingError::invalid_proc_label("1foo", label_err));
+
+    // locals accessed by the body must be declared
+    let err = ProcedureAst::builder()
+        .name("baz")
+        .num_locals(1)
+        .body(vec![Node::Instruction(Instruction::LocLoadW(1))])
+        .build()
+        .unwrap_err();
+    let expected =
+        ParsingError::proc_local_out_of_bounds("loc_loadw.1", SourceLocation::default(), 1, 1);
+    assert_eq!(err, expected);
+}
+
+#[test]
+fn test_ast_program_symbols() {
+    let source = "\
+    use.std::math::u64
+    const.ONE=1
+
+    proc.foo
+        push.ONE
+    end
+
+    begin
+        exec.foo
+        exec.u64::checked_add
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+    let expected = vec![
+        Symbol::new("u64".to_string(), SymbolKind::Import, SourceLocation::new(1, 1)),
+        Symbol::new("ONE".to_string(), SymbolKind::Constant, SourceLocation::new(2, 5)),
+        Symbol::new("foo".to_string(), SymbolKind::Procedure, SourceLocation::new(4, 5)),
+    ];
+    assert_eq!(program.symbols(), expected);
+
+    // declarations are not serialized, and thus deserialized programs report only their imports
+    // and procedures; symbols without source locations are reported at the default location
+    let bytes = program.to_bytes_with_locations(AstSerdeOptions::new(true));
+    let deserialized = ProgramAst::from_bytes_with_locations(&bytes).unwrap();
+    assert_eq!(deserialized, program);
+    let imported = Symbol::new("u64".to_string(), SymbolKind::Import, SourceLocation::default());
+    assert_eq!(deserialized.symbols(), vec![imported, expected[2].clone()]);
+
+    // programs recovered from malformed sources report the same declarations
+    let (partial, errors) = ProgramAst::parse_partial(&source.replace("push.ONE", "push.TWO"));
+    assert!(!errors.is_empty());
+    assert_eq!(partial.unwrap().symbols()[..2], expected[..2]);
+}
+
+#[test]
+fn test_ast_program_add_import() {
+    let source = "\
+    use.std::math::u64
+
+    begin
+        exec.u64::checked_add
+    end";
+    let mut program = ProgramAst::parse(source).unwrap();
+
+    let path = LibraryPath::new("std::crypto::hashes::blake3").unwrap();
+    program.add_import(path.clone()).unwrap();
+    assert_eq!(program.imports.get("blake3"), Some(&path));
+    assert_eq!(program.imports.len(), 2);
+
+    // modules with the same name cannot be imported twice, even if their paths differ
+    let path = LibraryPath::new("other::u64").unwrap();
+    let err = program.add_import(path).unwrap_err();
+    let token = Token::new("use.other::u64", SourceLocation::default());
+    let expected = ParsingError::conflicting_import_alias(&token, "std::math::u64", "other::u64");
+    assert_eq!(err, expected);
+    assert_eq!(program.imports.len(), 2);
+
+    // modules cannot be imported twice under the same name
+    let path = LibraryPath::new("std::crypto::hashes::blake3").unwrap();
+    let err = program.add_import(path).unwrap_err();
+    let token = Token::new("use.std::crypto::hashes::blake3", SourceLocation::default());
+    let expected = ParsingError::duplicate_module_import(&token, "std::crypto::hashes::blake3");
+    assert_eq!(err, expected);
+}
+
+#[test]
+fn test_ast_program_add_import_overflow() {
+    let mut program =
+        ProgramAst::new(vec![Node::Instruction(Instruction::Add)], vec![], BTreeMap::new())
+            .unwrap();
+    for i in 0..super::MAX_IMPORTS {
+        program
+            .add_import(LibraryPath::new(format!("std::module_{i}")).unwrap())
+            .unwrap();
+    }
+
+    let err = program.add_import(LibraryPath::new("std::one_too_many").unwrap()).unwrap_err();
+    assert_eq!(err, ParsingError::too_many_imports(super::MAX_IMPORTS + 1, super::MAX_IMPORTS));
+}
+
+#[test]
+fn test_ast_program_node_at() {
+    let source = "\
+    begin
+        push.1
+        if.true
+            push.2
+            while.true
+                push.3
+            end
+        else
+            push.4
+        end
+        push.5
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+    let while_node = Node::While {
+        body: CodeBody::new([Node::Instruction(Instruction::PushU8(3))]),
+    };
+    let if_node = Node::IfElse {
+        true_case: CodeBody::new([Node::Instruction(Instruction::PushU8(2)), while_node.clone()]),
+        false_case: CodeBody::new([Node::Instruction(Instruction::PushU8(4))]),
+    };
+    let expected = [
+        Node::Instruction(Instruction::PushU8(1)),
+        if_node,
+        Node::Instruction(Instruction::PushU8(2)),
+        while_node,
+        Node::Instruction(Instruction::PushU8(3)),
+        Node::Instruction(Instruction::PushU8(4)),
+        Node::Instruction(Instruction::PushU8(5)),
+    ];
+    for (index, node) in expected.iter().enumerate() {
+        assert_eq!(program.node_at(index), Some(node));
+    }
+    assert_eq!(program.node_at(expected.len()), None);
+
+    // indexes are stable across serialization round-trips
+    let bytes = program.to_bytes(AstSerdeOptions::new(false));
+    let deserialized = ProgramAst::from_bytes(&bytes).unwrap();
+    for index in 0..=expected.len() {
+        assert_eq!(deserialized.node_at(index), program.node_at(index));
+    }
+}
+
+#[test]
+fn test_ast_program_node_at_skips_nops() {
+    let push = |value| Node::Instruction(Instruction::PushU8(value));
+    let while_node = Node::While {
+        body: CodeBody::new([Node::Nop, push(2)]),
+    };
+    let nodes = [Node::Nop, push(1), while_node, Node::Nop, push(3)];
+    let location = |column| SourceLocation::new(1, column);
+    let body = CodeBody::new(nodes)
+        .with_source_locations((1..=6).map(location))
+        .with_end_locations((1..=5).map(|column| location(column + 10)));
+    let parts = ProgramParts {
+        imports: BTreeMap::new(),
+        local_procs: Vec::new(),
+        body,
+        start: SourceLocation::default(),
+        declarations: Vec::new(),
+    };
+    let program = ProgramAst::from_program_parts(parts).unwrap();
+
+    // no-op nodes are not numbered
+    assert_eq!(program.node_at(0), Some(&push(1)));
+    assert!(matches!(program.node_at(1), Some(Node::While { .. })));
+    assert_eq!(program.node_at(2), Some(&push(2)));
+    assert_eq!(program.node_at(3), Some(&push(3)));
+    assert_eq!(program.node_at(4), None);
+    assert_eq!(program.body().span(0), Some(SourceSpan::new(location(2), location(12))));
+    assert_eq!(program.body().span(2), Some(SourceSpan::new(location(5), location(15))));
+    assert_eq!(program.body().span(3), None);
+
+    // no-op nodes are not serialized, and thus the indexes are the same after a round-trip
+    let bytes = program.to_bytes_with_locations(AstSerdeOptions::new(false));
+    let deserialized = ProgramAst::from_bytes_with_locations(&bytes).unwrap();
+    assert_eq!(deserialized.body_nodes().len(), 3);
+    for index in [0, 2, 3, 4] {
+        assert_eq!(deserialized.node_at(index), program.node_at(index));
+    }
+    assert!(matches!(deserialized.node_at(1), Some(Node::While { .. })));
+    for index in 0..=3 {
+        assert_eq!(deserialized.body().span(index), program.body().span(index));
+    }
+}
+
+#[test]
+fn test_ast_program_body_start_location() {
+    let source = "\
+    proc.foo
+        push.1
+    end
+
+    begin
+        exec.foo
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+    assert_eq!(program.body_start_location(), &SourceLocation::new(5, 5));
+    assert_eq!(program.source_locations().next(), Some(program.body_start_location()));
+}
+
+#[test]
+fn test_ast_node_spans() {
+    let source = "\
+    proc.foo
+        push.1.2.3 add
+    end
+
+    begin
+        if.true
+            exec.foo
+        end
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+
+    // the span of a multi-part instruction covers all of its parts
+    let body = &program.procedures()[0].body;
+    let push = body.span(0).unwrap();
+    assert_eq!(push.start(), SourceLocation::new(2, 9));
+    assert_eq!(push.end(), SourceLocation::new(2, 19));
+    assert!(push.contains(&SourceLocation::new(2, 18)));
+    assert!(!push.contains(&SourceLocation::new(2, 19)));
+    assert_eq!(body.span(1).unwrap().start(), SourceLocation::new(2, 20));
+    assert_eq!(body.span(2), None);
+
+    // the span of a control flow block extends to the end of its `end` token
+    let span = program.body().span(0).unwrap();
+    assert_eq!(span.start(), SourceLocation::new(6, 9));
+    assert_eq!(span.end(), SourceLocation::new(8, 12));
+    assert_eq!(span.to_string(), "[6:9]..[8:12]");
+
+    // spans survive serialization of source locations
+    let bytes = program.to_bytes_with_locations(AstSerdeOptions::new(false));
+    let deserialized = ProgramAst::from_bytes_with_locations(&bytes).unwrap();
+    assert_eq!(deserialized.body().span(0), Some(span));
+    assert_eq!(deserialized.procedures()[0].body.span(0), Some(push));
+
+    // locations in the unversioned format have no end locations, and thus no spans
+    let options = AstSerdeOptions::new(false).with_version(AstFormatVersion::V0);
+    let bytes = program.to_bytes_with_locations(options);
+    let deserialized = ProgramAst::from_bytes_with_locations(&bytes).unwrap();
+    assert_eq!(deserialized.body().source_locations(), program.body().source_locations());
+    assert_eq!(deserialized.body().span(0), None);
+}
+
+#[test]
+fn test_ast_program_parse_partial() {
+    // a complete procedure followed by an unterminated one
+    let source = "\
+    proc.foo
+        add
+    end
+
+    proc.bar
+        push.1
+        if.true
+            mul";
+    let (program, errors) = ProgramAst::parse_partial(source);
+    let program = program.unwrap();
+    let names = program.procedures().iter().map(|proc| proc.name.as_ref()).collect::<Vec<_>>();
+    assert_eq!(names, ["foo"]);
+    assert!(program.body().nodes().is_empty());
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].message(), "if without matching else/end");
+    assert_eq!(errors[1].message(), "unexpected EOF");
+
+    // parsing resumes at the next procedure after a malformed one
+    let source = "\
+    proc.foo
+        add
+    end
+
+    proc.bar
+        push.a
+    end
+
+    proc.baz
+        mul
+    end
+
+    begin
+        exec.baz
+    end";
+    let (program, errors) = ProgramAst::parse_partial(source);
+    let program = program.unwrap();
+    let names = program.procedures().iter().map(|proc| proc.name.as_ref()).collect::<Vec<_>>();
+    assert_eq!(names, ["foo", "baz"]);
+    assert_eq!(program.body().nodes(), [Node::Instruction(Instruction::ExecLocal(1))]);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].location(), &SourceLocation::new(6, 9));
+
+    // valid source is parsed without errors
+    let source = "proc.foo add end begin exec.foo end";
+    let (program, errors) = ProgramAst::parse_partial(source);
+    assert_eq!(program, Some(ProgramAst::parse(source).unwrap()));
+    assert!(errors.is_empty());
+
+    // nothing can be recovered from malformed imports
+    let (program, errors) = ProgramAst::parse_partial("use.std::math::u64 use.std::math::u64");
+    assert!(program.is_none());
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn test_ast_program_visit_calls() {
+    let source = "\
+    use.std::math::u64
+
+    proc.foo
+        add
+    end
+
+    proc.bar
+        exec.foo
+        if.true
+            call.foo
+        else
+            syscall.bump
+        end
+    end
+
+    begin
+        exec.u64::checked_add
+        while.true
+            call.u64::wrapping_add
+            exec.0xc2545da99d3a1f3f38d957c7893c44d78998d8ea8b11aba7e22c8c2b2a213dae
+        end
+        call.0xc2545da99d3a1f3f38d957c7893c44d78998d8ea8b11aba7e22c8c2b2a213dae
+        exec.bar
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+
+    let mut targets = Vec::new();
+    program.visit_calls(|target| targets.push(*target));
+
+    let u64_path = LibraryPath::new("std::math::u64").unwrap();
+    let root = match program.body().node_at(3) {
+        Some(Node::Instruction(Instruction::ExecMastRoot(root))) => *root,
+        node => panic!("unexpected node: {node:?}"),
+    };
+    let expected = [
+        CallTarget::ExecLocal(0),
+        CallTarget::CallLocal(0),
+        CallTarget::SysCall(ProcedureId::from_kernel_name("bump")),
+        CallTarget::ExecImported(ProcedureId::from_name("checked_add", &u64_path)),
+        CallTarget::CallImported(ProcedureId::from_name("wrapping_add", &u64_path)),
+        CallTarget::ExecMastRoot(root),
+        CallTarget::CallMastRoot(root),
+        CallTarget::ExecLocal(1),
+    ];
+    assert_eq!(targets, expected);
+
+    // instructions which do not invoke procedures have no target
+    assert_eq!(CallTarget::from_instruction(&Instruction::Add), None);
+}
+
+#[test]
+fn test_ast_program_instruction_count() {
+    let program = ProgramAst::parse("begin push.1 push.2 add drop end").unwrap();
+    assert_eq!(program.instruction_count(), 4);
+
+    let source = "\
+    proc.foo
+        push.1
+        if.true
+            add
+        else
+            repeat.4
+                mul
+            end
+        end
+    end
+
+    begin
+        exec.foo
+        while.true
+            exec.foo
+            push.0
+        end
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+    // 3 instructions of `foo`, counted once, and 3 instructions of the body; control flow blocks
+    // are not counted
+    assert_eq!(program.instruction_count(), 6);
+}
+
+#[test]
+fn test_ast_program_largest_procedure() {
+    let source = "\
+    proc.foo
+        push.1
+        add
+    end
+
+    proc.bar
+        push.1
+        if.true
+            push.2
+            mul
+        end
+    end
+
+    proc.baz
+        push.1
+        push.2
+        push.3
+    end
+
+    begin
+        exec.foo
+        exec.bar
+        exec.baz
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+    assert_eq!(program.largest_procedure(), Some(("bar", 3)));
+
+    // a program without procedures
+    let program = ProgramAst::parse("begin push.1 end").unwrap();
+    assert_eq!(program.largest_procedure(), None);
+}
+
+#[test]
+fn test_ast_program_body_nodes() {
+    let program = ProgramAst::parse("begin end").unwrap();
+    assert_eq!(program.num_body_nodes(), 0);
+    assert!(program.body_nodes().is_empty());
+
+    let program = ProgramAst::parse("begin push.1 if.true add end mul end").unwrap();
+    assert_eq!(program.num_body_nodes(), 3);
+    assert_eq!(program.body_nodes()[0], Node::Instruction(Instruction::PushU8(1)));
+    assert!(matches!(program.body_nodes()[1], Node::IfElse { .. }));
+    assert_eq!(program.body_nodes()[2], Node::Instruction(Instruction::Mul));
+}
+
+#[test]
+fn test_ast_program_imported_namespaces() {
+    let source = "\
+    use.std::math::u64
+    use.std::collections::mmr
+    use.foo::bar
+
+    begin
+        exec.u64::checked_add
+        exec.mmr::get
+        exec.bar::baz
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+    let namespaces = program.imported_namespaces();
+    assert_eq!(namespaces.len(), 2);
+    assert!(namespaces.contains("std"));
+    assert!(namespaces.contains("foo"));
+
+    let program = ProgramAst::parse("begin push.1 end").unwrap();
+    assert!(program.imported_namespaces().is_empty());
+}
+
+#[test]
+fn test_ast_module_get_proc() {
+    let source = "\
+    proc.foo.1
+        loc_store.0
+    end
+
+    export.bar
+        exec.foo
+    end
+
+    export.baz
+        exec.bar
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+
+    // internal procedure
+    let proc = module.get_proc("foo").unwrap();
+    assert!(!proc.is_export);
+    assert_eq!(proc.num_locals, 1);
+    assert_eq!(module.get_proc_index("foo"), Some(0));
+
+    // exported procedures
+    let proc = module.get_proc("bar").unwrap();
+    assert!(proc.is_export);
+    assert_eq!(module.get_proc_index("bar"), Some(1));
+    assert_eq!(module.get_proc("baz").unwrap().name.as_ref(), "baz");
+    assert_eq!(module.get_proc_index("baz"), Some(2));
+
+    // missing procedures
+    assert!(module.get_proc("qux").is_none());
+    assert!(module.get_proc_index("qux").is_none());
+    assert!(module.get_proc("").is_none());
+
+    // an exported and an internal procedure cannot share the same name
+    let source = "proc.foo add end export.foo mul end";
+    assert!(ModuleAst::parse(source).is_err());
+}
+
+#[test]
+fn test_ast_program_merge_procs() {
+    let mut program = ProgramAst::parse("proc.foo add end begin exec.foo end").unwrap();
+    let helpers =
+        ProgramAst::parse("proc.bar mul end proc.foo sub end begin exec.bar end").unwrap();
+    let body = program.body().clone();
+
+    // successful merge appends the procedure after the existing ones
+    program.merge_procs(helpers.procedures()[0].clone()).unwrap();
+    assert_eq!(program.procedures().len(), 2);
+    assert_eq!(program.procedures()[0].name.as_ref(), "foo");
+    assert_eq!(program.procedures()[1].name.as_ref(), "bar");
+    assert_eq!(program.body(), &body);
+
+    // name collision with an existing procedure
+    let foo = helpers.procedures()[1].clone();
+    let err = program.merge_procs(foo.clone()).err().unwrap();
+    assert_eq!(err, ParsingError::duplicate_merged_proc_name("foo", foo.start));
+    assert_eq!(program.procedures().len(), 2);
+
+    // exceeding the procedure limit
+    let procs = (0..u16::MAX)
+        .map(|i| {
+            let name = format!("proc{i}").try_into().unwrap();
+            ProcedureAst::new(name, 0, vec![Node::Instruction(Instruction::Add)], false, None)
+        })
+        .collect::<Vec<_>>();
+    let mut program =
+        ProgramAst::new(vec![Node::Instruction(Instruction::Add)], procs, BTreeMap::new()).unwrap();
+    let err = program.merge_procs(foo).err().unwrap();
+    assert_eq!(
+        err,
+        ParsingError::too_many_module_procs(u16::MAX as usize + 1, u16::MAX as usize)
+    );
+}
+
+#[test]
+fn test_ast_program_replace_proc_body() {
+    let source = "\
+    proc.foo.2
+        push.1 loc_store.0
+    end
+
+    begin
+        exec.foo
+    end";
+    let mut program = ProgramAst::parse(source).unwrap();
+    let original = program.procedures()[0].clone();
+
+    // the body is replaced, while the rest of the procedure is preserved
+    let new_body = vec![
+        Node::Instruction(Instruction::PushU8(2)),
+        Node::Instruction(Instruction::LocStore(1)),
+    ];
+    program.replace_proc_body("foo", new_body.clone()).unwrap();
+    let proc = &program.procedures()[0];
+    assert_eq!(proc.body.nodes(), new_body);
+    assert!(!proc.body.has_locations());
+    assert_eq!(proc.name, original.name);
+    assert_eq!(proc.num_locals, original.num_locals);
+    assert_eq!(proc.docs, original.docs);
+    assert_eq!(proc.is_export, original.is_export);
+
+    // the display of the procedure reflects the new body
+    let display = proc
+        .body
+        .nodes()
+        .iter()
+        .map(|node| match node {
+            Node::Instruction(instruction) => instruction.to_string(),
+            node => panic!("unexpected node: {node:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(display, ["push.2", "loc_store.1"]);
+
+    // the procedure must exist
+    let err = program.replace_proc_body("bar", Vec::new()).unwrap_err();
+    assert_eq!(err, ParsingError::undefined_replaced_proc("bar"));
+    assert_eq!(err.message(), "cannot replace procedure body: undefined local procedure: bar");
+}
+
+#[test]
+fn test_ast_module_qualified_procedures() {
+    let source = "\
+    proc.foo
+        push.1
+    end
+
+    export.bar
+        exec.foo
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+    let module_path = LibraryPath::new("std::math::u64").unwrap();
+
+    let procs = module.qualified_procedures(&module_path);
+    assert_eq!(procs.len(), 2);
+    assert_eq!(procs[0].0, LibraryPath::new("std::math::u64::foo").unwrap());
+    assert_eq!(procs[0].1, &module.procs()[0]);
+    assert_eq!(procs[1].0, LibraryPath::new("std::math::u64::bar").unwrap());
+    assert_eq!(procs[1].1, &module.procs()[1]);
+}
+
+#[test]
+fn test_ast_module_strip_internal() {
+    let source = "\
+    #! Module docs
+
+    use.std::math::u64
+
+    proc.internal
+        push.1
+    end
+
+    export.foo
+        exec.internal
+    end
+
+    proc.other
+        push.2
+    end
+
+    export.bar
+        exec.foo
+        call.foo
+        exec.u64::checked_add
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+    let stripped = module.clone().strip_internal();
+
+    // the internal procedure invoked by `foo` is retained
+    assert_eq!(stripped.procs().len(), 3);
+    assert_eq!(stripped.procs()[0].name.as_ref(), "internal");
+    assert_eq!(stripped.procs()[1].name.as_ref(), "foo");
+    assert_eq!(stripped.procs()[2].name.as_ref(), "bar");
+    assert_eq!(stripped.docs(), module.docs());
+    assert_eq!(stripped.imports(), module.imports());
+
+    // local invocations refer to the new procedure indexes
+    let body = stripped.procs()[2].body.nodes();
+    assert_eq!(body[0], Node::Instruction(Instruction::ExecLocal(1)));
+    assert_eq!(body[1], Node::Instruction(Instruction::CallLocal(1)));
+}
+
+#[test]
+fn test_ast_module_strip_internal_invoked_by_export() {
+    let source = "\
+    #! Helper docs
+    proc.helper
+        push.1
+    end
+
+    #! Nested docs
+    proc.nested
+        exec.helper
+    end
+
+    proc.unused
+        push.2
+    end
+
+    export.foo
+        exec.helper
+    end
+
+    export.bar
+        call.nested
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+    let stripped = module.strip_internal();
+
+    // internal procedures invoked directly or transitively by exports are retained without docs
+    let names = stripped.procs().iter().map(|proc| proc.name.as_ref()).collect::<Vec<_>>();
+    assert_eq!(names, ["helper", "nested", "foo", "bar"]);
+    assert!(stripped.procs()[..2].iter().all(|proc| !proc.is_export && proc.docs.is_none()));
+
+    // every invocation refers to the same procedure as before stripping
+    let exec_helper = [Node::Instruction(Instruction::ExecLocal(0))];
+    assert_eq!(stripped.procs()[1].body.nodes(), exec_helper);
+    assert_eq!(stripped.procs()[2].body.nodes(), exec_helper);
+    assert_eq!(stripped.procs()[3].body.nodes(), [Node::Instruction(Instruction::CallLocal(1))]);
+    assert!(stripped.unreachable_procs().is_empty());
+}
+
+#[test]
+fn test_ast_module_inline_small_procs() {
+    let source = "\
+    proc.store.1
+        loc_store.0
+    end
+
+    proc.double
+        dup.0 add
+    end
+
+    proc.large
+        push.1 push.2 push.3 push.4
+    end
+
+    proc.called
+        push.5
+    end
+
+    export.foo.1
+        loc_store.0
+        exec.store
+        if.true
+            exec.double
+        end
+        exec.large
+        call.called
+    end
+
+    export.bar
+        exec.foo
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+    let mut inlined = module.inline_small_procs(2);
+    inlined.clear_locations();
+
+    // `store` and `double` are inlined and removed; `large` is too large to be inlined, and
+    // `called` is invoked via `call`
+    let names = inlined.procs().iter().map(|proc| proc.name.as_ref()).collect::<Vec<_>>();
+    assert_eq!(names, ["large", "called", "foo", "bar"]);
+
+    // the locals of `store` are renumbered to follow the locals of `foo`
+    let foo = &inlined.procs()[2];
+    assert_eq!(foo.num_locals, 2);
+    let expected = CodeBody::new([
+        Node::Instruction(Instruction::LocStore(0)),
+        Node::Instruction(Instruction::LocStore(1)),
+        Node::IfElse {
+            true_case: CodeBody::new([
+                Node::Instruction(Instruction::Dup0),
+                Node::Instruction(Instruction::Add),
+            ]),
+            false_case: CodeBody::default(),
+        },
+        Node::Instruction(Instruction::ExecLocal(0)),
+        Node::Instruction(Instruction::CallLocal(1)),
+    ]);
+    assert_eq!(foo.body, expected);
+
+    // exported procedures are never inlined
+    let bar = &inlined.procs()[3];
+    assert_eq!(bar.body.nodes(), [Node::Instruction(Instruction::ExecLocal(2))]);
+}
+
+// CONCRETE SYNTAX TREE TESTS
+// ================================================================================================
+
+#[test]
+fn test_cst_reproduces_source() {
+    let source = "#! Module docs\r\n\
+        \r\n\
+        use.std::math::u64\n\
+        \n\
+        const.FOO=3   # trailing comment\n\
+        \n\
+        proc.foo.1\t\n\
+        \tloc_load.0  \n\
+        end\n\
+        \n\
+        begin\n\
+        \x20   push.1.2 # push things\n\
+        \x20   if.true\n\
+        \x20       exec.foo\n\
+        \x20   else\n\
+        \x20       repeat.3 push.FOO exec.u64::checked_add end\n\
+        \x20   end\n\
+        end\n\n";
+
+    let cst = ProgramAst::parse_cst(source).unwrap();
+    assert_eq!(cst.to_string(), source);
+
+    // top-level nodes are the module docs, the import, the constant, the procedure, and the
+    // program body, separated by trivia
+    let blocks = cst.nodes().iter().filter(|node| matches!(node, CstNode::Block(_)));
+    assert_eq!(blocks.count(), 2);
+    assert!(matches!(cst.nodes()[0], CstNode::DocComment("#! Module docs")));
+    assert!(matches!(cst.nodes()[1], CstNode::Newline("\r\n")));
+    assert!(matches!(
+        cst.nodes()[3],
+        CstNode::Token {
+            text: "use.std::math::u64",
+            location
+        } if location == SourceLocation::new(3, 1)
+    ));
+
+    // invalid programs are rejected
+    assert!(ProgramAst::parse_cst("begin push.1").is_err());
+}
+
+// PROCEDURE IMPORTS
+// ================================================================================================
+
+#[test]
+fn test_missing_import() {
+    let source = "\
+    begin
+        exec.u64::add
+    end";
+
+    let result = ProgramAst::parse(source);
+    match result {
+        Ok(_) => assert!(false),
+        Err(err) => assert!(err.to_string().contains("module 'u64' was not imported")),
+    }
+}
+
+#[test]
+fn test_duplicate_import_lenient() {
+    let source = "\
+    use.std::math::u64
+    use.std::math::u64
+
+    begin
+        exec.u64::checked_add
+    end";
+
+    // duplicate imports are rejected by default
+    let err = ProgramAst::parse(source).unwrap_err();
+    let token = Token::new("use.std::math::u64", SourceLocation::new(2, 5));
+    assert_eq!(err, ParsingError::duplicate_module_import(&token, "std::math::u64"));
+
+    // in lenient mode, an exact duplicate results in a warning and a single import
+    let options = ParsingOptions::new(true);
+    let (program, warnings) = ProgramAst::parse_with_options(source, options).unwrap();
+    assert_eq!(program.imports.len(), 1);
+    assert_eq!(
+        warnings,
+        vec![ParsingWarning::duplicate_module_import(&token, "std::math::u64")]
+    );
+
+    // without duplicates, no warnings are produced
+    let source = "use.std::math::u64\nbegin\nexec.u64::checked_add\nend";
+    let (_, warnings) = ProgramAst::parse_with_options(source, options).unwrap();
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn test_ast_module_procedure_ids() {
+    let source = "\
+    use.std::math::u64
+
+    proc.helper
+        push.1
+    end
+
+    export.foo
+        exec.helper
+    end
+
+    export.u64::checked_add->add";
+    let module = ModuleAst::parse(source).unwrap();
+    let module_path = LibraryPath::new("my_lib::my_module").unwrap();
+
+    let ids = module.procedure_ids(&module_path);
+    let names = ids.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["helper", "foo", "add"]);
+    for (name, id) in ids {
+        assert_eq!(id, ProcedureId::from_name(&name, &module_path));
+    }
+    assert_eq!(
+        module.procedure_ids(&module_path)[2].1,
+        module.reexported_procs()[0].get_alias_id(&module_path)
+    );
+}
+
+#[test]
+fn test_ast_module_unreachable_procs() {
+    let source = "\
+    proc.helper
+        push.1
+    end
+
+    proc.dead
+        exec.helper
+    end
+
+    proc.nested
+        add
+    end
+
+    export.foo
+        if.true
+            exec.helper
+        end
+    end
+
+    proc.also_dead
+        exec.dead
+    end
+
+    export.bar
+        repeat.2
+            call.nested
+        end
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+    let unreachable = module
+        .unreachable_procs()
+        .iter()
+        .map(|proc| proc.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(unreachable, ["dead", "also_dead"]);
+
+    let source = "\
+    proc.helper
+        push.1
+    end
+
+    proc.caller
+        exec.helper
+    end
+
+    export.foo
+        exec.caller
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+    assert!(module.unreachable_procs().is_empty());
+}
+
+#[test]
+fn test_ast_module_reexports() {
+    let source = "\
+    use.std::math::u64
+
+    export.u64::checked_add
+    export.u64::checked_sub->sub
+    export.add_unchecked->u64::wrapping_add";
+
+    let module = ModuleAst::parse(source).unwrap();
+    let u64_path = LibraryPath::new("std::math::u64").unwrap();
+    let reexports = module
+        .reexports()
+        .map(|(name, proc_id)| (name.to_string(), proc_id))
+        .collect::<Vec<_>>();
+    let expected = vec![
+        ("add_unchecked".to_string(), ProcedureId::from_name("wrapping_add", &u64_path)),
+        ("checked_add".to_string(), ProcedureId::from_name("checked_add", &u64_path)),
+        ("sub".to_string(), ProcedureId::from_name("checked_sub", &u64_path)),
+    ];
+    assert_eq!(reexports, expected);
+
+    // re-exports must survive a serialization round-trip
+    let bytes = module.to_bytes(AstSerdeOptions::new(true));
+    let deserialized = ModuleAst::from_bytes(&bytes).unwrap();
+    assert!(deserialized.reexports().eq(module.reexports()));
+}
+
+#[test]
+fn test_ast_module_reexport_malformed_alias_target() {
+    let source = "\
+    use.std::math::u64
+
+    export.add_unchecked->std::math::u64::wrapping_add";
+
+    let err = ModuleAst::parse(source).unwrap_err();
+    let token =
+        Token::new("export.add_unchecked->std::math::u64::wrapping_add", SourceLocation::new(3, 5));
+    let label = "add_unchecked->std::math::u64::wrapping_add";
+    assert_eq!(err, ParsingError::invalid_reexported_procedure(&token, label));
+
+    // an alias cannot be specified on both sides of the re-exported procedure
+    let source = "\
+    use.std::math::u64
+
+    export.add_unchecked->u64::wrapping_add->add";
+
+    let err = ModuleAst::parse(source).unwrap_err();
+    let token =
+        Token::new("export.add_unchecked->u64::wrapping_add->add", SourceLocation::new(3, 5));
+    let label = "add_unchecked->u64::wrapping_add->add";
+    assert_eq!(err, ParsingError::invalid_reexported_procedure(&token, label));
+}
+
+#[test]
+fn test_ast_module_resolve_import() {
+    let source = "\
+    use.std::math::u64
+    use.std::crypto::hashes::blake3
+
+    export.foo
+        exec.u64::checked_add
+        exec.blake3::hash_1to1
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+
+    // the alias differs from the full path of the module
+    let path = module.resolve_import("u64").unwrap();
+    assert_eq!(path.as_str(), "std::math::u64");
+    assert_eq!(path.last(), "u64");
+    assert_eq!(module.resolve_import("blake3").unwrap().as_str(), "std::crypto::hashes::blake3");
+
+    // full paths and unknown aliases are not resolved
+    assert_eq!(module.resolve_import("std::math::u64"), None);
+    assert_eq!(module.resolve_import("u32"), None);
+}
+
+#[test]
+fn test_ast_module_imported_constants() {
+    let consts_source = "\
+    const.WORD_SIZE=4
+    const.MEM_OFFSET=0x100";
+    let consts_module = ModuleAst::parse(consts_source).unwrap();
+    assert_eq!(consts_module.constants().get("MEM_OFFSET"), Some(&256));
+
+    let path = LibraryPath::new("std::consts").unwrap();
+    let const_modules = BTreeMap::from([(path, consts_module)]);
+
+    let source = "\
+    use.const.std::consts
+    const.MEM_END=MEM_OFFSET+WORD_SIZE
+
+    export.foo
+        push.MEM_END
+        mem_load.MEM_OFFSET
+    end";
+    let options = ParsingOptions::default();
+    let (module, _) =
+        ModuleAst::parse_with_imported_constants(source, options, &const_modules).unwrap();
+    let expected = CodeBody::new([
+        Node::Instruction(Instruction::PushU16(260)),
+        Node::Instruction(Instruction::MemLoadImm(256)),
+    ]);
+    assert_eq!(module.procs()[0].body, expected);
+
+    // imported constants are not re-exported
+    assert_eq!(module.constants(), &BTreeMap::from([("MEM_END".to_string(), 260)]));
+
+    // constants survive a serialization round-trip
+    let bytes = module.to_bytes(AstSerdeOptions::new(true));
+    assert_eq!(ModuleAst::from_bytes(&bytes).unwrap().constants(), module.constants());
+
+    // the module must be provided in order to import its constants
+    let err = ModuleAst::parse(source).unwrap_err();
+    let token = Token::new("use.const.std::consts", SourceLocation::new(1, 1));
+    assert_eq!(err, ParsingError::const_module_not_found(&token, "std::consts"));
+}
+
+#[test]
+fn test_ast_module_imported_constants_conflict() {
+    let consts_module = ModuleAst::parse("const.WORD_SIZE=4").unwrap();
+    let path = LibraryPath::new("std::consts").unwrap();
+    let const_modules = BTreeMap::from([(path, consts_module)]);
+
+    let source = "\
+    use.const.std::consts
+    const.WORD_SIZE=8
+
+    export.foo
+        push.WORD_SIZE
+    end";
+    let options = ParsingOptions::default();
+    let err =
+        ModuleAst::parse_with_imported_constants(source, options, &const_modules).unwrap_err();
+    let token = Token::new("const.WORD_SIZE=8", SourceLocation::new(2, 5));
+    assert_eq!(err, ParsingError::imported_const_conflict(&token, "WORD_SIZE"));
+}
+
+#[test]
+fn test_ast_module_parse_constants_only() {
+    let source = "\
+    #! Shared configuration
+    const.MEM_OFFSET=100
+    const.MEM_SIZE=0x40
+    const.MEM_END=MEM_OFFSET+MEM_SIZE";
+
+    let constants = ModuleAst::parse_constants_only(source).unwrap();
+    let expected = BTreeMap::from([
+        ("MEM_END".to_string(), (164, SourceLocation::new(4, 5))),
+        ("MEM_OFFSET".to_string(), (100, SourceLocation::new(2, 5))),
+        ("MEM_SIZE".to_string(), (64, SourceLocation::new(3, 5))),
+    ]);
+    assert_eq!(constants, expected);
+}
+
+#[test]
+fn test_ast_module_parse_constants_only_with_procedure() {
+    let source = "\
+    const.MEM_OFFSET=100
+
+    export.foo
+        push.MEM_OFFSET
+    end";
+
+    let err = ModuleAst::parse_constants_only(source).unwrap_err();
+    let token = Token::new("export.foo", SourceLocation::new(3, 5));
+    assert_eq!(err, ParsingError::unexpected_token(&token, Token::CONST));
+}
+
+#[test]
+fn test_conflicting_import_lenient() {
+    let source = "\
+    use.std::math::u64
+    use.other::u64
+
+    begin
+        exec.u64::checked_add
+    end";
+
+    // imports binding different paths to the same name are rejected even in lenient mode
+    let options = ParsingOptions::new(true);
+    let err = ProgramAst::parse_with_options(source, options).unwrap_err();
+    let token = Token::new("use.other::u64", SourceLocation::new(2, 5));
+    let expected = ParsingError::conflicting_import_alias(&token, "std::math::u64", "other::u64");
+    assert_eq!(err, expected);
+}
+
+#[test]
+fn test_conflicting_import_alias() {
+    let source = "\
+    use.a::util
+    use.b::util
+
+    begin
+        exec.util::foo
+    end";
+    let err = ProgramAst::parse(source).unwrap_err();
+    let token = Token::new("use.b::util", SourceLocation::new(2, 5));
+    assert_eq!(err, ParsingError::conflicting_import_alias(&token, "a::util", "b::util"));
+    assert_eq!(
+        err.message(),
+        "conflicting module imports: 'util' refers to both a::util and b::util"
+    );
+
+    let source = "\
+    use.a::util
+    use.b::util
+
+    export.foo
+        exec.util::foo
+    end";
+    let err = ModuleAst::parse(source).unwrap_err();
+    assert_eq!(err, ParsingError::conflicting_import_alias(&token, "a::util", "b::util"));
+}
+
+#[test]
+fn test_module_merge_imports() {
+    let mut module =
+        ModuleAst::parse("use.std::math::u64\nexport.foo\nexec.u64::add\nend").unwrap();
+    let other = ModuleAst::parse(
+        "use.std::math::u64\nuse.std::sys\nexport.bar\nexec.u64::add\nexec.sys::foo\nend",
+    )
+    .unwrap();
+    module.merge_imports(&other).unwrap();
+    let aliases = module.imports().keys().map(String::as_str).collect::<Vec<_>>();
+    assert_eq!(aliases, ["sys", "u64"]);
+    assert_eq!(module.imports()["sys"], LibraryPath::new("std::sys").unwrap());
+
+    // an alias referring to different modules is rejected and leaves the module unchanged
+    let conflicting =
+        ModuleAst::parse("use.foo::bar\nuse.my::u64\nexport.baz\nexec.u64::add\nend").unwrap();
+    let expected = module.clone();
+    let err = module.merge_imports(&conflicting).unwrap_err();
+    let token = Token::new("use.my::u64", SourceLocation::default());
+    assert_eq!(err, ParsingError::conflicting_import_alias(&token, "std::math::u64", "my::u64"));
+    assert_eq!(module, expected);
+
+    // the merged imports must not exceed the maximum number of imports
+    let imports = |prefix: &str, count: usize| {
+        (0..count)
+            .map(|i| {
+                let path = LibraryPath::new(format!("std::{prefix}{i}")).unwrap();
+                (path.last().to_string(), path)
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+    let half = u16::MAX as usize / 2 + 1;
+    let mut module = ModuleAst::new(vec![], vec![], imports("a", half), None).unwrap();
+    let other = ModuleAst::new(vec![], vec![], imports("b", half), None).unwrap();
+    let err = module.merge_imports(&other).unwrap_err();
+    assert_eq!(err, ParsingError::too_many_imports(2 * half, u16::MAX as usize));
+}
+
+// PROCEDURE ATTRIBUTES
+// ================================================================================================
+
+#[test]
+fn test_proc_budget_attribute() {
+    let source = "\
+    @[budget=5000]
+    proc.foo.1
+        loc_store.0
+    end
+
+    proc.bar
+        exec.foo
+    end
+
+    begin
+        exec.bar
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+    assert_eq!(program.procedures()[0].budget(), Some(5000));
+    assert_eq!(program.procedures()[1].budget(), None);
+
+    // the budget must be preserved through serialization
+    let bytes = program.to_bytes(AstSerdeOptions::new(false));
+    let parsed = ProgramAst::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed.procedures()[0].budget(), Some(5000));
+    assert_eq!(parsed.procedures()[1].budget(), None);
+
+    // doc comments may precede the attributes of an exported procedure
+    let source = "\
+    #! Module docs
+
+    #! Procedure docs
+    @[budget=10]
+    export.foo
+        push.1
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+    assert_eq!(module.procs()[0].budget(), Some(10));
+    assert_eq!(module.procs()[0].docs.as_deref(), Some("Procedure docs"));
+}
+
+#[test]
+fn test_proc_budget_attribute_fail() {
+    let source = "@[budget=0]\nproc.foo\npush.1\nend\nbegin\nexec.foo\nend";
+    let err = ProgramAst::parse(source).err().unwrap();
+    let token = Token::new("@[budget=0]", SourceLocation::new(1, 1));
+    let expected =
+        ParsingError::invalid_proc_attribute(&token, "budget must be a positive 32-bit integer");
+    assert_eq!(err, expected);
+
+    let source = "@[budget=abc]\nproc.foo\npush.1\nend\nbegin\nexec.foo\nend";
+    let err = ProgramAst::parse(source).err().unwrap();
+    let token = Token::new("@[budget=abc]", SourceLocation::new(1, 1));
+    let expected =
+        ParsingError::invalid_proc_attribute(&token, "budget must be a positive 32-bit integer");
+    assert_eq!(err, expected);
+
+    let source = "proc.foo\npush.1\nend\n@[budget=5]\nbegin\nexec.foo\nend";
+    let err = ProgramAst::parse(source).err().unwrap();
+    let token = Token::new("@[budget=5]", SourceLocation::new(4, 1));
+    assert_eq!(err, ParsingError::dangling_proc_attribute(&token));
+}
+
+#[test]
+fn test_proc_inline_attributes() {
+    let source = "\
+    @[inline]
+    proc.foo
+        push.1
+    end
+
+    @[noinline]
+    @[budget=20]
+    proc.bar
+        push.2
+    end
+
+    proc.baz
+        push.3
+    end
+
+    begin
+        exec.foo
+        exec.bar
+        exec.baz
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+    let procs = program.procedures();
+    assert_eq!(procs[0].flags(), ProcedureFlags::INLINE);
+    assert_eq!(procs[1].flags(), ProcedureFlags::NOINLINE);
+    assert_eq!(procs[1].budget(), Some(20));
+    assert!(procs[2].flags().is_empty());
+
+    // the flags must be preserved through serialization
+    let bytes = program.to_bytes(AstSerdeOptions::new(false));
+    let parsed = ProgramAst::from_bytes(&bytes).unwrap();
+    let flags = parsed.procedures().iter().map(ProcedureAst::flags).collect::<Vec<_>>();
+    assert_eq!(
+        flags,
+        [ProcedureFlags::INLINE, ProcedureFlags::NOINLINE, ProcedureFlags::default()]
+    );
+
+    // the flags must be rendered back into source
+    let module = ModuleAst::parse("@[inline]\nexport.foo\npush.1\nend").unwrap();
+    let rendered = module.to_source().unwrap();
+    assert!(rendered.starts_with("@[inline]\nexport.foo\n"));
+    assert_eq!(ModuleAst::parse(&rendered).unwrap().procs()[0].flags(), ProcedureFlags::INLINE);
+}
+
+#[test]
+fn test_proc_inline_attributes_fail() {
+    let source = "@[inline]\n@[inline]\nproc.foo\npush.1\nend\nbegin\nexec.foo\nend";
+    let err = ProgramAst::parse(source).err().unwrap();
+    let token = Token::new("@[inline]", SourceLocation::new(2, 1));
+    assert_eq!(err, ParsingError::invalid_proc_attribute(&token, "duplicate attribute"));
+
+    let source = "@[inline]\n@[noinline]\nproc.foo\npush.1\nend\nbegin\nexec.foo\nend";
+    let err = ProgramAst::parse(source).err().unwrap();
+    let token = Token::new("@[noinline]", SourceLocation::new(2, 1));
+    let expected = ParsingError::invalid_proc_attribute(
+        &token,
+        "inline and noinline attributes are mutually exclusive",
+    );
+    assert_eq!(err, expected);
+
+    // unknown flag bits are rejected on deserialization
+    assert_eq!(ProcedureFlags::from_bits(0b100), None);
+}
+
+// INDENTATION DIAGNOSTICS
+// ================================================================================================
+
+#[test]
+fn test_mixed_indentation_warning() {
+    let source = "proc.foo\n    push.1\n\tadd\nend\nbegin\n    exec.foo\nend";
+    let (_, warnings) = ProgramAst::parse_with_options(source, ParsingOptions::default()).unwrap();
+    assert_eq!(warnings, vec![ParsingWarning::mixed_indentation(SourceLocation::new(3, 1))]);
+
+    // consistent indentation produces no warnings
+    let source = "proc.foo\n\tpush.1\n\tadd\nend\nbegin\n\texec.foo\nend";
+    let (_, warnings) = ProgramAst::parse_with_options(source, ParsingOptions::default()).unwrap();
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn test_unused_symbol_warnings() {
+    // an unused import produces exactly one warning
+    let source = "use.std::math::u64\nbegin\npush.1\nend";
+    let (_, warnings) = ProgramAst::parse_with_warnings(source).unwrap();
+    assert_eq!(warnings, vec![ParsingWarning::unused_import("u64", SourceLocation::new(1, 1))]);
+
+    // a used import produces no warnings
+    let source = "use.std::math::u64\nbegin\npush.1\nexec.u64::checked_add\nend";
+    let (_, warnings) = ProgramAst::parse_with_warnings(source).unwrap();
+    assert!(warnings.is_empty());
+
+    // constants referenced by instructions or by other constants produce no warnings
+    let source = "\
+    const.A=1
+    const.B=A*2
+    const.C=3
+    proc.foo
+        push.B
+    end
+    begin
+        exec.foo
+    end";
+    let (_, warnings) = ProgramAst::parse_with_warnings(source).unwrap();
+    let expected = vec![ParsingWarning::unreferenced_constant("C", SourceLocation::new(3, 5))];
+    assert_eq!(warnings, expected);
+}
+
+#[test]
+fn test_empty_export_warning() {
+    let source = "\
+    proc.internal
+    end
+
+    export.empty
+    end
+
+    export.trivial
+        if.true
+        else
+            while.true
+            end
+        end
+    end
+
+    export.foo
+        exec.internal
+        push.1
+    end";
+    let (module, warnings) =
+        ModuleAst::parse_with_options(source, ParsingOptions::default()).unwrap();
+    assert!(!module.procs()[0].is_empty_export());
+    assert!(module.procs()[1].is_empty_export());
+    assert!(module.procs()[2].is_empty_export());
+    assert!(!module.procs()[3].is_empty_export());
+    assert_eq!(
+        warnings,
+        vec![
+            ParsingWarning::empty_exported_proc("empty", SourceLocation::new(4, 5)),
+            ParsingWarning::empty_exported_proc("trivial", SourceLocation::new(7, 5)),
+        ]
+    );
+
+    // a module without empty exports produces no warnings
+    let source = "export.foo\npush.1\nend";
+    let (_, warnings) = ModuleAst::parse_with_options(source, ParsingOptions::default()).unwrap();
+    assert!(warnings.is_empty());
+}
+
+// INVALID BODY TESTS
+// ================================================================================================
+
+#[test]
+fn test_use_in_proc_body() {
+    let source = "\
+    export.foo.1
+        loc_load.0
+        use
+    end";
+
+    let result = ModuleAst::parse(source);
+    match result {
+        Ok(_) => assert!(false),
+        Err(err) => assert!(err.to_string().contains("import in procedure body")),
+    }
+}
+
+#[test]
+fn test_unterminated_proc() {
+    let source = "proc.foo add mul begin push.1 end";
+
+    let result = ModuleAst::parse(source);
+    match result {
+        Ok(_) => assert!(false),
+        Err(err) => assert!(err.to_string().contains("procedure 'foo' has no matching end")),
+    }
+}
+
+#[test]
+fn test_unterminated_if() {
+    let source = "proc.foo add mul if.true add.2 begin push.1 end";
+
+    let result = ModuleAst::parse(source);
+    match result {
+        Ok(_) => assert!(false),
+        Err(err) => assert!(err.to_string().contains("if without matching else/end")),
+    }
+}
+
+// DOCUMENTATION PARSING TESTS
+// ================================================================================================
+
+#[test]
+fn test_ast_parsing_simple_docs() {
+    let source = "\
+    #! proc doc
+    export.foo.1
+        loc_load.0
+    end";
+
+    let docs_foo = "proc doc".to_string();
+    let procedure = ProcedureAst::new(
+        String::from("foo").try_into().unwrap(),
+        1,
+        [Node::Instruction(Instruction::LocLoad(0))].to_vec(),
+        true,
+        Some(docs_foo),
+    )
+    .with_source_locations(
+        [SourceLocation::new(3, 9), SourceLocation::new(4, 5)],
+        SourceLocation::new(2, 5),
+    );
+
+    let module = ModuleAst::parse(source).unwrap();
+
+    assert_eq!(module.local_procs.len(), 1);
+    assert_eq!(procedure, module.local_procs[0]);
+}
+
+#[test]
+fn test_ast_parsing_module_docs() {
+    let source = "\
+#! Test documentation for the whole module in parsing test. Lorem ipsum dolor sit amet,
+#! consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.
+#! This comment is intentionally longer than 256 characters, since we need to be sure that the size
+#! of the comments is correctly parsed. There was a bug here earlier.
+
+#! Test documentation for export procedure foo in parsing test. Lorem ipsum dolor sit amet,
+#! consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.
+#! This comment is intentionally longer than 256 characters, since we need to be sure that the size
+#! of the comments is correctly parsed. There was a bug here earlier.
+export.foo.1
+    loc_load.0
+end
+
+#! Test documentation for internal procedure bar in parsing test. Lorem ipsum dolor sit amet,
+#! consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna
+#! aliqua.
+proc.bar.2
+    padw
+end
+
+#! Test documentation for export procedure baz in parsing test. Lorem ipsum dolor sit amet,
+#! consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna
+#! aliqua.
+export.baz.3
+    padw
+    push.0
+end";
+    let mut procedures: LocalProcMap = BTreeMap::new();
+    let docs_foo =
+        "Test documentation for export procedure foo in parsing test. Lorem ipsum dolor sit amet,
+consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.
+This comment is intentionally longer than 256 characters, since we need to be sure that the size
+of the comments is correctly parsed. There was a bug here earlier."
+            .to_string();
+    procedures.insert(
+        String::from("foo"),
+        (
+            0,
+            ProcedureAst::new(
+                String::from("foo").try_into().unwrap(),
+                1,
+                [Node::Instruction(Instruction::LocLoad(0))].to_vec(),
+                true,
+                Some(docs_foo),
+            )
+            .with_source_locations(
+                [SourceLocation::new(11, 5), SourceLocation::new(12, 1)],
+                SourceLocation::new(10, 1),
+            ),
+        ),
+    );
+
+    procedures.insert(
+        String::from("bar"),
+        (
+            1,
+            ProcedureAst::new(
+                String::from("bar").try_into().unwrap(),
+                2,
+                [Node::Instruction(Instruction::PadW)].to_vec(),
+                false,
+                None,
+            )
+            .with_source_locations(
+                [SourceLocation::new(18, 5), SourceLocation::new(19, 1)],
+                SourceLocation::new(17, 1),
+            ),
+        ),
+    );
+
+    let docs_baz =
+        "Test documentation for export procedure baz in parsing test. Lorem ipsum dolor sit amet,
+consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna
+aliqua."
+            .to_string();
+    procedures.insert(
+        String::from("baz"),
+        (
+            2,
+            ProcedureAst::new(
                 String::from("baz").try_into().unwrap(),
                 3,
                 [Node::Instruction(Instruction::PadW), Node::Instruction(Instruction::PushU8(0))]
@@ -647,87 +2440,846 @@ of the comments is correctly parsed. There was a bug here earlier."
             proc
         );
     }
-    let module_serialized = module.to_bytes(AstSerdeOptions::new(false));
-    let module_deserialized = ModuleAst::from_bytes(module_serialized.as_slice()).unwrap();
+    let module_serialized = module.to_bytes(AstSerdeOptions::new(false));
+    let module_deserialized = ModuleAst::from_bytes(module_serialized.as_slice()).unwrap();
+
+    let module = clear_procs_loc_module(module);
+    assert_eq!(module, module_deserialized);
+}
+
+#[test]
+fn test_ast_parsing_const_docs() {
+    let source = "\
+    #! Module docs
+
+    #! Size of a word in elements
+    const.WORD_SIZE=4
+    const.UNDOCUMENTED=1
+    #! Address of the first word
+    const.ADDR=0
+
+    export.foo
+        push.WORD_SIZE.UNDOCUMENTED.ADDR
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+    assert_eq!(module.docs().map(String::as_str), Some("Module docs"));
+    let expected = BTreeMap::from([
+        ("WORD_SIZE".to_string(), "Size of a word in elements".to_string()),
+        ("ADDR".to_string(), "Address of the first word".to_string()),
+    ]);
+    assert_eq!(module.const_docs(), &expected);
+    assert!(module.const_docs().get("UNDOCUMENTED").is_none());
+
+    // constant docs are preserved through serialization
+    let bytes = module.to_bytes(AstSerdeOptions::new(false));
+    let parsed = ModuleAst::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed.const_docs(), &expected);
+
+    // a doc comment which is not followed by a constant is rejected
+    let source = "\
+    const.A=1
+
+    #! dangling doc
+
+    export.foo
+        push.A
+    end";
+    ModuleAst::parse(source)
+        .expect_err("Constant comment is not immediately followed by a constant declaration.");
+}
+
+#[test]
+fn test_ast_module_doc_sections() {
+    let source = "\
+#! A library of math procedures.
+#!
+#! @section examples
+#! exec.foo
+#! @section safety
+#! Inputs are not validated.
+#! Use with care.
+
+export.foo
+    add
+end";
+    let module = ModuleAst::parse(source).unwrap();
+    let expected = vec![
+        (String::new(), "A library of math procedures.".to_string()),
+        ("examples".to_string(), "exec.foo".to_string()),
+        ("safety".to_string(), "Inputs are not validated.\nUse with care.".to_string()),
+    ];
+    assert_eq!(module.doc_sections(), expected);
+
+    // the combined docs still contain the whole text, including the section markers
+    let docs = module.docs().unwrap();
+    assert!(docs.starts_with("A library of math procedures."));
+    assert!(docs.contains("@section safety"));
+
+    // sections are preserved through serialization
+    let bytes = module.to_bytes(AstSerdeOptions::new(false));
+    let deserialized = ModuleAst::from_bytes(&bytes).unwrap();
+    assert_eq!(deserialized.doc_sections(), expected);
+
+    // docs without section markers form a single default section
+    let source = "\
+#! A library of math procedures.
+#! The @section marker must start a line.
+
+export.foo
+    add
+end";
+    let module = ModuleAst::parse(source).unwrap();
+    assert_eq!(module.doc_sections(), vec![(String::new(), module.docs().unwrap().clone())]);
+
+    // a module without docs has no sections
+    let module = ModuleAst::parse("export.foo add end").unwrap();
+    assert!(module.doc_sections().is_empty());
+}
+
+#[test]
+fn test_ast_module_serde_long_docs() {
+    // multi-byte characters make sure that chunk boundaries do not need to be character
+    // boundaries
+    let docs = "Módulo de prueba. ".repeat(100 * 1024 / 19);
+    assert!(docs.len() > 100_000);
+    let source = format!("#! {docs}\n\nexport.foo\n    add\nend");
+
+    let module = ModuleAst::parse(&source).unwrap();
+    assert_eq!(module.docs().map(String::as_str), Some(docs.trim()));
+
+    let bytes = module.to_bytes(AstSerdeOptions::new(true));
+    let deserialized = ModuleAst::from_bytes(&bytes).unwrap();
+    assert_eq!(deserialized.docs(), module.docs());
+    assert_eq!(clear_procs_loc_module(module), deserialized);
+
+    // the unversioned format can hold docs of up to u16::MAX bytes only
+    let options = AstSerdeOptions::new(true).with_version(AstFormatVersion::V0);
+    let docs = "a".repeat(u16::MAX as usize);
+    let module = ModuleAst::new(Vec::new(), Vec::new(), BTreeMap::new(), Some(docs)).unwrap();
+    let deserialized = ModuleAst::from_bytes(&module.to_bytes(options)).unwrap();
+    assert_eq!(deserialized, module);
+
+    // docs exceeding the limit are rejected
+    let docs = "a".repeat(super::MAX_MODULE_DOCS_LEN + 1);
+    let err = ModuleAst::new(Vec::new(), Vec::new(), BTreeMap::new(), Some(docs)).unwrap_err();
+    let expected = ParsingError::module_docs_too_long(
+        super::MAX_MODULE_DOCS_LEN + 1,
+        super::MAX_MODULE_DOCS_LEN,
+    );
+    assert_eq!(err, expected);
+}
+
+#[test]
+#[should_panic(expected = "docs too long for the unversioned format")]
+fn test_ast_module_serde_long_docs_unversioned() {
+    let docs = "a".repeat(u16::MAX as usize + 1);
+    let module = ModuleAst::new(Vec::new(), Vec::new(), BTreeMap::new(), Some(docs)).unwrap();
+    let options = AstSerdeOptions::new(true).with_version(AstFormatVersion::V0);
+    module.to_bytes(options);
+}
+
+#[test]
+fn test_ast_parsing_module_docs_fail() {
+    let source = "\
+    #! module doc
+
+    #! proc doc
+    export.foo.1
+        loc_load.0
+    end
+
+    #! malformed doc
+    ";
+    ModuleAst::parse(source)
+        .expect_err("Procedure comment is not immediately followed by a procedure declaration.");
+
+    let source = "\
+    #! proc doc
+    export.foo.1
+        loc_load.0
+    end
+
+    #! malformed doc
+    ";
+    ModuleAst::parse(source)
+        .expect_err("Procedure comment is not immediately followed by a procedure declaration.");
+
+    let source = "\
+    #! module doc
+
+    #! malformed doc
+    ";
+    ModuleAst::parse(source)
+        .expect_err("Procedure comment is not immediately followed by a procedure declaration.");
+
+    let source = "\
+    export.foo.1
+        loc_load.0
+    end
+
+    #! malformed doc
+    ";
+    ModuleAst::parse(source)
+        .expect_err("Procedure comment is not immediately followed by a procedure declaration.");
+
+    let source = "\
+    #! module doc
+
+    export.foo.1
+        loc_load.0
+    end
+
+    #! malformed doc
+    ";
+    ModuleAst::parse(source)
+        .expect_err("Procedure comment is not immediately followed by a procedure declaration.");
+
+    let source = "\
+    #! proc doc
+    export.foo.1
+        #! malformed doc
+        loc_load.0
+    end
+    ";
+    ModuleAst::parse(source)
+        .expect_err("Procedure comment is not immediately followed by a procedure declaration.");
+}
+
+// SERIALIZATION AND DESERIALIZATION TESTS
+// ================================================================================================
+
+#[test]
+fn test_ast_program_serde_simple() {
+    let source = "begin push.0xabc234 push.0 assertz end";
+    assert_correct_program_serialization(source, false);
+}
+
+#[cfg(feature = "compress")]
+#[test]
+fn test_ast_module_serde_compressed() {
+    let mut source = String::from("use.std::math::u64\n");
+    for i in 0..200 {
+        source.push_str(&format!(
+            "export.foo_{i}.2\n    push.{i} loc_store.0 loc_load.0 exec.u64::checked_add drop\nend\n"
+        ));
+    }
+    let module = ModuleAst::parse(&source).unwrap();
+    let module = clear_procs_loc_module(module);
+    let options = AstSerdeOptions::new(true);
+
+    let uncompressed = module.to_bytes(options);
+    let compressed = module.to_bytes_compressed(options);
+    assert!(compressed.len() < uncompressed.len());
+
+    // both compressed and uncompressed bytes are accepted
+    assert_eq!(ModuleAst::from_bytes_compressed(&compressed).unwrap(), module);
+    assert_eq!(ModuleAst::from_bytes_compressed(&uncompressed).unwrap(), module);
+
+    // corrupted compressed bytes are rejected
+    let truncated = &compressed[..compressed.len() / 2];
+    assert!(matches!(
+        ModuleAst::from_bytes_compressed(truncated),
+        Err(DeserializationError::InvalidValue(_))
+    ));
+}
+
+#[test]
+fn test_ast_serde_format_versions() {
+    let source = "\
+    @[budget=100]
+    @[inline]
+    proc.foo
+        push.1
+    end
+
+    begin
+        exec.foo
+    end";
+    let program = clear_procs_loc_program(ProgramAst::parse(source).unwrap());
+
+    // the current version preserves procedure budgets and flags
+    let bytes = program.to_bytes(AstSerdeOptions::new(false));
+    assert_eq!(ProgramAst::from_bytes(&bytes).unwrap(), program);
+
+    // the unversioned format has the header of a plain import flag, and drops budgets and flags
+    let options = AstSerdeOptions::new(true).with_version(AstFormatVersion::V0);
+    let bytes = program.to_bytes(options);
+    assert_eq!(bytes[0], 1);
+    let parsed = ProgramAst::from_bytes(&bytes).unwrap();
+    assert_eq!(parsed.procedures()[0].budget(), None);
+    assert_eq!(parsed.procedures()[0].flags(), ProcedureFlags::default());
+    assert_eq!(parsed.body(), program.body());
+
+    // unknown format versions are rejected
+    let mut bytes = program.to_bytes(AstSerdeOptions::new(false));
+    bytes[0] = 0x10;
+    let err = ProgramAst::from_bytes(&bytes).unwrap_err();
+    let expected = "unsupported AST format version: 8";
+    assert_eq!(err, DeserializationError::InvalidValue(expected.to_string()));
+}
+
+#[test]
+fn test_ast_module_serde_format_versions() {
+    let source = "\
+    #! Module docs
+
+    #! Constant docs
+    const.A=42
+
+    export.foo
+        push.A
+    end";
+    let module = clear_procs_loc_module(ModuleAst::parse(source).unwrap());
+    assert_eq!(module.constants().len(), 1);
+    assert_eq!(module.const_docs().len(), 1);
+
+    // the current version preserves constants and their docs
+    let bytes = module.to_bytes(AstSerdeOptions::new(true));
+    assert_eq!(ModuleAst::from_bytes(&bytes).unwrap(), module);
+
+    // the unversioned format drops them
+    let options = AstSerdeOptions::new(true).with_version(AstFormatVersion::V0);
+    let parsed = ModuleAst::from_bytes(&module.to_bytes(options)).unwrap();
+    assert!(parsed.constants().is_empty());
+    assert!(parsed.const_docs().is_empty());
+    assert_eq!(parsed.docs(), module.docs());
+    assert_eq!(parsed.procs(), module.procs());
+}
+
+#[test]
+fn test_module_to_source_roundtrip() {
+    let sources = [
+        // docs, constants, and attributes
+        "\
+        #! Module docs
+        #!   spanning multiple lines
+
+        use.std::math::u64
+        use.std::crypto::hashes::blake3
+
+        #! The mask
+        const.MASK=0xFF
+        const.ADDR=MASK*2+1
+
+        #! Adds the mask
+        #!
+        #! with an empty docs line
+        @[budget=100]
+        export.add_mask.2
+            push.MASK loc_store.1 loc_load.1 add
+        end
+
+        proc.helper
+            drop
+        end
+
+        export.foo
+            exec.helper
+            call.add_mask
+            call.0xc2545da99d3a1f3f38d957c7893c44d78998d8ea8b11aba7e22c8c2b2a21b3b4
+        end",
+        // control flow
+        "\
+        export.control_flow
+            if.true
+                repeat.3
+                    push.1 add
+                end
+            else
+                while.true
+                    dup.0 neq.0
+                end
+            end
+            if.true
+                push.1
+            end
+            if.true
+            else
+                drop
+            end
+        end",
+        // a variety of instructions
+        "\
+        export.instructions.4
+            push.0 push.255 push.256 push.65536 push.4294967296
+            push.1.2 push.256.257 push.65536.65537 push.4294967296.5 push.1.2.3.4
+            push.0xffffffff00000000
+            add add.1 add.2 sub.3 mul.4 div.5 exp exp.3 exp.u32 eq.1 neq.2
+            u32assert u32assert.2 u32assertw u32checked_add.5 u32wrapping_mul.3 u32checked_shl.7
+            u32unchecked_rotr.1 u32checked_divmod.3
+            dup.3 dupw.2 swap.1 swapw.3 swapdw movup.4 movdn.15 movupw.2 movdnw.3 cswapw cdrop
+            mem_load mem_load.1 mem_loadw.2 mem_store.3 mem_storew.4 mem_stream adv_pipe
+            loc_load.0 loc_loadw.0 loc_store.3 loc_storew.0 locaddr.2
+            adv_push.4 adv_loadw adv.push_u64div adv.push_mapval adv.push_mapval.2
+            adv.push_mapvaln.1 adv.insert_mem adv.insert_hdword adv.insert_hdword.3
+            hash hmerge hperm mtree_get mtree_set mtree_merge mtree_verify fri_ext2fold4
+            ext2add ext2mul ext2inv sdepth caller clk breakpoint
+        end",
+        // imported and kernel procedures
+        "\
+        use.std::math::u64
+        use.std::crypto::hashes::blake3
+
+        export.u64::checked_add
+        export.blake3::hash_2to1->merge
+
+        export.invocations
+            exec.u64::wrapping_add
+            call.blake3::hash_1to1
+            syscall.foo
+        end",
+    ];
+
+    for source in sources {
+        let module = ModuleAst::parse(source).unwrap();
+        let rendered = module.to_source().unwrap();
+        let reparsed = ModuleAst::parse(&rendered).unwrap();
+
+        // the rendered source is canonical
+        assert_eq!(reparsed.to_source().unwrap(), rendered);
+
+        // serialized modules do not include source locations
+        let options = AstSerdeOptions::new(true);
+        assert_eq!(reparsed.to_bytes(options), module.to_bytes(options));
+    }
 
-    let module = clear_procs_loc_module(module);
-    assert_eq!(module, module_deserialized);
+    // imported procedures are rendered via the aliases of their modules
+    let source = "use.std::math::u64\nexport.foo\n exec.u64::wrapping_add\nend";
+    let module = ModuleAst::parse(source).unwrap();
+    assert!(module.to_source().unwrap().contains("exec.u64::wrapping_add"));
+
+    // the names of invoked procedures are not serialized, and thus cannot be rendered
+    let proc_id =
+        ProcedureId::from_name("wrapping_add", &LibraryPath::new("std::math::u64").unwrap());
+    let deserialized = ModuleAst::from_bytes(&module.to_bytes(AstSerdeOptions::new(true))).unwrap();
+    assert_eq!(deserialized, clear_procs_loc_module(module));
+    assert_eq!(deserialized.to_source(), Err(RenderError::unknown_procedure(proc_id)));
+
+    // modules without any statements have no source representation
+    let module = ModuleAst::new(Vec::new(), Vec::new(), BTreeMap::new(), None).unwrap();
+    assert_eq!(module.to_source(), Err(RenderError::empty_module()));
 }
 
 #[test]
-fn test_ast_parsing_module_docs_fail() {
+fn test_program_from_parts() {
     let source = "\
-    #! module doc
+    use.std::math::u64
+    const.A=3
+    const.B=A*2
+    proc.foo.1
+        loc_store.0
+    end
+    begin
+        push.A.B
+        if.true
+            exec.foo
+        end
+        exec.u64::wrapping_add
+    end";
+    let program = ProgramAst::parse(source).unwrap();
 
-    #! proc doc
-    export.foo.1
-        loc_load.0
+    let imports = program.imports().clone();
+    let start = *program.body_start_location();
+    let locations = program.body().source_locations().to_vec();
+    let (local_procs, body) = program.clone().into_parts();
+    let rebuilt = ProgramAst::from_parts(imports, local_procs, body, start, locations).unwrap();
+    assert_eq!(rebuilt, program);
+    assert!(rebuilt.source_locations().eq(program.source_locations()));
+
+    // the end locations and declarations are preserved only by the program parts
+    assert!(rebuilt.body().end_locations().is_empty());
+    assert!(!program.body().end_locations().is_empty());
+    let rebuilt = ProgramAst::from_program_parts(program.clone().into_program_parts()).unwrap();
+    assert_eq!(rebuilt, program);
+    assert_eq!(rebuilt.body().end_locations(), program.body().end_locations());
+    assert_eq!(rebuilt.symbols(), program.symbols());
+    assert_eq!(rebuilt.symbols().len(), 4);
+}
+
+#[test]
+fn test_program_structural_diff() {
+    let source = "\
+    use.std::math::u64
+    proc.foo.1
+        loc_store.0
+        if.true
+            push.1
+        else
+            push.2
+        end
     end
+    begin
+        exec.foo
+        exec.u64::wrapping_add
+    end";
+    let program = ProgramAst::parse(source).unwrap();
 
-    #! malformed doc
-    ";
-    ModuleAst::parse(source)
-        .expect_err("Procedure comment is not immediately followed by a procedure declaration.");
+    // the same program with different formatting differs only in source locations
+    let reformatted = ProgramAst::parse(&source.replace("    ", "  ")).unwrap();
+    assert_ne!(program, reformatted);
+    assert!(program.structural_eq(&reformatted));
+    assert!(program.diff(&reformatted).is_empty());
+
+    // a single differing instruction in a nested block of a procedure
+    let other = ProgramAst::parse(&source.replace("push.2", "push.3")).unwrap();
+    assert!(!program.structural_eq(&other));
+    let expected = Difference::Procedure {
+        index: 0,
+        name: "foo".to_string(),
+        node: Some(1),
+    };
+    assert_eq!(program.diff(&other), vec![expected]);
+
+    // a differing import and an extra instruction in the program body
+    let other = ProgramAst::parse(
+        &source
+            .replace("std::math::u64", "std::u64")
+            .replace("exec.foo\n", "exec.foo\nadd\n"),
+    )
+    .unwrap();
+    let expected = vec![
+        Difference::Import {
+            alias: "u64".to_string(),
+            left: Some(LibraryPath::new("std::math::u64").unwrap()),
+            right: Some(LibraryPath::new("std::u64").unwrap()),
+        },
+        Difference::Body { node: 1 },
+    ];
+    assert_eq!(program.diff(&other), expected);
+}
 
+#[test]
+fn test_strip_docs() {
     let source = "\
-    #! proc doc
-    export.foo.1
-        loc_load.0
+    #! Module docs
+
+    #! Constant docs
+    const.A=1
+
+    #! Procedure docs
+    #! inputs: [] outputs: [a]
+    export.foo
+        push.A
     end
 
-    #! malformed doc
-    ";
-    ModuleAst::parse(source)
-        .expect_err("Procedure comment is not immediately followed by a procedure declaration.");
+    proc.bar
+        push.2
+    end";
+    let mut module = ModuleAst::parse(source).unwrap();
+    assert!(module.procs()[0].docs.is_some());
+    assert!(module.procs()[0].stack_effect().is_some());
+    let bytes = module.to_bytes(AstSerdeOptions::new(false));
+
+    module.strip_docs();
+    assert!(module.docs().is_none());
+    assert!(module.const_docs().is_empty());
+    assert!(module.procs().iter().all(|proc| proc.docs.is_none()));
+    assert!(module.procs().iter().all(|proc| proc.stack_effect().is_none()));
+    assert_eq!(module.constants().len(), 1);
+
+    let stripped_bytes = module.to_bytes(AstSerdeOptions::new(false));
+    assert!(stripped_bytes.len() < bytes.len());
+    let deserialized = ModuleAst::from_bytes(&stripped_bytes).unwrap();
+    assert_eq!(deserialized, clear_procs_loc_module(module));
+
+    // only exported procedures are parsed with docs, and thus procedures of a program can have
+    // docs only if they were merged into it
+    let mut program = ProgramAst::parse("begin push.1 end").unwrap();
+    let foo = ModuleAst::parse(source).unwrap().procs()[0].clone();
+    program.merge_procs(foo).unwrap();
+    assert!(program.procedures()[0].docs.is_some());
+    let bytes = program.to_bytes(AstSerdeOptions::new(false));
+
+    program.strip_docs();
+    assert!(program.procedures().iter().all(|proc| proc.docs.is_none()));
+    assert!(program.procedures().iter().all(|proc| proc.stack_effect().is_none()));
+
+    let stripped_bytes = program.to_bytes(AstSerdeOptions::new(false));
+    assert!(stripped_bytes.len() < bytes.len());
+    let deserialized = ProgramAst::from_bytes(&stripped_bytes).unwrap();
+    assert_eq!(deserialized, clear_procs_loc_program(program));
+}
 
+#[test]
+fn test_module_cfg_blocks() {
     let source = "\
-    #! module doc
+    export.foo
+        push.1
+    end
 
-    #! malformed doc
-    ";
-    ModuleAst::parse(source)
-        .expect_err("Procedure comment is not immediately followed by a procedure declaration.");
+    @cfg.fast begin
+        export.bar
+            if.true
+                push.2
+            end
+        end
+
+        @cfg.debug begin
+            proc.log
+                push.3
+            end
+        end
+    end
+
+    @cfg.legacy begin
+        export.baz
+            push.4
+        end
+    end";
+    let proc_names = |features: &[&str]| {
+        let features = features.iter().map(|feature| feature.to_string()).collect();
+        let (module, _) =
+            ModuleAst::parse_with_features(source, ParsingOptions::default(), &features).unwrap();
+        module.procs().iter().map(|proc| proc.name.to_string()).collect::<Vec<_>>()
+    };
+
+    // disabled blocks produce no procedures
+    assert_eq!(proc_names(&[]), ["foo"]);
+    assert_eq!(ModuleAst::parse(source).unwrap().procs().len(), 1);
+
+    // enabled blocks are parsed as if there were no directive
+    assert_eq!(proc_names(&["fast"]), ["foo", "bar"]);
+    assert_eq!(proc_names(&["fast", "debug"]), ["foo", "bar", "log"]);
+    assert_eq!(proc_names(&["legacy"]), ["foo", "baz"]);
+
+    // nested blocks are included only if the enclosing block is enabled
+    assert_eq!(proc_names(&["debug"]), ["foo"]);
+
+    // unknown feature names do not enable any blocks
+    assert_eq!(proc_names(&["unknown"]), ["foo"]);
+}
+
+#[test]
+fn test_module_cfg_block_errors() {
+    let features = BTreeSet::from(["fast".to_string()]);
+    let parse =
+        |source| ModuleAst::parse_with_features(source, ParsingOptions::default(), &features);
+
+    // the block must be terminated regardless of whether it is enabled
+    let source = "@cfg.fast begin export.foo push.1 end";
+    let token = Token::new("@cfg.fast", SourceLocation::new(1, 1));
+    assert_eq!(parse(source).unwrap_err(), ParsingError::unmatched_cfg(&token));
+    let source = "@cfg.slow begin export.foo push.1 end";
+    let token = Token::new("@cfg.slow", SourceLocation::new(1, 1));
+    assert_eq!(parse(source).unwrap_err(), ParsingError::unmatched_cfg(&token));
+
+    // the directive must be followed by a block
+    let source = "@cfg.fast export.foo push.1 end";
+    let token = Token::new("export.foo", SourceLocation::new(1, 11));
+    assert_eq!(parse(source).unwrap_err(), ParsingError::unexpected_token(&token, "begin"));
+
+    // the feature name must be valid
+    let source = "@cfg.fa$t begin end";
+    let token = Token::new("@cfg.fa$t", SourceLocation::new(1, 1));
+    assert_eq!(parse(source).unwrap_err(), ParsingError::invalid_cfg_feature(&token, "fa$t"));
+}
+
+#[test]
+fn test_module_rejects_main_proc_name() {
+    // the reserved name for `main` can be created only via ProcedureName::main()
+    let err = ProcedureName::try_from(ProcedureName::MAIN_PROC_NAME.to_string()).unwrap_err();
+    assert_eq!(err, LabelError::invalid_fist_letter("#main"));
+    assert!(ProcedureName::main().is_main());
+
+    // and thus a library module cannot define or re-export a procedure with this name
+    for source in ["export.#main\n    push.1\nend", "export.foo::#main"] {
+        let token = Token::new(source.lines().next().unwrap(), SourceLocation::new(1, 1));
+        let expected = ParsingError::invalid_proc_name(&token, err.clone());
+        assert_eq!(ModuleAst::parse(source).unwrap_err(), expected);
+    }
+}
 
+#[test]
+fn test_procedure_stack_effect() {
     let source = "\
-    export.foo.1
-        loc_load.0
+    #! Module docs
+
+    #! Adds two numbers.
+    #! inputs: [a, b] outputs: [c]
+    export.add
+        add
     end
 
-    #! malformed doc
-    ";
-    ModuleAst::parse(source)
-        .expect_err("Procedure comment is not immediately followed by a procedure declaration.");
+    #! inputs: [a, b outputs: [c]
+    export.malformed
+        add
+    end
 
+    export.undocumented
+        add
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+    let expected = StackEffect {
+        inputs: vec!["a".to_string(), "b".to_string()],
+        outputs: vec!["c".to_string()],
+    };
+    assert_eq!(module.procs()[0].stack_effect(), Some(expected.clone()));
+    assert_eq!(
+        module.procs()[0].docs.as_deref(),
+        Some("Adds two numbers.\ninputs: [a, b] outputs: [c]")
+    );
+
+    // a malformed declaration is kept as plain docs
+    assert_eq!(module.procs()[1].stack_effect(), None);
+    assert_eq!(module.procs()[1].docs.as_deref(), Some("inputs: [a, b outputs: [c]"));
+    assert_eq!(module.procs()[2].stack_effect(), None);
+
+    // the stack effect is preserved through serialization of the docs
+    let parsed = ModuleAst::from_bytes(&module.to_bytes(AstSerdeOptions::new(false))).unwrap();
+    let effects = parsed.procs().iter().map(ProcedureAst::stack_effect).collect::<Vec<_>>();
+    assert_eq!(effects, [Some(expected.clone()), None, None]);
+
+    // the stack effect follows changes to the docs
+    let mut proc = module.procs()[0].clone();
+    proc.docs = Some("inputs: [a] outputs: [a]".to_string());
+    assert_eq!(proc.stack_effect().unwrap().inputs, ["a"]);
+
+    // empty lists are allowed
+    let expected = StackEffect {
+        inputs: vec![],
+        outputs: vec!["x".to_string()],
+    };
+    assert_eq!(StackEffect::parse_docs("inputs: [] outputs: [x]"), Some(expected));
+}
+
+#[test]
+fn test_procedure_stack_balance() {
     let source = "\
-    #! module doc
+    #! Module docs
 
-    export.foo.1
-        loc_load.0
+    #! inputs: [a, b] outputs: [c]
+    export.balanced
+        dup.1
+        add
+        swap
+        drop
     end
 
-    #! malformed doc
-    ";
-    ModuleAst::parse(source)
-        .expect_err("Procedure comment is not immediately followed by a procedure declaration.");
+    #! inputs: [a] outputs: [c]
+    export.underflow
+        add
+        push.1
+        mul
+        add
+    end
+
+    #! inputs: [] outputs: []
+    export.branching
+        if.true
+            drop
+        end
+        drop
+    end
+
+    export.undeclared
+        drop
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+    let procs = module.procs();
+    assert_eq!(procs[0].check_stack_balance(), Ok(()));
+
+    // `add` needs two elements; after it, the analysis continues as if they were present, and
+    // thus the final `add` underflows again
+    let expected = vec![
+        StackWarning {
+            instruction: Instruction::Add,
+            location: SourceLocation::new(13, 9),
+            required: 2,
+            available: 1,
+        },
+        StackWarning {
+            instruction: Instruction::Add,
+            location: SourceLocation::new(16, 9),
+            required: 2,
+            available: 1,
+        },
+    ];
+    assert_eq!(procs[1].check_stack_balance(), Err(expected));
+
+    // the analysis stops at control flow blocks and requires a declared stack effect
+    assert_eq!(procs[2].check_stack_balance(), Ok(()));
+    assert_eq!(procs[3].check_stack_balance(), Ok(()));
+}
 
+#[test]
+fn test_procedure_locals_used() {
     let source = "\
-    #! proc doc
-    export.foo.1
-        #! malformed doc
-        loc_load.0
+    export.foo.8
+        loc_store.0
+        if.true
+            loc_loadw.3
+        end
     end
-    ";
-    ModuleAst::parse(source)
-        .expect_err("Procedure comment is not immediately followed by a procedure declaration.");
+
+    export.bar.2
+        push.1
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+    assert_eq!(module.procs()[0].locals_used(), 4);
+    assert_eq!(module.procs()[1].locals_used(), 0);
 }
 
-// SERIALIZATION AND DESERIALIZATION TESTS
-// ================================================================================================
+#[test]
+fn test_procedure_body_hash() {
+    let source = "\
+    export.foo.1
+        push.1 loc_store.0
+        if.true
+            add
+        end
+    end
+
+    export.bar.2
+        push.1 loc_store.0
+        if.true
+            add
+        end
+    end
+
+    export.baz.1
+        push.1 loc_store.0
+        if.true
+            mul
+        end
+    end";
+    let module = ModuleAst::parse(source).unwrap();
+    let [foo, bar, baz] = module.procs() else {
+        panic!("expected three procedures");
+    };
+
+    // procedures which differ only in their name and locals share the body hash
+    assert_eq!(foo.body_hash(), bar.body_hash());
+    assert_ne!(foo.body_hash(), baz.body_hash());
+}
 
 #[test]
-fn test_ast_program_serde_simple() {
-    let source = "begin push.0xabc234 push.0 assertz end";
+fn test_ast_program_serde_empty_body() {
+    let source = "begin end";
     assert_correct_program_serialization(source, false);
+    assert_correct_program_serialization(source, true);
+
+    // the serialized body consists of the body length only
+    let program = ProgramAst::parse(source).unwrap();
+    let bytes = program.to_bytes(AstSerdeOptions::new(false));
+    assert_eq!(&bytes[bytes.len() - 2..], &[0, 0]);
+    let deserialized = ProgramAst::from_bytes(&bytes).unwrap();
+    assert!(deserialized.body_nodes().is_empty());
+}
+
+#[test]
+fn test_ast_program_parse_empty_body() {
+    let source = "\
+    proc.foo
+        add
+    end
+
+    # the program does nothing
+    begin
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+    assert_eq!(program.procedures().len(), 1);
+    assert_eq!(program.num_body_nodes(), 0);
+    assert_eq!(program.body_start_location(), &SourceLocation::new(6, 5));
 }
 
 #[test]
@@ -790,6 +3342,38 @@ fn test_ast_program_serde_control_flow() {
     assert_correct_program_serialization(source, false);
 }
 
+#[test]
+fn test_ast_program_serde_write_into() {
+    let source = "\
+    use.std::math::u64
+
+    proc.foo.1
+        loc_load.0
+    end
+
+    begin
+        exec.foo
+        exec.u64::checked_add
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+
+    for serialize_imports in [true, false] {
+        let options = AstSerdeOptions::new(serialize_imports);
+        let mut target = Vec::new();
+        options.write_into(&mut target);
+        program.write_into(&mut target, options);
+        assert_eq!(target, program.to_bytes(options));
+
+        // skip the single-byte options header
+        let mut source = SliceReader::new(&target[1..]);
+        let mut expected = clear_procs_loc_program(program.clone());
+        if !serialize_imports {
+            clear_imports_program(&mut expected);
+        }
+        assert_eq!(expected, ProgramAst::read_from(&mut source, options).unwrap());
+    }
+}
+
 #[test]
 fn assert_parsing_line_unmatched_begin() {
     let source = format!("\n\nbegin\npush.1.2\n\nadd mul");
@@ -861,6 +3445,144 @@ fn assert_parsing_line_unexpected_token() {
     assert_eq!(err, ParsingError::unexpected_token(&Token::new("mul", location), "begin"));
 }
 
+#[test]
+fn test_render_diagnostic_mid_line() {
+    let source = "begin\n    push.1 foo.2\nend";
+    let err = ProgramAst::parse(source).err().unwrap();
+    let expected = "\
+error: instruction 'foo.2' is invalid
+ --> 2:12
+  |
+2 |     push.1 foo.2
+  |            ^";
+    assert_eq!(err.render_diagnostic(source), expected);
+}
+
+#[test]
+fn test_render_diagnostic_at_eof() {
+    let source = "proc.foo\nadd\nend";
+    let err = ProgramAst::parse(source).err().unwrap();
+    let expected = "\
+error: unexpected EOF
+ --> 3:1
+  |
+3 | end
+  | ^";
+    assert_eq!(err.render_diagnostic(source), expected);
+}
+
+#[test]
+fn test_ast_program_serde_with_locations() {
+    let source = "\
+    use.std::math::u64
+
+    proc.foo.1
+        loc_store.0
+    end
+
+    begin
+        push.1
+        if.true
+            exec.foo
+        end
+        exec.u64::checked_add
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+
+    let bytes = program.to_bytes_with_locations(AstSerdeOptions::new(true));
+    let parsed = ProgramAst::from_bytes_with_locations(&bytes).unwrap();
+    assert_eq!(program, parsed);
+    assert!(program.source_locations().eq(parsed.source_locations()));
+    for (proc, parsed_proc) in program.procedures().iter().zip(parsed.procedures()) {
+        assert!(proc.source_locations().eq(parsed_proc.source_locations()));
+    }
+
+    // the prefix can still be read without locations
+    let parsed = ProgramAst::from_bytes(&bytes).unwrap();
+    assert_eq!(program.body(), parsed.body());
+    assert!(!parsed.body().has_locations());
+}
+
+#[test]
+fn test_codebody_roundtrips() {
+    let source = "\
+    begin
+        push.1
+        if.true
+            push.2
+            while.true
+                repeat.3
+                    push.0x1234
+                end
+            end
+        else
+            mem_storew.4
+        end
+        adv.push_mapval
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+    assert_codebody_roundtrips(program.body());
+    assert_codebody_roundtrips(&CodeBody::default());
+}
+
+#[test]
+fn test_ast_program_serde_truncated() {
+    let source = "\
+    use.std::math::u64
+    use.std::crypto::fri::frie2f4
+
+    proc.foo
+        push.1
+    end
+
+    begin
+        exec.foo
+        exec.u64::checked_add
+    end";
+    let program = ProgramAst::parse(source).unwrap();
+    let options = AstSerdeOptions::new(true);
+    let bytes = program.to_bytes(options);
+
+    // truncated right after the number of imports
+    let imports_start = options.to_bytes().len() + 2;
+    let err = ProgramAst::from_bytes(&bytes[..imports_start]).unwrap_err();
+    let expected = "unexpected end of input: expected 2 imports, but only 0 could be read";
+    assert_eq!(err, DeserializationError::InvalidValue(expected.to_string()));
+
+    // truncated right after the number of local procedures
+    let imports_len: usize = program.imports.values().map(|path| path.to_bytes().len()).sum();
+    // the program declares no constants, and thus only their number (0) is written
+    let procs_start = imports_start + imports_len + 2 + 2;
+    let err = ProgramAst::from_bytes(&bytes[..procs_start]).unwrap_err();
+    let expected = "unexpected end of input: expected 1 local procedures, but only 0 could be read";
+    assert_eq!(err, DeserializationError::InvalidValue(expected.to_string()));
+
+    // truncated right after the number of body nodes
+    let body_len: usize = program.body_nodes().iter().map(|node| node.to_bytes().len()).sum();
+    let body_start = bytes.len() - body_len;
+    let err = ProgramAst::from_bytes(&bytes[..body_start]).unwrap_err();
+    let expected = "unexpected end of input: expected 2 body nodes, but only 0 could be read";
+    assert_eq!(err, DeserializationError::InvalidValue(expected.to_string()));
+
+    // truncated in the middle of the body
+    let err = ProgramAst::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err();
+    let expected = "unexpected end of input: expected 2 body nodes, but only 1 could be read";
+    assert_eq!(err, DeserializationError::InvalidValue(expected.to_string()));
+}
+
+#[test]
+fn test_ast_procedure_serde_docs_len_exceeds_input() {
+    let name = ProcedureName::try_from("foo".to_string()).unwrap();
+    let mut bytes = name.to_bytes();
+    // docs length of 1000 bytes, followed by only 3 bytes of docs
+    bytes.extend_from_slice(&1000_u16.to_le_bytes());
+    bytes.extend_from_slice(b"abc");
+
+    let err = ProcedureAst::read_from(&mut SliceReader::new(&bytes)).unwrap_err();
+    let expected = "unexpected end of input: expected 1000 bytes of procedure docs";
+    assert_eq!(err, DeserializationError::InvalidValue(expected.to_string()));
+}
+
 #[test]
 fn test_ast_program_serde_imports_serialized() {
     let source = "\
@@ -981,7 +3703,8 @@ fn assert_correct_program_serialization(source: &str, serialize_imports: bool) {
     let program = ProgramAst::parse(source).unwrap();
 
     // assert the correct program serialization
-    let program_serialized = program.to_bytes(AstSerdeOptions::new(serialize_imports));
+    let options = AstSerdeOptions::new(serialize_imports);
+    let program_serialized = program.to_bytes(options);
     let mut program_deserialized = ProgramAst::from_bytes(program_serialized.as_slice()).unwrap();
     let mut clear_program = clear_procs_loc_program(program.clone());
     if !serialize_imports {
@@ -991,7 +3714,7 @@ fn assert_correct_program_serialization(source: &str, serialize_imports: bool) {
 
     // assert the correct locations serialization
     let mut locations = Vec::new();
-    program.write_source_locations(&mut locations);
+    program.write_source_locations(&mut locations, options);
 
     // assert empty locations
     {
@@ -1002,7 +3725,7 @@ fn assert_correct_program_serialization(source: &str, serialize_imports: bool) {
     }
 
     program_deserialized
-        .load_source_locations(&mut SliceReader::new(&locations))
+        .load_source_locations(&mut SliceReader::new(&locations), options)
         .unwrap();
     if !serialize_imports {
         program_deserialized.imports = program.imports.clone();
@@ -1012,7 +3735,8 @@ fn assert_correct_program_serialization(source: &str, serialize_imports: bool) {
 
 fn assert_correct_module_serialization(source: &str, serialize_imports: bool) {
     let module = ModuleAst::parse(source).unwrap();
-    let module_serialized = module.to_bytes(AstSerdeOptions::new(serialize_imports));
+    let options = AstSerdeOptions::new(serialize_imports);
+    let module_serialized = module.to_bytes(options);
     let mut module_deserialized = ModuleAst::from_bytes(module_serialized.as_slice()).unwrap();
     let mut clear_module = clear_procs_loc_module(module.clone());
     if !serialize_imports {
@@ -1022,7 +3746,7 @@ fn assert_correct_module_serialization(source: &str, serialize_imports: bool) {
 
     // assert the correct locations serialization
     let mut locations = Vec::new();
-    module.write_source_locations(&mut locations);
+    module.write_source_locations(&mut locations, options);
 
     // assert module locations are empty
     module_deserialized.procs().iter().for_each(|m| {
@@ -1033,7 +3757,7 @@ fn assert_correct_module_serialization(source: &str, serialize_imports: bool) {
     });
 
     module_deserialized
-        .load_source_locations(&mut SliceReader::new(&locations))
+        .load_source_locations(&mut SliceReader::new(&locations), options)
         .unwrap();
     if !serialize_imports {
         module_deserialized.imports = module.imports.clone();
@@ -0,0 +1,41 @@
+use super::{CodeBody, Instruction, Node};
+
+// VISITOR
+// ================================================================================================
+
+/// A read-only pass over the nodes of a [CodeBody].
+///
+/// Visitors are driven by [walk_body], which invokes the methods of this trait for every node of
+/// a code body, including the nodes of nested control flow blocks. All methods do nothing by
+/// default, so that a visitor needs to implement only the methods it is interested in.
+pub trait Visitor {
+    /// Invoked for every node, including control flow nodes, before the nodes of their bodies are
+    /// visited.
+    fn visit_node(&mut self, _node: &Node) {}
+
+    /// Invoked for every instruction node, after [Visitor::visit_node] is invoked for it.
+    fn visit_instruction(&mut self, _instruction: &Instruction) {}
+}
+
+/// Walks the nodes of the provided code body in depth-first pre-order, invoking the `visitor`
+/// for every node.
+///
+/// The nodes of the `true` branch of an `if` node are visited before the nodes of its `else`
+/// branch.
+pub fn walk_body<V: Visitor>(body: &CodeBody, visitor: &mut V) {
+    for node in body.nodes() {
+        visitor.visit_node(node);
+        match node {
+            Node::Instruction(instruction) => visitor.visit_instruction(instruction),
+            Node::IfElse {
+                true_case,
+                false_case,
+            } => {
+                walk_body(true_case, visitor);
+                walk_body(false_case, visitor);
+            }
+            Node::Repeat { body, .. } | Node::While { body } => walk_body(body, visitor),
+            Node::Nop => {}
+        }
+    }
+}
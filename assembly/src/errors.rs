@@ -1,6 +1,6 @@
 use super::{
-    crypto::hash::RpoDigest, tokens::SourceLocation, LibraryNamespace, ProcedureId, String,
-    ToString, Token, Vec,
+    crypto::hash::RpoDigest, tokens::SourceLocation, LibraryNamespace, LibraryPath, ProcedureId,
+    String, ToString, Token, Vec,
 };
 use core::fmt;
 use vm_core::utils::write_hex_bytes;
@@ -214,6 +214,16 @@ impl ParsingError {
         }
     }
 
+    pub fn const_value_overflow(token: &Token, value: &str, max_value: u64) -> Self {
+        ParsingError {
+            message: format!(
+                "malformed constant `{token}` - value `{value}` is too large: constant value must be less than or equal to {max_value}"
+            ),
+            location: *token.location(),
+            op: token.to_string(),
+        }
+    }
+
     pub fn invalid_const_value(token: &Token, value: &str, reason: &str) -> Self {
         ParsingError {
             message: format!(
@@ -232,6 +242,34 @@ impl ParsingError {
         }
     }
 
+    pub fn const_module_not_found(token: &Token, module_path: &str) -> Self {
+        ParsingError {
+            message: format!(
+                "cannot import constants from module '{module_path}': module not found"
+            ),
+            location: *token.location(),
+            op: token.to_string(),
+        }
+    }
+
+    pub fn imported_const_conflict(token: &Token, label: &str) -> Self {
+        ParsingError {
+            message: format!("constant '{label}' conflicts with an imported constant"),
+            location: *token.location(),
+            op: token.to_string(),
+        }
+    }
+
+    pub fn const_docs_too_long(token: &Token, doc_len: usize, max_len: usize) -> Self {
+        ParsingError {
+            message: format!(
+                "constant doc comments cannot exceed {max_len} bytes, but was {doc_len}"
+            ),
+            location: *token.location(),
+            op: token.to_string(),
+        }
+    }
+
     pub fn const_not_found(token: &Token) -> Self {
         ParsingError {
             message: format!("constant used in operation `{token}` not found"),
@@ -422,6 +460,38 @@ impl ParsingError {
         }
     }
 
+    pub fn duplicate_merged_proc_name(label: &str, location: SourceLocation) -> Self {
+        ParsingError {
+            message: format!("cannot merge procedure: duplicate procedure name: {label}"),
+            location,
+            op: "".to_string(),
+        }
+    }
+
+    pub fn undefined_replaced_proc(label: &str) -> Self {
+        ParsingError {
+            message: format!("cannot replace procedure body: undefined local procedure: {label}"),
+            location: SourceLocation::default(),
+            op: "".to_string(),
+        }
+    }
+
+    pub fn missing_proc_name() -> Self {
+        ParsingError {
+            message: "procedure name is not specified".to_string(),
+            location: SourceLocation::default(),
+            op: "".to_string(),
+        }
+    }
+
+    pub fn invalid_proc_label(label: &str, err: LabelError) -> Self {
+        ParsingError {
+            message: format!("invalid procedure name: {err}"),
+            location: SourceLocation::default(),
+            op: label.to_string(),
+        }
+    }
+
     pub fn invalid_proc_name(token: &Token, err: LabelError) -> Self {
         ParsingError {
             message: format!("invalid procedure name: {err}"),
@@ -465,6 +535,21 @@ impl ParsingError {
         }
     }
 
+    pub fn proc_local_out_of_bounds(
+        op: &str,
+        location: SourceLocation,
+        index: u16,
+        num_locals: u16,
+    ) -> Self {
+        ParsingError {
+            message: format!(
+                "procedure local index {index} is out of bounds: procedure has {num_locals} locals"
+            ),
+            location,
+            op: op.to_string(),
+        }
+    }
+
     pub fn unmatched_proc(token: &Token, proc_name: &str) -> Self {
         ParsingError {
             message: format!("procedure '{proc_name}' has no matching end"),
@@ -491,28 +576,57 @@ impl ParsingError {
         }
     }
 
-    // PROCEDURE INVOCATION
+    pub fn invalid_proc_attribute(token: &Token, reason: &str) -> Self {
+        ParsingError {
+            message: format!("invalid procedure attribute '{token}': {reason}"),
+            location: *token.location(),
+            op: token.to_string(),
+        }
+    }
+
+    pub fn dangling_proc_attribute(token: &Token) -> Self {
+        ParsingError {
+            message: format!(
+                "procedure attribute '{token}' is not followed by a procedure declaration"
+            ),
+            location: *token.location(),
+            op: token.to_string(),
+        }
+    }
+
+    // CONDITIONAL COMPILATION
     // --------------------------------------------------------------------------------------------
 
-    pub fn invalid_proc_root_invocation(token: &Token, label: &str, err: LabelError) -> Self {
+    pub fn invalid_cfg_feature(token: &Token, feature: &str) -> Self {
         ParsingError {
-            message: format!("invalid procedure root invocation: {label} - {err}"),
+            message: format!("invalid feature name in cfg directive: '{feature}'"),
             location: *token.location(),
             op: token.to_string(),
         }
     }
 
-    pub fn invalid_proc_invocation(token: &Token, label: &str) -> Self {
+    pub fn unmatched_cfg(token: &Token) -> Self {
         ParsingError {
-            message: format!("invalid procedure invocation: {label}"),
+            message: "cfg block without matching end".to_string(),
             location: *token.location(),
             op: token.to_string(),
         }
     }
 
-    pub fn exec_with_mast_root(token: &Token) -> Self {
+    // PROCEDURE INVOCATION
+    // --------------------------------------------------------------------------------------------
+
+    pub fn invalid_proc_root_invocation(token: &Token, label: &str, err: LabelError) -> Self {
         ParsingError {
-            message: "invalid exec: cannot invoke a procedure on a mast root".to_string(),
+            message: format!("invalid procedure root invocation: {label} - {err}"),
+            location: *token.location(),
+            op: token.to_string(),
+        }
+    }
+
+    pub fn invalid_proc_invocation(token: &Token, label: &str) -> Self {
+        ParsingError {
+            message: format!("invalid procedure invocation: {label}"),
             location: *token.location(),
             op: token.to_string(),
         }
@@ -561,6 +675,17 @@ impl ParsingError {
         }
     }
 
+    pub fn conflicting_import_alias(token: &Token, existing_path: &str, new_path: &str) -> Self {
+        let alias = new_path.rsplit(LibraryPath::PATH_DELIM).next().unwrap_or(new_path);
+        ParsingError {
+            message: format!(
+                "conflicting module imports: '{alias}' refers to both {existing_path} and {new_path}"
+            ),
+            location: *token.location(),
+            op: token.to_string(),
+        }
+    }
+
     pub fn invalid_module_path(token: &Token, module_path: &str) -> Self {
         ParsingError {
             message: format!("invalid module import path: {module_path}"),
@@ -608,6 +733,45 @@ impl ParsingError {
     pub const fn location(&self) -> &SourceLocation {
         &self.location
     }
+
+    // DIAGNOSTICS
+    // --------------------------------------------------------------------------------------------
+
+    /// Renders this error as a multi-line diagnostic against the `source` it was produced from.
+    ///
+    /// The diagnostic contains the error message, the location of the error, the text of the
+    /// offending line, and a caret pointing at the offending column, e.g.:
+    ///
+    /// ```text
+    /// error: invalid operation: 'foo'
+    ///  --> 3:5
+    ///   |
+    /// 3 |     foo
+    ///   |     ^
+    /// ```
+    ///
+    /// If the location of the error lies beyond the end of the `source`, the line text is left
+    /// empty.
+    pub fn render_diagnostic(&self, source: &str) -> String {
+        let line = self.location.line();
+        let column = self.location.column();
+        let line_text =
+            source.lines().nth((line as usize).saturating_sub(1)).unwrap_or("").trim_end();
+
+        // keep tabs in the padding so that the caret lines up with the offending column
+        let padding: String = line_text
+            .chars()
+            .chain(core::iter::repeat(' '))
+            .take((column as usize).saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let gutter = " ".repeat(line.to_string().len());
+
+        format!(
+            "error: {message}\n{gutter}--> {line}:{column}\n{gutter} |\n{line} | {line_text}\n{gutter} | {padding}^",
+            message = self.message,
+        )
+    }
 }
 
 impl fmt::Debug for ParsingError {
@@ -632,6 +796,89 @@ impl From<ParsingError> for std::io::Error {
 #[cfg(feature = "std")]
 impl std::error::Error for ParsingError {}
 
+// PARSING WARNING
+// ================================================================================================
+
+/// A non-fatal diagnostic which can be generated while parsing a Miden assembly source code into
+/// an AST.
+#[derive(Clone, Eq, PartialEq)]
+pub struct ParsingWarning {
+    message: String,
+    location: SourceLocation,
+    op: String,
+}
+
+impl ParsingWarning {
+    // CONSTRUCTORS
+    // --------------------------------------------------------------------------------------------
+
+    pub fn duplicate_module_import(token: &Token, module: &str) -> Self {
+        ParsingWarning {
+            message: format!("duplicate module import ignored: {module}"),
+            location: *token.location(),
+            op: token.to_string(),
+        }
+    }
+
+    pub fn empty_exported_proc(proc_name: &str, location: SourceLocation) -> Self {
+        ParsingWarning {
+            message: format!("exported procedure '{proc_name}' does not contain any instructions"),
+            location,
+            op: "".to_string(),
+        }
+    }
+
+    pub fn mixed_indentation(location: SourceLocation) -> Self {
+        ParsingWarning {
+            message: "indentation mixes tabs and spaces".to_string(),
+            location,
+            op: "".to_string(),
+        }
+    }
+
+    pub fn unused_import(module_name: &str, location: SourceLocation) -> Self {
+        ParsingWarning {
+            message: format!("module '{module_name}' is imported but never used"),
+            location,
+            op: "".to_string(),
+        }
+    }
+
+    pub fn unreferenced_constant(const_name: &str, location: SourceLocation) -> Self {
+        ParsingWarning {
+            message: format!("constant '{const_name}' is declared but never referenced"),
+            location,
+            op: "".to_string(),
+        }
+    }
+
+    // PUBLIC ACCESSORS
+    // --------------------------------------------------------------------------------------------
+    pub fn message(&self) -> &String {
+        &self.message
+    }
+
+    pub fn operation(&self) -> &String {
+        &self.op
+    }
+
+    pub const fn location(&self) -> &SourceLocation {
+        &self.location
+    }
+}
+
+impl fmt::Debug for ParsingWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "parsing warning at {}: {}", self.location, self.message)
+    }
+}
+
+impl fmt::Display for ParsingWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "parsing warning at {}: {}", self.location, self.message)
+    }
+}
+
 // NAME ERROR
 // ================================================================================================
 
@@ -979,3 +1226,48 @@ impl fmt::Display for PathError {
 
 #[cfg(feature = "std")]
 impl std::error::Error for PathError {}
+
+// RENDER ERROR
+// ================================================================================================
+
+/// An error which can be generated while rendering an AST into Miden assembly source.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum RenderError {
+    EmptyModule,
+    UnknownLocalProcedure(u16),
+    UnknownProcedure(ProcedureId),
+}
+
+impl RenderError {
+    pub const fn empty_module() -> Self {
+        Self::EmptyModule
+    }
+
+    pub const fn unknown_local_procedure(index: u16) -> Self {
+        Self::UnknownLocalProcedure(index)
+    }
+
+    pub const fn unknown_procedure(proc_id: ProcedureId) -> Self {
+        Self::UnknownProcedure(proc_id)
+    }
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use RenderError::*;
+        match self {
+            EmptyModule => {
+                write!(f, "module without imports, constants, or procedures cannot be rendered")
+            }
+            UnknownLocalProcedure(index) => {
+                write!(f, "module has no local procedure at index {index}")
+            }
+            UnknownProcedure(proc_id) => {
+                write!(f, "name of the invoked procedure {proc_id} is not known")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RenderError {}
@@ -19,17 +19,21 @@ mod library;
 pub use library::{Library, LibraryNamespace, LibraryPath, MaslLibrary, Module, Version};
 
 mod procedures;
+pub use procedures::{detect_id_collisions, ProcedureId, ProcedureName};
 use procedures::{CallSet, Procedure};
-pub use procedures::{ProcedureId, ProcedureName};
 
 pub mod ast;
+#[cfg(any(test, feature = "testing"))]
+pub use ast::assert_codebody_roundtrips;
 use ast::{NAMESPACE_LABEL_PARSER, PROCEDURE_LABEL_PARSER};
 
 mod tokens;
 use tokens::{Token, TokenStream};
 
 mod errors;
-pub use errors::{AssemblyError, LabelError, LibraryError, ParsingError, PathError};
+pub use errors::{
+    AssemblyError, LabelError, LibraryError, ParsingError, ParsingWarning, PathError, RenderError,
+};
 
 mod assembler;
 pub use assembler::{Assembler, AssemblyContext, AssemblyContextType};
@@ -1,7 +1,7 @@
 use super::{
-    super::BTreeSet, AstSerdeOptions, ByteReader, ByteWriter, Deserializable, DeserializationError,
-    Library, LibraryError, LibraryNamespace, LibraryPath, Module, ModuleAst, Serializable, Vec,
-    Version, MAX_DEPENDENCIES, MAX_MODULES,
+    super::BTreeSet, AstFormatVersion, AstSerdeOptions, ByteReader, ByteWriter, Deserializable,
+    DeserializationError, Library, LibraryError, LibraryNamespace, LibraryPath, Module, ModuleAst,
+    Serializable, Vec, Version, MAX_DEPENDENCIES, MAX_MODULES,
 };
 use core::slice::Iter;
 
@@ -12,8 +12,14 @@ use core::slice::Iter;
 /// Serialization options for [ModuleAst]. Imports are part of the ModuleAst serialization.
 const AST_SERDE_OPTIONS: AstSerdeOptions = AstSerdeOptions {
     serialize_imports: true,
+    version: AstFormatVersion::CURRENT,
 };
 
+/// Leading byte of libraries serialized with an explicit AST format version. Libraries serialized
+/// before the format was versioned start with the length of the library namespace instead, which
+/// is never zero.
+const VERSIONED_FORMAT_MARKER: u8 = 0;
+
 // LIBRARY IMPLEMENTATION FOR MASL FILES
 // ================================================================================================
 
@@ -273,6 +279,8 @@ mod use_std {
 
 impl Serializable for MaslLibrary {
     fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write_u8(VERSIONED_FORMAT_MARKER);
+        AST_SERDE_OPTIONS.version.write_into(target);
         self.namespace.write_into(target);
         self.version.write_into(target);
 
@@ -297,13 +305,23 @@ impl Serializable for MaslLibrary {
         // written, we can safely dump the locations structs
         target.write_bool(self.has_source_locations);
         if self.has_source_locations {
-            self.modules.iter().for_each(|m| m.write_source_locations(target));
+            self.modules
+                .iter()
+                .for_each(|m| m.write_source_locations(target, AST_SERDE_OPTIONS));
         }
     }
 }
 
 impl Deserializable for MaslLibrary {
     fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        let version = if source.peek_u8()? == VERSIONED_FORMAT_MARKER {
+            source.read_u8()?;
+            AstFormatVersion::read_from(source)?
+        } else {
+            AstFormatVersion::V0
+        };
+        let options = AST_SERDE_OPTIONS.with_version(version);
+
         let namespace = LibraryNamespace::read_from(source)?;
         let version = Version::read_from(source)?;
 
@@ -321,14 +339,14 @@ impl Deserializable for MaslLibrary {
             let path = LibraryPath::read_from(source)?
                 .prepend(&namespace)
                 .map_err(|err| DeserializationError::InvalidValue(format!("{err}")))?;
-            let ast = ModuleAst::read_from(source, AST_SERDE_OPTIONS)?;
+            let ast = ModuleAst::read_from(source, options)?;
             modules.push(Module { path, ast });
         }
 
         // for each module, load its locations
         let has_source_locations = source.read_bool()?;
         if has_source_locations {
-            modules.iter_mut().try_for_each(|m| m.load_source_locations(source))?;
+            modules.iter_mut().try_for_each(|m| m.load_source_locations(source, options))?;
         }
 
         let deps = deps_set.into_iter().collect();
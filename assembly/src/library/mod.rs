@@ -1,5 +1,5 @@
 use super::{
-    ast::{AstSerdeOptions, ModuleAst},
+    ast::{AstFormatVersion, AstSerdeOptions, ModuleAst},
     ByteReader, ByteWriter, Deserializable, DeserializationError, LibraryError, PathError,
     Serializable, String, ToString, Vec, MAX_LABEL_LEN, NAMESPACE_LABEL_PARSER,
 };
@@ -119,16 +119,19 @@ impl Module {
     // --------------------------------------------------------------------------------------------
 
     /// Loads the [SourceLocation] of the procedures via [ModuleAst::load_source_locations].
+    ///
+    /// The serde options must correspond to the options used to write the locations.
     pub fn load_source_locations<R: ByteReader>(
         &mut self,
         source: &mut R,
+        options: AstSerdeOptions,
     ) -> Result<(), DeserializationError> {
-        self.ast.load_source_locations(source)
+        self.ast.load_source_locations(source, options)
     }
 
     /// Writes the [SourceLocation] of the procedures via [ModuleAst::write_source_locations].
-    pub fn write_source_locations<W: ByteWriter>(&self, target: &mut W) {
-        self.ast.write_source_locations(target)
+    pub fn write_source_locations<W: ByteWriter>(&self, target: &mut W, options: AstSerdeOptions) {
+        self.ast.write_source_locations(target, options)
     }
 
     /// Serialization of [Module] via [LibraryPath::write_into] and
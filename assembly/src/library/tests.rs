@@ -1,5 +1,8 @@
-use super::{LibraryNamespace, LibraryPath, MaslLibrary, Module, ModuleAst, Version};
-use vm_core::utils::{Deserializable, Serializable, SliceReader};
+use super::{
+    AstFormatVersion, AstSerdeOptions, LibraryNamespace, LibraryPath, MaslLibrary, Module,
+    ModuleAst, Version,
+};
+use vm_core::utils::{ByteWriter, Deserializable, Serializable, SliceReader};
 
 #[test]
 fn masl_locations_serialization() {
@@ -56,3 +59,33 @@ fn masl_locations_serialization() {
     bundle.clear_locations();
     assert_eq!(bundle, deserialized);
 }
+
+#[test]
+fn masl_unversioned_deserialization() {
+    let source = r#"
+        export.foo
+            add
+        end
+    "#;
+    let path = LibraryPath::new("test::foo").unwrap();
+    let ast = ModuleAst::parse(source).unwrap();
+    let module = Module::new(path, ast);
+
+    // write the library in the format used before the AST format was versioned
+    let namespace = LibraryNamespace::new("test").unwrap();
+    let mut bytes = Vec::new();
+    namespace.write_into(&mut bytes);
+    Version::MIN.write_into(&mut bytes);
+    bytes.write_u16(0);
+    bytes.write_u16(1);
+    LibraryPath::new("foo").unwrap().write_into(&mut bytes);
+    let options = AstSerdeOptions::new(true).with_version(AstFormatVersion::V0);
+    module.ast.write_into(&mut bytes, options);
+    bytes.write_bool(false);
+
+    let deserialized = MaslLibrary::read_from(&mut SliceReader::new(&bytes)).unwrap();
+    let mut expected =
+        MaslLibrary::new(namespace, Version::MIN, false, vec![module], Vec::new()).unwrap();
+    expected.clear_locations();
+    assert_eq!(deserialized, expected);
+}
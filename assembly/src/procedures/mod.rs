@@ -1,13 +1,15 @@
 use super::{
-    crypto::hash::Blake3_160, BTreeSet, ByteReader, ByteWriter, CodeBlock, Deserializable,
-    DeserializationError, LabelError, LibraryPath, Serializable, String, ToString,
-    PROCEDURE_LABEL_PARSER,
+    crypto::hash::{Blake3_160, RpoDigest},
+    BTreeMap, BTreeSet, ByteReader, ByteWriter, CodeBlock, Deserializable, DeserializationError,
+    LabelError, LibraryPath, Serializable, String, ToString, Vec, PROCEDURE_LABEL_PARSER,
 };
 use core::{
     fmt,
     ops::{self, Deref},
-    str::from_utf8,
+    str::FromStr,
 };
+#[cfg(feature = "std")]
+use std::collections::HashSet;
 
 // PROCEDURE
 // ================================================================================================
@@ -81,6 +83,49 @@ impl Procedure {
     }
 }
 
+/// Two procedures are considered equal if they reserve the same number of memory locals, have
+/// the same MAST root, and have the same callset.
+///
+/// This is semantic (hash-based) equality: procedures with different IDs or labels, or with
+/// MASTs which differ only in the decorators, are considered equal.
+impl PartialEq for Procedure {
+    fn eq(&self, other: &Self) -> bool {
+        self.num_locals == other.num_locals
+            && self.code_root.hash() == other.code_root.hash()
+            && self.callset == other.callset
+    }
+}
+
+impl Eq for Procedure {}
+
+/// Serializes the procedure together with its callset, so that the call graph of a compiled
+/// library does not need to be recomputed after it is loaded.
+///
+/// Only the hash of the procedure's MAST root is serialized. Thus, the code root of a
+/// deserialized procedure is a proxy block for the original MAST root.
+impl Serializable for Procedure {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        self.id.write_into(target);
+        self.label.write_into(target);
+        target.write_bool(self.is_export);
+        target.write_u32(self.num_locals);
+        self.code_root.hash().write_into(target);
+        self.callset.write_into(target);
+    }
+}
+
+impl Deserializable for Procedure {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        let id = ProcedureId::read_from(source)?;
+        let label = ProcedureName::read_from(source)?;
+        let is_export = source.read_bool()?;
+        let num_locals = source.read_u32()?;
+        let code_root = CodeBlock::new_proxy(RpoDigest::read_from(source)?);
+        let callset = CallSet::read_from(source)?;
+        Ok(Self::new(id, label, is_export, num_locals, code_root, callset))
+    }
+}
+
 // PROCEDURE NAME
 // ================================================================================================
 
@@ -118,6 +163,24 @@ impl ProcedureName {
         }
     }
 
+    // PUBLIC ACCESSORS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns the length of this name in bytes.
+    ///
+    /// The length is the one written as the `u8` prefix of the serialized name, and thus never
+    /// exceeds 255.
+    pub fn len(&self) -> usize {
+        self.name.len()
+    }
+
+    /// Returns true if this name is empty.
+    ///
+    /// Names constructed via [Self::main] or [Self::try_from] are never empty.
+    pub fn is_empty(&self) -> bool {
+        self.name.is_empty()
+    }
+
     // HELPERS
     // --------------------------------------------------------------------------------------------
 
@@ -125,15 +188,41 @@ impl ProcedureName {
     pub fn is_main(&self) -> bool {
         self.name == Self::MAIN_PROC_NAME
     }
+
+    /// Returns true if this name is equal to the provided one, where the reserved name for `main`
+    /// is also considered equal to `main`.
+    ///
+    /// Unlike the [Eq] implementation, this does not distinguish between `#main` and `main`.
+    pub fn matches(&self, other: &str) -> bool {
+        let strip_main = |name| match name {
+            Self::MAIN_PROC_NAME => &Self::MAIN_PROC_NAME[1..],
+            _ => name,
+        };
+        strip_main(self.name.as_str()) == strip_main(other)
+    }
+
+    /// Returns true if the provided string is a valid procedure name.
+    ///
+    /// The name is checked against the same rules as [`Self::try_from`], with the reserved name
+    /// for `main` also being accepted. Unlike [`Self::try_from`], this does not allocate.
+    pub fn is_valid(name: &str) -> bool {
+        name == Self::MAIN_PROC_NAME || PROCEDURE_LABEL_PARSER.is_valid_label(name)
+    }
+
+    /// Returns the ID of the procedure with this name located in the module at `module_path`.
+    ///
+    /// This is equivalent to [ProcedureId::from_name].
+    pub fn to_id(&self, module_path: &LibraryPath) -> ProcedureId {
+        ProcedureId::from_name(&self.name, module_path)
+    }
 }
 
 impl TryFrom<String> for ProcedureName {
     type Error = LabelError;
 
     fn try_from(name: String) -> Result<Self, Self::Error> {
-        Ok(Self {
-            name: (PROCEDURE_LABEL_PARSER.parse_label(&name)?).to_string(),
-        })
+        PROCEDURE_LABEL_PARSER.parse_label(&name)?;
+        Ok(Self { name })
     }
 }
 
@@ -166,11 +255,13 @@ impl Serializable for ProcedureName {
 impl Deserializable for ProcedureName {
     fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
         let nlen = source.read_u8()? as usize;
-        let name = source.read_vec(nlen)?;
-        let name =
-            from_utf8(&name).map_err(|e| DeserializationError::InvalidValue(e.to_string()))?;
-        ProcedureName::try_from(name.to_string())
-            .map_err(|e| DeserializationError::InvalidValue(e.to_string()))
+        // the bytes are converted into the name in place, so that the only allocation in the
+        // common case is the one backing the name
+        let name = String::from_utf8(source.read_vec(nlen)?).map_err(|e| {
+            DeserializationError::InvalidValue(format!("invalid procedure name: {e}"))
+        })?;
+        ProcedureName::try_from(name)
+            .map_err(|e| DeserializationError::InvalidValue(format!("invalid procedure name: {e}")))
     }
 }
 
@@ -220,6 +311,48 @@ impl ProcedureId {
         let path = module_path.append_unchecked(index.to_string());
         Self::new(path)
     }
+
+    /// Creates a new procedure ID from the provided bytes.
+    ///
+    /// # Errors
+    /// Returns an error if the number of provided bytes is not equal to [ProcedureId::SIZE].
+    pub fn try_from_bytes(bytes: &[u8]) -> Result<Self, DeserializationError> {
+        let id = bytes.try_into().map_err(|_| {
+            DeserializationError::InvalidValue(format!(
+                "procedure id must consist of {} bytes, but was {}",
+                Self::SIZE,
+                bytes.len()
+            ))
+        })?;
+        Ok(Self(id))
+    }
+
+    /// Returns a human-readable representation of this procedure ID combined with the provided
+    /// procedure path, e.g. `std::math::u64::add (0x1a2b…)`.
+    ///
+    /// Only the first two bytes of the ID are included. No validation is performed regarding
+    /// whether the ID was derived from the provided path.
+    pub fn format_with_path(&self, path: &LibraryPath) -> String {
+        format!("{} (0x{:02x}{:02x}…)", path.as_str(), self.0[0], self.0[1])
+    }
+
+    /// Returns the number of bits in which this procedure ID differs from the `other` one (i.e.,
+    /// the Hamming distance between the two IDs).
+    pub fn distance(&self, other: &Self) -> u32 {
+        self.0.iter().zip(other.0.iter()).map(|(a, b)| (a ^ b).count_ones()).sum()
+    }
+
+    /// Returns the smallest Hamming distance between any two of the provided procedure IDs, or
+    /// None if fewer than two IDs are provided.
+    ///
+    /// This can be used to audit how close a set of procedures is to an ID collision. All pairs
+    /// are compared, and thus the running time is quadratic in the number of IDs.
+    pub fn min_id_distance(ids: &[Self]) -> Option<u32> {
+        ids.iter()
+            .enumerate()
+            .flat_map(|(i, a)| ids[i + 1..].iter().map(move |b| a.distance(b)))
+            .min()
+    }
 }
 
 impl From<[u8; ProcedureId::SIZE]> for ProcedureId {
@@ -252,6 +385,38 @@ impl fmt::Display for ProcedureId {
     }
 }
 
+impl FromStr for ProcedureId {
+    type Err = DeserializationError;
+
+    /// Parses a procedure ID from its hexadecimal representation, as produced by the [Display]
+    /// implementation.
+    ///
+    /// The string may optionally be prefixed with `0x`, and must otherwise consist of exactly
+    /// `2 * SIZE` hexadecimal characters. Parsing is case-insensitive.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")).unwrap_or(s);
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(DeserializationError::InvalidValue(format!(
+                "procedure id '{s}' contains non-hex characters"
+            )));
+        }
+        if hex.len() != Self::SIZE * 2 {
+            return Err(DeserializationError::InvalidValue(format!(
+                "procedure id must consist of {} hex characters, but '{s}' has {}",
+                Self::SIZE * 2,
+                hex.len()
+            )));
+        }
+
+        let mut id = [0u8; Self::SIZE];
+        for (i, byte) in id.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16)
+                .expect("hex characters were validated above");
+        }
+        Ok(Self(id))
+    }
+}
+
 impl Serializable for ProcedureId {
     fn write_into<W: ByteWriter>(&self, target: &mut W) {
         target.write_bytes(&self.0);
@@ -265,26 +430,180 @@ impl Deserializable for ProcedureId {
     }
 }
 
+/// Returns the IDs which are shared by several differently named procedures, together with the
+/// names of these procedures.
+///
+/// The `procs` are the compiled procedures of all modules of a library. Since procedure IDs are
+/// truncated hashes of procedure paths, distinct procedures may, however unlikely, share an ID, in
+/// which case invocations by ID would be dispatched to the wrong procedure. Procedures with the
+/// same label and ID are considered to be the same procedure, and thus are not reported.
+///
+/// The collisions are returned in the order of their IDs, and the names of each collision are
+/// listed in the order in which they first appear in `procs`.
+pub fn detect_id_collisions<'a, I>(procs: I) -> Vec<(ProcedureId, Vec<ProcedureName>)>
+where
+    I: IntoIterator<Item = &'a Procedure>,
+{
+    let mut names_by_id = BTreeMap::<ProcedureId, Vec<ProcedureName>>::new();
+    for proc in procs {
+        let names = names_by_id.entry(*proc.id()).or_default();
+        if !names.contains(proc.label()) {
+            names.push(proc.label().clone());
+        }
+    }
+    names_by_id.into_iter().filter(|(_, names)| names.len() > 1).collect()
+}
+
 // CALLSET
 // ================================================================================================
 
 /// Contains a list of all procedures which may be invoked from a procedure via call or syscall
 /// instructions.
-#[derive(Debug, Default, Clone, PartialEq, Eq)]
-pub struct CallSet(BTreeSet<ProcedureId>);
+///
+/// The procedures are kept in an ordered set, which the call set dereferences to. When the `std`
+/// feature is enabled, a call set with an additional hash index can be created via
+/// [CallSet::with_hash_backing]. The index provides faster membership checks for call-heavy
+/// programs, and otherwise does not change the behavior of the call set.
+#[derive(Debug, Default, Clone)]
+pub struct CallSet {
+    proc_ids: BTreeSet<ProcedureId>,
+    #[cfg(feature = "std")]
+    index: Option<HashSet<ProcedureId>>,
+}
 
 impl CallSet {
+    /// Returns a new empty [CallSet] whose membership checks are backed by a hash set.
+    #[cfg(feature = "std")]
+    pub fn with_hash_backing() -> Self {
+        Self {
+            proc_ids: BTreeSet::new(),
+            index: Some(HashSet::new()),
+        }
+    }
+
+    /// Returns a new [CallSet] containing the procedures invoked by the specified `callees`.
+    ///
+    /// Each callee name is resolved into a procedure ID via the provided `name_to_id` map.
+    ///
+    /// # Errors
+    /// Returns the name of the first callee which could not be resolved.
+    pub fn resolve(
+        callees: &[String],
+        name_to_id: &BTreeMap<String, ProcedureId>,
+    ) -> Result<Self, String> {
+        callees
+            .iter()
+            .map(|callee| name_to_id.get(callee).copied().ok_or_else(|| callee.clone()))
+            .collect::<Result<_, _>>()
+            .map(Self::from_ordered)
+    }
+
     pub fn contains(&self, proc_id: &ProcedureId) -> bool {
-        self.0.contains(proc_id)
+        #[cfg(feature = "std")]
+        if let Some(index) = &self.index {
+            return index.contains(proc_id);
+        }
+        self.proc_ids.contains(proc_id)
+    }
+
+    /// Returns the number of procedures in this [CallSet].
+    pub fn len(&self) -> usize {
+        self.proc_ids.len()
+    }
+
+    /// Returns true if this [CallSet] does not contain any procedures.
+    pub fn is_empty(&self) -> bool {
+        self.proc_ids.is_empty()
     }
 
     pub fn insert(&mut self, proc_id: ProcedureId) {
-        self.0.insert(proc_id);
+        self.proc_ids.insert(proc_id);
+        #[cfg(feature = "std")]
+        if let Some(index) = &mut self.index {
+            index.insert(proc_id);
+        }
     }
 
     pub fn append(&mut self, other: &CallSet) {
-        for &item in other.0.iter() {
-            self.0.insert(item);
+        for &item in other.iter() {
+            self.insert(item);
+        }
+    }
+
+    /// Retains only the procedures for which the predicate returns true, removing all other
+    /// procedures from this [CallSet].
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&ProcedureId) -> bool,
+    {
+        self.proc_ids.retain(f);
+        #[cfg(feature = "std")]
+        if let Some(index) = &mut self.index {
+            index.retain(|proc_id| self.proc_ids.contains(proc_id));
+        }
+    }
+
+    /// Returns a new [CallSet] containing the procedures present in both `self` and `other`.
+    ///
+    /// The returned [CallSet] has a hash index only if `self` has one.
+    pub fn intersection(&self, other: &CallSet) -> CallSet {
+        self.filter(|proc_id| other.contains(proc_id))
+    }
+
+    /// Returns a new [CallSet] containing the procedures present in `self` but not in `other`.
+    ///
+    /// The returned [CallSet] has a hash index only if `self` has one.
+    pub fn difference(&self, other: &CallSet) -> CallSet {
+        self.filter(|proc_id| !other.contains(proc_id))
+    }
+
+    /// Returns the MAST roots of all procedures which may be invoked starting from the procedures
+    /// in this [CallSet].
+    ///
+    /// This includes the procedures in this [CallSet] as well as the procedures in the call sets
+    /// of these procedures, transitively. Procedures are looked up by their IDs in the provided
+    /// `procedures`; procedures which cannot be found are skipped.
+    pub fn reachable_roots(&self, procedures: &[Procedure]) -> BTreeSet<RpoDigest> {
+        let procedures = procedures.iter().map(|proc| (proc.id, proc)).collect::<BTreeMap<_, _>>();
+
+        let mut visited = BTreeSet::new();
+        let mut roots = BTreeSet::new();
+        let mut pending = self.iter().copied().collect::<Vec<_>>();
+        while let Some(proc_id) = pending.pop() {
+            if !visited.insert(proc_id) {
+                continue;
+            }
+            if let Some(proc) = procedures.get(&proc_id) {
+                roots.insert(proc.code_root.hash());
+                pending.extend(proc.callset.iter().copied());
+            }
+        }
+        roots
+    }
+
+    // HELPER METHODS
+    // --------------------------------------------------------------------------------------------
+
+    /// Returns a new [CallSet] without a hash index containing the specified procedures.
+    fn from_ordered(proc_ids: BTreeSet<ProcedureId>) -> Self {
+        Self {
+            proc_ids,
+            #[cfg(feature = "std")]
+            index: None,
+        }
+    }
+
+    /// Returns a new [CallSet] containing the procedures of `self` for which the predicate
+    /// returns true. The returned [CallSet] has a hash index only if `self` has one.
+    fn filter<P>(&self, predicate: P) -> CallSet
+    where
+        P: Fn(&ProcedureId) -> bool,
+    {
+        let proc_ids = self.proc_ids.iter().copied().filter(predicate).collect::<BTreeSet<_>>();
+        Self {
+            #[cfg(feature = "std")]
+            index: self.index.as_ref().map(|_| proc_ids.iter().copied().collect()),
+            proc_ids,
         }
     }
 }
@@ -293,13 +612,72 @@ impl ops::Deref for CallSet {
     type Target = BTreeSet<ProcedureId>;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.proc_ids
+    }
+}
+
+impl PartialEq for CallSet {
+    /// Call sets are equal if they contain the same procedures, regardless of their hash index.
+    fn eq(&self, other: &Self) -> bool {
+        self.proc_ids == other.proc_ids
+    }
+}
+
+impl Eq for CallSet {}
+
+impl Serializable for CallSet {
+    fn write_into<W: ByteWriter>(&self, target: &mut W) {
+        target.write_u32(self.proc_ids.len() as u32);
+        for proc_id in self.proc_ids.iter() {
+            proc_id.write_into(target);
+        }
+    }
+}
+
+/// A deserialized call set has no hash index.
+impl Deserializable for CallSet {
+    fn read_from<R: ByteReader>(source: &mut R) -> Result<Self, DeserializationError> {
+        let num_procs = source.read_u32()?;
+        let proc_ids = (0..num_procs)
+            .map(|_| ProcedureId::read_from(source))
+            .collect::<Result<BTreeSet<_>, _>>()?;
+        Ok(Self::from_ordered(proc_ids))
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{super::MAX_LABEL_LEN, LabelError, ProcedureName};
+    use super::{
+        super::{Operation, MAX_LABEL_LEN},
+        detect_id_collisions, BTreeMap, CallSet, CodeBlock, Deserializable, DeserializationError,
+        LabelError, LibraryPath, Procedure, ProcedureId, ProcedureName, Serializable,
+    };
+    use core::str::FromStr;
+    use vm_core::utils::SliceReader;
+
+    #[test]
+    fn test_procedure_eq() {
+        let code = CodeBlock::new_span(vec![Operation::Push(1u32.into()), Operation::Add]);
+        let foo = ProcedureName::try_from("foo".to_string()).unwrap();
+        let bar = ProcedureName::try_from("bar".to_string()).unwrap();
+        let new_proc = |name: &ProcedureName, num_locals| {
+            let id = ProcedureId::new(name.as_str());
+            Procedure::new(id, name.clone(), false, num_locals, code.clone(), CallSet::default())
+        };
+
+        // identical code is equal regardless of the procedure name and ID
+        assert_eq!(new_proc(&foo, 1), new_proc(&foo, 1));
+        assert_eq!(new_proc(&foo, 1), new_proc(&bar, 1));
+
+        // procedures differing only in the number of locals are not equal
+        assert_ne!(new_proc(&foo, 1), new_proc(&foo, 2));
+
+        // procedures with different code are not equal
+        let other_code = CodeBlock::new_span(vec![Operation::Push(1u32.into()), Operation::Mul]);
+        let id = ProcedureId::new("foo");
+        let other = Procedure::new(id, foo.clone(), false, 1, other_code, CallSet::default());
+        assert_ne!(new_proc(&foo, 1), other);
+    }
 
     #[test]
     fn test_procedure_name_max_len() {
@@ -311,4 +689,399 @@ mod test {
             Err(LabelError::LabelTooLong(long, MAX_LABEL_LEN))
         );
     }
+
+    #[test]
+    fn test_procedure_name_errors() {
+        // each kind of invalid name yields a distinct error
+        assert_eq!(ProcedureName::try_from(String::new()), Err(LabelError::EmptyLabel));
+
+        let long = "a".repeat(MAX_LABEL_LEN + 1);
+        assert_eq!(
+            ProcedureName::try_from(long.clone()),
+            Err(LabelError::LabelTooLong(long, MAX_LABEL_LEN))
+        );
+
+        assert_eq!(
+            ProcedureName::try_from("foo-bar".to_string()),
+            Err(LabelError::InvalidChars("foo-bar".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_procedure_name_len() {
+        let main = ProcedureName::main();
+        assert_eq!(main.len(), ProcedureName::MAIN_PROC_NAME.len());
+        assert!(!main.is_empty());
+
+        let name = ProcedureName::try_from("a".to_string()).unwrap();
+        assert_eq!(name.len(), 1);
+        assert!(!name.is_empty());
+        assert_eq!(name.to_bytes()[0] as usize, name.len());
+    }
+
+    #[test]
+    fn test_procedure_name_deserialization() {
+        let read = |bytes: &[u8]| ProcedureName::read_from(&mut SliceReader::new(bytes));
+
+        let name = ProcedureName::try_from("foo_bar".to_string()).unwrap();
+        assert_eq!(read(&name.to_bytes()), Ok(name));
+
+        // invalid UTF-8 is rejected before the label rules are checked
+        let err = read(&[2, 0xc3, 0x28]).unwrap_err();
+        assert!(matches!(&err, DeserializationError::InvalidValue(msg) if msg.contains("utf-8")));
+
+        // valid UTF-8 which is not a valid label is rejected by the label rules
+        let err = read(&[7, b'f', b'o', b'o', b'-', b'b', b'a', b'r']).unwrap_err();
+        let expected = LabelError::InvalidChars("foo-bar".to_string());
+        assert_eq!(
+            err,
+            DeserializationError::InvalidValue(format!("invalid procedure name: {expected}"))
+        );
+    }
+
+    #[test]
+    fn test_procedure_name_is_valid() {
+        assert!(ProcedureName::is_valid("foo"));
+        assert!(ProcedureName::is_valid("foo_bar1"));
+        assert!(ProcedureName::is_valid(ProcedureName::MAIN_PROC_NAME));
+        assert!(ProcedureName::is_valid(&"a".repeat(MAX_LABEL_LEN)));
+
+        assert!(!ProcedureName::is_valid(""));
+        assert!(!ProcedureName::is_valid("1foo"));
+        assert!(!ProcedureName::is_valid("foo-bar"));
+        assert!(!ProcedureName::is_valid("#foo"));
+        assert!(!ProcedureName::is_valid(&"a".repeat(MAX_LABEL_LEN + 1)));
+
+        // validity must agree with the checked constructor
+        for name in ["foo", "", "1foo", "foo-bar"] {
+            assert_eq!(
+                ProcedureName::is_valid(name),
+                ProcedureName::try_from(name.to_string()).is_ok()
+            );
+        }
+    }
+
+    #[test]
+    fn test_callset_retain() {
+        let proc_ids = (0..6).map(|i| ProcedureId::from([i; ProcedureId::SIZE]));
+
+        let mut callset = CallSet::default();
+        proc_ids.clone().for_each(|id| callset.insert(id));
+        callset.retain(|id| id[0] % 2 == 0);
+
+        let mut expected = CallSet::default();
+        proc_ids.filter(|id| id[0] % 2 == 0).for_each(|id| expected.insert(id));
+        assert_eq!(callset, expected);
+        assert_eq!(callset.len(), 3);
+        assert!(callset.iter().all(|id| id[0] % 2 == 0));
+
+        callset.retain(|_| false);
+        assert!(callset.is_empty());
+    }
+
+    #[test]
+    fn test_callset_intersection_and_difference() {
+        let foo = ProcedureId::new("foo::foo");
+        let bar = ProcedureId::new("foo::bar");
+        let baz = ProcedureId::new("foo::baz");
+        let qux = ProcedureId::new("foo::qux");
+
+        let mut a = CallSet::default();
+        [foo, bar, baz].into_iter().for_each(|id| a.insert(id));
+        let mut b = CallSet::default();
+        [bar, baz, qux].into_iter().for_each(|id| b.insert(id));
+
+        // overlapping sets
+        let mut expected = CallSet::default();
+        expected.insert(bar);
+        expected.insert(baz);
+        assert_eq!(a.intersection(&b), expected);
+        assert_eq!(b.intersection(&a), expected);
+        assert!(a.intersection(&b).iter().eq(a.iter().filter(|id| b.contains(id))));
+
+        let mut expected = CallSet::default();
+        expected.insert(foo);
+        assert_eq!(a.difference(&b), expected);
+
+        let mut expected = CallSet::default();
+        expected.insert(qux);
+        assert_eq!(b.difference(&a), expected);
+
+        // disjoint sets
+        let mut c = CallSet::default();
+        c.insert(qux);
+        assert!(a.intersection(&c).is_empty());
+        assert_eq!(a.difference(&c), a);
+    }
+
+    #[test]
+    fn test_procedure_name_to_id() {
+        let module_path = LibraryPath::new("std::math::u64").unwrap();
+        let name = ProcedureName::try_from("checked_add".to_string()).unwrap();
+        assert_eq!(name.to_id(&module_path), ProcedureId::from_name("checked_add", &module_path));
+        assert_eq!(name.to_id(&module_path), ProcedureId::new("std::math::u64::checked_add"));
+    }
+
+    #[test]
+    fn test_procedure_id_format_with_path() {
+        let path = LibraryPath::new("std::math::u64::add").unwrap();
+        let mut bytes = [0u8; ProcedureId::SIZE];
+        bytes[..3].copy_from_slice(&[0x1a, 0x2b, 0x3c]);
+        let id = ProcedureId::from(bytes);
+        assert_eq!(id.format_with_path(&path), "std::math::u64::add (0x1a2b…)");
+
+        let id = ProcedureId::new(path.as_str());
+        let expected = format!("std::math::u64::add ({}…)", &id.to_string()[..6]);
+        assert_eq!(id.format_with_path(&path), expected);
+    }
+
+    #[test]
+    fn test_callset_len() {
+        let mut callset = CallSet::default();
+        assert_eq!(callset.len(), 0);
+        assert!(callset.is_empty());
+
+        callset.insert(ProcedureId::new("foo::foo"));
+        callset.insert(ProcedureId::new("foo::bar"));
+        assert_eq!(callset.len(), 2);
+        assert!(!callset.is_empty());
+    }
+
+    #[test]
+    fn test_callset_resolve() {
+        let foo = ProcedureId::new("foo::foo");
+        let bar = ProcedureId::new("foo::bar");
+        let name_to_id = BTreeMap::from([("foo".to_string(), foo), ("bar".to_string(), bar)]);
+
+        // all callees resolved; duplicate callees are collapsed
+        let callees = ["foo".to_string(), "bar".to_string(), "foo".to_string()];
+        let mut expected = CallSet::default();
+        expected.insert(foo);
+        expected.insert(bar);
+        assert_eq!(CallSet::resolve(&callees, &name_to_id), Ok(expected));
+        assert_eq!(CallSet::resolve(&[], &name_to_id), Ok(CallSet::default()));
+
+        // unresolved callee
+        let callees = ["foo".to_string(), "baz".to_string()];
+        assert_eq!(CallSet::resolve(&callees, &name_to_id), Err("baz".to_string()));
+    }
+
+    #[test]
+    fn test_procedure_id_from_str() {
+        // round trip through the display representation
+        let id = ProcedureId::new("std::math::u64::checked_add");
+        assert_eq!(id, ProcedureId::from_str(&id.to_string()).unwrap());
+
+        // the `0x` prefix is optional and parsing is case-insensitive
+        let hex = "0a0b0c0d0e0f000102030405060708090a0b0c0d";
+        let id = ProcedureId::from_str(hex).unwrap();
+        assert_eq!(id[..3], [0x0a, 0x0b, 0x0c]);
+        assert_eq!(id, ProcedureId::from_str(&format!("0x{hex}")).unwrap());
+        assert_eq!(id, ProcedureId::from_str(&hex.to_uppercase()).unwrap());
+
+        // too short
+        assert!(ProcedureId::from_str(&hex[..38]).is_err());
+        assert!(ProcedureId::from_str("0x").is_err());
+
+        // too long
+        assert!(ProcedureId::from_str(&format!("0x{hex}00")).is_err());
+
+        // non-hex characters
+        assert!(ProcedureId::from_str(&format!("0x{}zz", &hex[..38])).is_err());
+        assert!(ProcedureId::from_str(&format!("{}é", &hex[..38])).is_err());
+    }
+
+    #[test]
+    fn test_procedure_id_min_distance() {
+        let zero = ProcedureId([0; ProcedureId::SIZE]);
+        let mut one_bit = zero;
+        one_bit.0[5] = 0b1000;
+        let mut three_bits = zero;
+        three_bits.0[0] = 0b111;
+        let ones = ProcedureId([0xff; ProcedureId::SIZE]);
+
+        assert_eq!(zero.distance(&ones), 160);
+        assert_eq!(one_bit.distance(&three_bits), 4);
+        assert_eq!(ProcedureId::min_id_distance(&[zero, ones, three_bits]), Some(3));
+        assert_eq!(ProcedureId::min_id_distance(&[ones, three_bits, one_bit, zero]), Some(1));
+
+        // identical IDs collide
+        assert_eq!(ProcedureId::min_id_distance(&[ones, zero, ones]), Some(0));
+
+        // at least two IDs are needed
+        assert_eq!(ProcedureId::min_id_distance(&[zero]), None);
+        assert_eq!(ProcedureId::min_id_distance(&[]), None);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_callset_backings_agree() {
+        // pseudo-random procedure IDs derived from hashes of their indices
+        let ids = (0..10_000).map(|i| ProcedureId::new(i.to_string())).collect::<Vec<_>>();
+
+        let mut ordered = CallSet::default();
+        let mut hashed = CallSet::with_hash_backing();
+        let mut other_ordered = CallSet::default();
+        let mut other_hashed = CallSet::with_hash_backing();
+        for (i, &id) in ids.iter().enumerate() {
+            // insert some of the IDs multiple times
+            if i % 3 != 0 {
+                ordered.insert(id);
+                hashed.insert(id);
+                hashed.insert(id);
+            }
+            if i % 5 == 0 {
+                other_ordered.insert(id);
+                other_hashed.insert(id);
+            }
+        }
+        assert_eq!(ordered.len(), hashed.len());
+        assert_eq!(ordered, hashed);
+        assert!(ids.iter().all(|id| ordered.contains(id) == hashed.contains(id)));
+
+        let intersection = ordered.intersection(&other_hashed);
+        assert_eq!(intersection, hashed.intersection(&other_ordered));
+        assert_eq!(intersection.len(), (0..10_000).filter(|i| i % 3 != 0 && i % 5 == 0).count());
+
+        let difference = ordered.difference(&other_hashed);
+        assert_eq!(difference, hashed.difference(&other_ordered));
+        assert_eq!(difference.len(), (0..10_000).filter(|i| i % 3 != 0 && i % 5 != 0).count());
+
+        ordered.append(&other_hashed);
+        hashed.append(&other_ordered);
+        assert_eq!(ordered, hashed);
+        assert_eq!(ordered.len(), (0..10_000).filter(|i| i % 3 != 0 || i % 5 == 0).count());
+        assert_ne!(ordered, CallSet::default());
+
+        // both backings are iterated in ascending order
+        assert!(ordered.iter().eq(hashed.iter()));
+        assert!(hashed.iter().zip(hashed.iter().skip(1)).all(|(a, b)| a < b));
+    }
+
+    #[test]
+    fn test_callset_reachable_roots() {
+        // build a call chain a -> b -> c, where c also calls back into a
+        let new_proc = |name: &str, value: u32, callees: &[&Procedure]| {
+            let code = CodeBlock::new_span(vec![Operation::Push(value.into()), Operation::Drop]);
+            let mut callset = CallSet::default();
+            callees.iter().for_each(|callee| callset.insert(*callee.id()));
+            let label = ProcedureName::try_from(name.to_string()).unwrap();
+            Procedure::new(ProcedureId::new(name), label, false, 0, code, callset)
+        };
+        let c = new_proc("c", 3, &[]);
+        let b = new_proc("b", 2, &[&c]);
+        let a = new_proc("a", 1, &[&b]);
+        let unreachable = new_proc("d", 4, &[&a]);
+        let mut c = c;
+        c.callset.insert(*a.id());
+
+        let procedures = vec![a.clone(), b.clone(), c.clone(), unreachable];
+        let mut entry = CallSet::default();
+        entry.insert(*a.id());
+
+        let expected = [&a, &b, &c].iter().map(|proc| proc.code_root().hash()).collect();
+        assert_eq!(entry.reachable_roots(&procedures), expected);
+
+        // unknown procedures are skipped
+        entry.insert(ProcedureId::new("unknown"));
+        assert_eq!(entry.reachable_roots(&procedures), expected);
+        assert!(CallSet::default().reachable_roots(&procedures).is_empty());
+    }
+
+    #[test]
+    fn test_procedure_name_matches() {
+        let main = ProcedureName::main();
+        let main_label = ProcedureName::try_from("main".to_string()).unwrap();
+        let foo = ProcedureName::try_from("foo".to_string()).unwrap();
+
+        assert!(main.matches("#main"));
+        assert!(main.matches("main"));
+        assert!(main_label.matches("#main"));
+        assert!(main_label.matches("main"));
+        assert!(!main.matches("foo"));
+        assert!(!main.matches("mainly"));
+        assert!(!foo.matches("#main"));
+        assert!(foo.matches("foo"));
+
+        // equality still distinguishes the two names
+        assert_ne!(main, main_label);
+    }
+
+    #[test]
+    fn test_procedure_id_try_from_bytes() {
+        let id = ProcedureId::new("std::math::u64::add");
+        assert_eq!(ProcedureId::try_from_bytes(&id.0), Ok(id));
+
+        // too short
+        let err = ProcedureId::try_from_bytes(&id.0[..ProcedureId::SIZE - 1]).unwrap_err();
+        let expected = "procedure id must consist of 20 bytes, but was 19";
+        assert_eq!(err, DeserializationError::InvalidValue(expected.to_string()));
+        assert!(ProcedureId::try_from_bytes(&[]).is_err());
+
+        // too long
+        let bytes = [0u8; ProcedureId::SIZE + 1];
+        let err = ProcedureId::try_from_bytes(&bytes).unwrap_err();
+        let expected = "procedure id must consist of 20 bytes, but was 21";
+        assert_eq!(err, DeserializationError::InvalidValue(expected.to_string()));
+    }
+
+    #[test]
+    fn test_procedure_serde_with_callset() {
+        let code = CodeBlock::new_span(vec![Operation::Push(1u32.into()), Operation::Add]);
+        let name = ProcedureName::try_from("foo".to_string()).unwrap();
+        let mut callset = CallSet::default();
+        callset.insert(ProcedureId::new("bar"));
+        callset.insert(ProcedureId::new("baz"));
+        let proc =
+            Procedure::new(ProcedureId::new("foo"), name.clone(), true, 2, code.clone(), callset);
+
+        let bytes = proc.to_bytes();
+        let deserialized = Procedure::read_from(&mut SliceReader::new(&bytes)).unwrap();
+        assert_eq!(deserialized, proc);
+        assert_eq!(deserialized.id(), proc.id());
+        assert_eq!(deserialized.label(), &name);
+        assert!(deserialized.is_export());
+        assert_eq!(deserialized.num_locals(), 2);
+        assert_eq!(deserialized.code_root().hash(), code.hash());
+        assert_eq!(deserialized.callset().len(), 2);
+        assert!(deserialized.callset().contains(&ProcedureId::new("bar")));
+        assert!(deserialized.callset().contains(&ProcedureId::new("baz")));
+    }
+
+    #[test]
+    fn test_detect_id_collisions() {
+        let path = LibraryPath::new("std::math::u64").unwrap();
+        let name = |name: &str| ProcedureName::try_from(name.to_string()).unwrap();
+        let id = |name: &str| ProcedureId::from_name(name, &path);
+        let new_proc = |label: &str, id: ProcedureId| {
+            let code = CodeBlock::new_span(vec![Operation::Noop]);
+            Procedure::new(id, name(label), true, 0, code, CallSet::default())
+        };
+
+        // distinct procedures with distinct IDs, and the same procedure listed twice
+        let mut procs = vec![
+            new_proc("add", id("add")),
+            new_proc("sub", id("sub")),
+            new_proc("add", id("add")),
+        ];
+        assert!(detect_id_collisions(&procs).is_empty());
+
+        // procedures forced to share an ID
+        let forced = ProcedureId([7; ProcedureId::SIZE]);
+        procs.push(new_proc("mul", forced));
+        procs.push(new_proc("div", forced));
+        procs.push(new_proc("mul", forced));
+        procs.push(new_proc("neg", id("sub")));
+        let mut expected = vec![
+            (id("sub"), vec![name("sub"), name("neg")]),
+            (forced, vec![name("mul"), name("div")]),
+        ];
+        // collisions are ordered by their IDs
+        expected.sort_by_key(|(id, _)| *id);
+        assert_eq!(detect_id_collisions(&procs), expected);
+
+        // procedures can also be passed as an iterator, e.g., over the values of a map
+        let by_id = procs.iter().map(|proc| (*proc.id(), proc)).collect::<BTreeMap<_, _>>();
+        assert!(detect_id_collisions(by_id.values().copied()).is_empty());
+    }
 }
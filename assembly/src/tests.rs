@@ -193,6 +193,36 @@ fn multiple_constants_push() {
     assert_eq!(expected, format!("{program}"));
 }
 
+#[test]
+fn constants_push_word() {
+    let assembler = super::Assembler::default();
+    let source = "const.A=1 \
+    begin \
+    push.A.2.3.4 \
+    end";
+    let expected = "\
+    begin \
+        span \
+            pad incr push(2) push(3) push(4) \
+        end \
+    end";
+    let program = assembler.compile(source).unwrap();
+    assert_eq!(expected, format!("{program}"));
+
+    // each element is range-checked independently
+    let source = "const.A=1 \
+    begin \
+    push.A.2.18446744069414584321.4 \
+    end";
+    let result = assembler.compile(source);
+    assert!(result.is_err());
+    let err = result.err().unwrap();
+    let expected_error = "malformed instruction 'push.A.2.18446744069414584321.4', parameter \
+    18446744069414584321 is invalid: parameter value must be greater than or equal to 0 and less \
+    than or equal to 18446744069414584320";
+    assert_eq!(expected_error, err.to_string());
+}
+
 #[test]
 fn constants_must_be_uppercase() {
     let assembler = super::Assembler::default();
@@ -247,9 +277,111 @@ fn constant_must_be_within_valid_felt_range() {
     let result = assembler.compile(source);
     assert!(result.is_err());
     let err = result.err().unwrap();
-    let expected_error = "malformed constant `const.CONSTANT=18446744073709551615` - invalid value: \
-     `18446744073709551615` - reason: constant value must be greater than or equal to 0 and less than or \
-      equal to 18446744069414584320";
+    let expected_error = "malformed constant `const.CONSTANT=18446744073709551615` - value \
+     `18446744073709551615` is too large: constant value must be less than or equal to \
+      18446744069414584320";
+    assert_eq!(expected_error, err.to_string());
+}
+
+#[test]
+fn constant_must_not_overflow_u64() {
+    let assembler = super::Assembler::default();
+    let source = "const.CONSTANT=18446744073709551616 \
+    begin \
+    push.CONSTANT \
+    end";
+    let result = assembler.compile(source);
+    assert!(result.is_err());
+    let err = result.err().unwrap();
+    let expected_error = "malformed constant `const.CONSTANT=18446744073709551616` - value \
+     `18446744073709551616` is too large: constant value must be less than or equal to \
+      18446744069414584320";
+    assert_eq!(expected_error, err.to_string());
+}
+
+#[test]
+fn constants_hex_and_binary() {
+    let assembler = super::Assembler::default();
+    let source = "const.MASK=0xFF \
+    const.FLAGS=0b1010 \
+    const.UPPER=0XfF \
+    const.LOWER=0B11 \
+    begin \
+    push.MASK.FLAGS.UPPER.LOWER \
+    end";
+    let expected = "\
+    begin \
+        span \
+            push(255) push(10) push(255) push(3) \
+        end \
+    end";
+    let program = assembler.compile(source).unwrap();
+    assert_eq!(expected, format!("{program}"));
+}
+
+#[test]
+fn constant_hex_must_be_within_valid_felt_range() {
+    let assembler = super::Assembler::default();
+    let source = "const.CONSTANT=0xFFFFFFFF00000001 \
+    begin \
+    push.CONSTANT \
+    end";
+    let result = assembler.compile(source);
+    assert!(result.is_err());
+    let err = result.err().unwrap();
+    let expected_error = "malformed constant `const.CONSTANT=0xFFFFFFFF00000001` - value \
+     `0xFFFFFFFF00000001` is too large: constant value must be less than or equal to \
+      18446744069414584320";
+    assert_eq!(expected_error, err.to_string());
+}
+
+#[test]
+fn constant_hex_must_be_valid() {
+    let assembler = super::Assembler::default();
+    let source = "const.CONSTANT=0xZZ \
+    begin \
+    push.CONSTANT \
+    end";
+    let result = assembler.compile(source);
+    assert!(result.is_err());
+    let err = result.err().unwrap();
+    let expected_error = "malformed constant `const.CONSTANT=0xZZ` - invalid value: \
+     `0xZZ` - reason: invalid digit found in string";
+    assert_eq!(expected_error, err.to_string());
+}
+
+#[test]
+fn constant_expressions() {
+    let assembler = super::Assembler::default();
+    let source = "const.X=5 \
+    const.Y=X+1 \
+    const.Z=X*2-3 \
+    const.W=0-1 \
+    begin \
+    push.Y.Z.W \
+    end";
+    let expected = "\
+    begin \
+        span \
+            push(6) push(7) push(18446744069414584320) \
+        end \
+    end";
+    let program = assembler.compile(source).unwrap();
+    assert_eq!(expected, format!("{program}"));
+}
+
+#[test]
+fn constant_expression_undefined_name() {
+    let assembler = super::Assembler::default();
+    let source = "const.X=5 \
+    const.Y=X+UNDEFINED \
+    begin \
+    push.Y \
+    end";
+    let result = assembler.compile(source);
+    assert!(result.is_err());
+    let err = result.err().unwrap();
+    let expected_error = "constant used in operation `const.Y=X+UNDEFINED` not found";
     assert_eq!(expected_error, err.to_string());
 }
 
@@ -598,6 +730,82 @@ fn program_with_mast_root_call_that_does_not_exist() {
     assert_eq!(expected_error, err.to_string());
 }
 
+#[test]
+fn program_with_mast_root_exec() {
+    const NAMESPACE: &str = "dummy";
+    const MODULE: &str = "math::u256";
+    const PROCEDURE: &str = r#"
+        export.iszero_unsafe
+            eq.0
+            repeat.7
+                swap
+                eq.0
+                and
+            end
+        end"#;
+
+    let namespace = LibraryNamespace::try_from(NAMESPACE.to_string()).unwrap();
+    let path = LibraryPath::try_from(MODULE.to_string()).unwrap().prepend(&namespace).unwrap();
+    let ast = ModuleAst::parse(PROCEDURE).unwrap();
+    let modules = vec![Module { path, ast }];
+    let library = DummyLibrary::new(namespace, modules);
+    let assembler = super::Assembler::default().with_library(&library).unwrap();
+
+    // executing a procedure by its MAST root inlines it in the same way as executing it by name;
+    // the procedure is loaded into the procedure cache by the first `exec`
+    let by_name = format!(
+        "use.{NAMESPACE}::{MODULE} begin exec.u256::iszero_unsafe exec.u256::iszero_unsafe end"
+    );
+    let by_root = format!(
+        "use.{NAMESPACE}::{MODULE} begin exec.u256::iszero_unsafe \
+        exec.0xc2545da99d3a1f3f38d957c7893c44d78998d8ea8b11aba7e22c8c2b2a213dae end"
+    );
+    let by_name = assembler.compile(by_name).unwrap();
+    let by_root = assembler.compile(by_root).unwrap();
+    assert_eq!(by_name.hash(), by_root.hash());
+
+    // the procedure must be in the procedure cache
+    let source =
+        "begin exec.0xc2545da99d3a1f3f38d957c7893c44d78998d8ea8b11aba7e22c8c2b2a213dae end";
+    let err = super::Assembler::default().compile(source).unwrap_err();
+    let expected_error = "procedure mast root not found for digest - 0xc2545da99d3a1f3f38d957c7893c44d78998d8ea8b11aba7e22c8c2b2a213dae";
+    assert_eq!(expected_error, err.to_string());
+}
+
+#[test]
+fn program_with_inlined_module_procs() {
+    const NAMESPACE: &str = "dummy";
+    const MODULE: &str = "math::inc";
+    const MODULE_BODY: &str = r#"
+        proc.inc
+            push.1
+            add
+        end
+
+        export.increment
+            exec.inc
+        end"#;
+
+    let namespace = LibraryNamespace::try_from(NAMESPACE.to_string()).unwrap();
+    let path = LibraryPath::try_from(MODULE.to_string()).unwrap().prepend(&namespace).unwrap();
+    let ast = ModuleAst::parse(MODULE_BODY).unwrap();
+    let inlined_ast = ast.clone().inline_small_procs(2);
+    assert_eq!(inlined_ast.procs().len(), 1);
+
+    // inlining must not change the MAST of the program
+    let source = format!("use.{NAMESPACE}::{MODULE} begin exec.inc::increment end");
+    let compile = |ast| {
+        let modules = vec![Module {
+            path: path.clone(),
+            ast,
+        }];
+        let library = DummyLibrary::new(namespace.clone(), modules);
+        let assembler = super::Assembler::default().with_library(&library).unwrap();
+        assembler.compile(&source).unwrap()
+    };
+    assert_eq!(compile(ast).hash(), compile(inlined_ast).hash());
+}
+
 // IMPORTS
 // ================================================================================================
 
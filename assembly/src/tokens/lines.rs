@@ -11,6 +11,8 @@ pub struct LinesStream<'a> {
     current_line: Option<&'a str>,
     current_line_num: u32,
     line_char_offset: u32,
+    line_indentation: &'a str,
+    indentation_char: Option<char>,
 }
 
 impl<'a> From<&'a str> for LinesStream<'a> {
@@ -20,6 +22,8 @@ impl<'a> From<&'a str> for LinesStream<'a> {
             current_line: None,
             current_line_num: 0,
             line_char_offset: 0,
+            line_indentation: "",
+            indentation_char: None,
         }
     }
 }
@@ -54,10 +58,28 @@ impl<'a> LinesStream<'a> {
 
             self.current_line.replace(trimmed);
             self.line_char_offset = (init_len - trimmed.len()) as u32;
+            self.line_indentation = &line[..init_len - trimmed.len()];
             self.current_line_num += 1;
         }
     }
 
+    /// Returns true if the indentation of the current line mixes tabs and spaces, either within
+    /// the line itself or with respect to the indentation of the previous token lines.
+    ///
+    /// The first indented token line determines the indentation character for the rest of the
+    /// source.
+    fn check_mixed_indentation(&mut self) -> bool {
+        let mut indentation = self.line_indentation.chars().filter(|c| *c == ' ' || *c == '\t');
+        let first = match self.indentation_char {
+            Some(c) => c,
+            None => match indentation.next() {
+                Some(c) => *self.indentation_char.insert(c),
+                None => return false,
+            },
+        };
+        indentation.any(|c| c != first)
+    }
+
     /// If the current line is a doc comment, take lines until EOF or not doc comment.
     fn take_docs_block(&mut self) -> Vec<&'a str> {
         iter::from_fn(|| {
@@ -112,7 +134,8 @@ impl<'a> Iterator for LinesStream<'a> {
             Some(line) => Some(
                 LineInfo::new(self.current_line_num, self.line_char_offset)
                     .with_contents(line)
-                    .with_docs(docs),
+                    .with_docs(docs)
+                    .with_mixed_indentation(self.check_mixed_indentation()),
             ),
             None => {
                 debug_assert!(false, "this is unreachable; these is a bug in `Self::is_token`");
@@ -132,6 +155,7 @@ pub struct LineInfo<'a> {
     docs: Vec<&'a str>,
     line_number: u32,
     char_offset: u32,
+    mixed_indentation: bool,
 }
 
 impl From<LineInfo<'_>> for SourceLocation {
@@ -154,6 +178,7 @@ impl<'a> LineInfo<'a> {
             docs: Vec::new(),
             line_number,
             char_offset,
+            mixed_indentation: false,
         }
     }
 
@@ -172,6 +197,12 @@ impl<'a> LineInfo<'a> {
         self
     }
 
+    /// Sets whether the indentation of this line mixes tabs and spaces.
+    pub fn with_mixed_indentation(mut self, mixed_indentation: bool) -> Self {
+        self.mixed_indentation = mixed_indentation;
+        self
+    }
+
     // PUBLIC ACCESSORS
     // --------------------------------------------------------------------------------------------
 
@@ -237,6 +268,12 @@ impl<'a> LineInfo<'a> {
     pub const fn char_offset(&self) -> u32 {
         self.char_offset
     }
+
+    /// Returns true if the indentation of this line mixes tabs and spaces, either within the
+    /// line itself or with respect to the indentation of the preceding lines.
+    pub const fn has_mixed_indentation(&self) -> bool {
+        self.mixed_indentation
+    }
 }
 
 #[cfg(test)]
@@ -507,6 +544,18 @@ end
         assert_eq!(None, lines.next());
     }
 
+    #[test]
+    fn token_lines_mixed_indentation() {
+        let source = "begin\n    push.1\n\tpush.2\n  \tadd\nend";
+        let mut lines = LinesStream::from(source);
+        assert_eq!(t(1, 0, "begin"), lines.next());
+        assert_eq!(t(2, 4, "push.1"), lines.next());
+        assert!(lines.next().unwrap().has_mixed_indentation());
+        assert!(lines.next().unwrap().has_mixed_indentation());
+        assert_eq!(t(5, 0, "end"), lines.next());
+        assert_eq!(None, lines.next());
+    }
+
     // TESTS HELPERS
     // ============================================================================================
 
@@ -35,6 +35,11 @@ impl SourceLocation {
         self.line
     }
 
+    /// Returns the column of the location.
+    pub const fn column(&self) -> u32 {
+        self.column
+    }
+
     // STATE MUTATORS
     // -------------------------------------------------------------------------------------------------
 
@@ -64,3 +69,50 @@ impl Deserializable for SourceLocation {
         Ok(Self { line, column })
     }
 }
+
+// SOURCE SPAN
+// ================================================================================================
+
+/// A struct containing information about the range of source occupied by a source item.
+///
+/// The `start` location points at the first character of the item, and the `end` location points
+/// immediately past its last character.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourceSpan {
+    start: SourceLocation,
+    end: SourceLocation,
+}
+
+impl SourceSpan {
+    // CONSTRUCTORS
+    // -------------------------------------------------------------------------------------------------
+
+    /// Creates a new instance of [SourceSpan].
+    pub const fn new(start: SourceLocation, end: SourceLocation) -> Self {
+        Self { start, end }
+    }
+
+    // PUBLIC ACCESSORS
+    // -------------------------------------------------------------------------------------------------
+
+    /// Returns the location of the first character of the span.
+    pub const fn start(&self) -> SourceLocation {
+        self.start
+    }
+
+    /// Returns the location immediately past the last character of the span.
+    pub const fn end(&self) -> SourceLocation {
+        self.end
+    }
+
+    /// Returns true if the provided location falls within this span.
+    pub fn contains(&self, location: &SourceLocation) -> bool {
+        self.start <= *location && *location < self.end
+    }
+}
+
+impl fmt::Display for SourceSpan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
@@ -1,6 +1,7 @@
 use super::{
-    ast::InvocationTarget, BTreeMap, ByteReader, ByteWriter, Deserializable, DeserializationError,
-    LibraryPath, ParsingError, ProcedureName, Serializable, String, ToString, Vec,
+    ast::{InvocationTarget, ProcedureFlags},
+    BTreeMap, ByteReader, ByteWriter, Deserializable, DeserializationError, LibraryPath,
+    ParsingError, ProcedureName, Serializable, String, ToString, Vec,
 };
 use core::fmt;
 
@@ -8,7 +9,7 @@ mod lines;
 pub use lines::{LineInfo, LinesStream};
 
 mod location;
-pub use location::SourceLocation;
+pub use location::{SourceLocation, SourceSpan};
 
 mod stream;
 pub use stream::TokenStream;
@@ -50,6 +51,18 @@ impl<'a> Token<'a> {
     pub const SYSCALL: &'static str = "syscall";
     pub const WHILE: &'static str = "while";
 
+    // PROCEDURE ATTRIBUTES
+    // --------------------------------------------------------------------------------------------
+    pub const ATTRIBUTE_PREFIX: &'static str = "@[";
+    pub const ATTRIBUTE_SUFFIX: &'static str = "]";
+    pub const BUDGET: &'static str = "budget";
+    pub const INLINE: &'static str = "inline";
+    pub const NOINLINE: &'static str = "noinline";
+
+    // CONDITIONAL COMPILATION
+    // --------------------------------------------------------------------------------------------
+    pub const CFG: &'static str = "@cfg";
+
     // DELIMITERS
     // --------------------------------------------------------------------------------------------
     pub const DOC_COMMENT_PREFIX: &str = "#!";
@@ -78,6 +91,18 @@ impl<'a> Token<'a> {
         &self.location
     }
 
+    /// Returns the [SourceLocation] immediately following the last character of this [Token].
+    ///
+    /// Tokens never span multiple lines, and thus the end location is on the same line as the
+    /// start location.
+    pub fn end_location(&self) -> SourceLocation {
+        let num_chars = self.parts.iter().map(|part| part.chars().count()).sum::<usize>()
+            + self.parts.len().saturating_sub(1);
+        let mut location = self.location;
+        location.move_column(num_chars as u32);
+        location
+    }
+
     /// Returns the number of parts in this token.
     pub fn num_parts(&self) -> usize {
         self.parts.len()
@@ -114,6 +139,47 @@ impl<'a> Token<'a> {
         }
     }
 
+    /// Returns true if this token is a constant import (e.g., `use.const.std::math::consts`).
+    pub fn is_const_use(&self) -> bool {
+        self.parts[0] == Self::USE && self.parts.get(1) == Some(&Self::CONST)
+    }
+
+    /// Parses a constant import of the form `use.const.<module path>` and returns the path of
+    /// the module from which the constants are imported.
+    pub fn parse_const_use(&self) -> Result<LibraryPath, ParsingError> {
+        assert!(self.is_const_use(), "not a constant import");
+        match self.num_parts() {
+            0 | 1 => unreachable!(),
+            2 => Err(ParsingError::missing_param(self)),
+            3 => validate_import_path(self.parts[2], self),
+            _ => Err(ParsingError::extra_param(self)),
+        }
+    }
+
+    /// Parses a conditional compilation directive of the form `@cfg.<feature>` and returns the
+    /// name of the feature.
+    ///
+    /// Feature names must be non-empty and consist of only ASCII letters, numbers, underscores,
+    /// and dashes.
+    pub fn parse_cfg(&self) -> Result<&str, ParsingError> {
+        assert_eq!(Self::CFG, self.parts[0], "not a cfg directive");
+        match self.num_parts() {
+            0 => unreachable!(),
+            1 => Err(ParsingError::missing_param(self)),
+            2 => {
+                let feature = self.parts[1];
+                let is_valid = !feature.is_empty()
+                    && feature.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                if is_valid {
+                    Ok(feature)
+                } else {
+                    Err(ParsingError::invalid_cfg_feature(self, feature))
+                }
+            }
+            _ => Err(ParsingError::extra_param(self)),
+        }
+    }
+
     pub fn validate_begin(&self) -> Result<(), ParsingError> {
         assert_eq!(Self::BEGIN, self.parts[0], "not a begin");
         if self.num_parts() > 1 {
@@ -161,10 +227,25 @@ impl<'a> Token<'a> {
                     .split_once(LibraryPath::PATH_DELIM)
                     .expect("Invalid procedure export {self.parts[1]}");
 
-                // get the alias name if it exists else export it with the original name
-                let (ref_name, proc_name) = proc_name_with_alias
-                    .split_once(Self::EXPORT_ALIAS_DELIM)
-                    .unwrap_or((proc_name_with_alias, proc_name_with_alias));
+                // get the alias name if it exists else export it with the original name; the
+                // alias can either follow the re-exported procedure (`export.foo::bar->baz`) or
+                // precede its module (`export.baz->foo::bar`)
+                let (module, ref_name, proc_name) =
+                    match module.split_once(Self::EXPORT_ALIAS_DELIM) {
+                        Some(_) if proc_name_with_alias.contains(Self::EXPORT_ALIAS_DELIM) => {
+                            return Err(ParsingError::invalid_reexported_procedure(
+                                self,
+                                self.parts[1],
+                            ));
+                        }
+                        Some((alias, module)) => (module, proc_name_with_alias, alias),
+                        None => {
+                            let (ref_name, proc_name) = proc_name_with_alias
+                                .split_once(Self::EXPORT_ALIAS_DELIM)
+                                .unwrap_or((proc_name_with_alias, proc_name_with_alias));
+                            (module, ref_name, proc_name)
+                        }
+                    };
 
                 // validate the procedure names
                 let ref_name = ProcedureName::try_from(ref_name.to_string())
@@ -178,6 +259,54 @@ impl<'a> Token<'a> {
         }
     }
 
+    /// Returns true if this token is a procedure attribute (e.g., `@[budget=5000]`).
+    pub fn is_proc_attribute(&self) -> bool {
+        self.parts[0].starts_with(Self::ATTRIBUTE_PREFIX)
+    }
+
+    /// Returns the flag specified by this procedure attribute if it is a flag attribute (i.e.,
+    /// `@[inline]` or `@[noinline]`), or None otherwise.
+    pub fn parse_proc_flag(&self) -> Option<ProcedureFlags> {
+        assert!(self.is_proc_attribute(), "not a procedure attribute");
+        let name = self.parts[0]
+            .strip_prefix(Self::ATTRIBUTE_PREFIX)
+            .and_then(|attr| attr.strip_suffix(Self::ATTRIBUTE_SUFFIX));
+        match name {
+            _ if self.num_parts() > 1 => None,
+            Some(Self::INLINE) => Some(ProcedureFlags::INLINE),
+            Some(Self::NOINLINE) => Some(ProcedureFlags::NOINLINE),
+            _ => None,
+        }
+    }
+
+    /// Parses a procedure budget attribute of the form `@[budget=<value>]` and returns the
+    /// budget value.
+    ///
+    /// The budget must be a positive 32-bit integer.
+    pub fn parse_proc_budget(&self) -> Result<u32, ParsingError> {
+        assert!(self.is_proc_attribute(), "not a procedure attribute");
+        if self.num_parts() > 1 {
+            return Err(ParsingError::invalid_proc_attribute(self, "unexpected '.'"));
+        }
+        let (name, value) = self.parts[0]
+            .strip_prefix(Self::ATTRIBUTE_PREFIX)
+            .and_then(|attr| attr.strip_suffix(Self::ATTRIBUTE_SUFFIX))
+            .and_then(|attr| attr.split_once('='))
+            .ok_or_else(|| {
+                ParsingError::invalid_proc_attribute(self, "expected the form @[name=value]")
+            })?;
+        if name != Self::BUDGET {
+            return Err(ParsingError::invalid_proc_attribute(self, "unknown attribute"));
+        }
+        match value.parse::<u32>() {
+            Ok(budget) if budget > 0 => Ok(budget),
+            _ => Err(ParsingError::invalid_proc_attribute(
+                self,
+                "budget must be a positive 32-bit integer",
+            )),
+        }
+    }
+
     pub fn validate_if(&self) -> Result<(), ParsingError> {
         assert_eq!(Self::IF, self.parts[0], "not an if");
         match self.num_parts() {
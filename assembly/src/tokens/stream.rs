@@ -15,6 +15,7 @@ pub struct TokenStream<'a> {
     temp: Token<'a>,
     proc_comments: BTreeMap<usize, Option<String>>,
     module_comment: Option<String>,
+    mixed_indentation: Vec<SourceLocation>,
 }
 
 impl<'a> TokenStream<'a> {
@@ -27,12 +28,23 @@ impl<'a> TokenStream<'a> {
         let mut locations = Vec::new();
         let mut proc_comments = BTreeMap::new();
         let mut module_comment = None;
+        let mut mixed_indentation = Vec::new();
 
         for line_info in LinesStream::from(source) {
             match line_info.contents() {
                 Some(line) => {
-                    // fill the doc comments for procedures
-                    if line.starts_with(Token::EXPORT) || line.starts_with(Token::PROC) {
+                    // record lines which mix tabs and spaces for indentation
+                    if line_info.has_mixed_indentation() {
+                        mixed_indentation.push(SourceLocation::new(line_info.line_number(), 1));
+                    }
+
+                    // fill the doc comments for procedures and constants; a procedure declaration
+                    // may be preceded by its attributes
+                    if line.starts_with(Token::EXPORT)
+                        || line.starts_with(Token::PROC)
+                        || line.starts_with(Token::ATTRIBUTE_PREFIX)
+                        || line.starts_with(Token::CONST)
+                    {
                         let doc_comment = build_comment(line_info.docs());
                         proc_comments.insert(tokens.len(), doc_comment);
                     } else if !line_info.docs().is_empty() {
@@ -80,6 +92,7 @@ impl<'a> TokenStream<'a> {
             temp: Token::default(),
             proc_comments,
             module_comment,
+            mixed_indentation,
         })
     }
 
@@ -97,6 +110,11 @@ impl<'a> TokenStream<'a> {
         &self.locations[idx]
     }
 
+    /// Returns the locations of the lines whose indentation mixes tabs and spaces.
+    pub fn mixed_indentation(&self) -> &[SourceLocation] {
+        &self.mixed_indentation
+    }
+
     /// Returns 'true' all tokens from this stream have been read.
     pub fn eof(&self) -> bool {
         self.pos == self.tokens.len()